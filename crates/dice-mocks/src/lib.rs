@@ -1,7 +1,12 @@
+use std::{collections::VecDeque, fmt};
+
 use rand::{CryptoRng, RngCore, rand_core};
 
 /// An RNG that will always roll a '1'.
 ///
+/// The faces it rolls depend on how `dice-parser` maps each `u64` to a face,
+/// so a test which needs exact faces should use [`ScriptedFaces`] instead.
+///
 /// See the [`rng`] crate's book: https://rust-random.github.io/book/guide-test-fn-rng.html
 #[derive(Clone, Debug)]
 pub struct MockCryptoRng {
@@ -11,7 +16,7 @@ pub struct MockCryptoRng {
 // impls for Rng //
 impl MockCryptoRng {
     pub fn new(seed: &[u64]) -> Self {
-        assert!(seed.len() > 0);
+        assert!(!seed.is_empty());
         Self {
             data: Vec::from(seed),
             index: 0,
@@ -42,3 +47,138 @@ impl Default for MockCryptoRng {
     }
 }
 // end impls for Rng //
+
+/// Die faces scripted by a test, like "the next d20 is 17, then the d6s are 3, 4".
+///
+/// Every face is checked against the die it's rolled on. When the script is
+/// dropped, it panics if a face was never rolled or a die was rolled after
+/// the last face, unless [`ScriptedFaces::finish`] already reported it.
+///
+/// A `dice-parser` roller rolls them with
+/// `DiceRoller::new(from_fn(|sides| script.face(sides)))`.
+///
+/// ```
+/// use dice_mocks::ScriptedFaces;
+///
+/// let mut script = ScriptedFaces::new().then(20, [17]).then(6, [3, 4]);
+/// assert_eq!(script.face(20), Some(17));
+/// assert_eq!(script.face(6), Some(3));
+///
+/// let error = script.finish().unwrap_err();
+/// assert_eq!(error.unused, [(6, 4)]);
+/// assert_eq!(error.to_string(), "scripted faces were never rolled: 4 on a d6");
+/// ```
+#[derive(Debug, Default)]
+pub struct ScriptedFaces {
+    /// The `(sides, face)` of every die left to roll.
+    script: VecDeque<(i64, i64)>,
+    /// How many faces were rolled so far.
+    rolled: usize,
+    /// The sides of every die rolled after the last face.
+    exhausted: Vec<i64>,
+    finished: bool,
+}
+
+impl ScriptedFaces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scripts `faces` as the next rolls, each on a die with `sides` sides.
+    ///
+    /// # Panics
+    ///
+    /// If a face isn't on the die.
+    pub fn then(mut self, sides: i64, faces: impl IntoIterator<Item = i64>) -> Self {
+        for face in faces {
+            assert!(
+                (1..=sides).contains(&face),
+                "face `{face}` can't be rolled on a d{sides}"
+            );
+            self.script.push_back((sides, face));
+        }
+        self
+    }
+
+    /// Rolls the next face on a die with `sides` sides, or [`None`] after the
+    /// last one, which is reported when the script is finished.
+    ///
+    /// # Panics
+    ///
+    /// If the next face was scripted for a die with different sides.
+    pub fn face(&mut self, sides: i64) -> Option<i64> {
+        let Some(&(expected, face)) = self.script.front() else {
+            self.exhausted.push(sides);
+            return None;
+        };
+        assert_eq!(
+            expected,
+            sides,
+            "roll #{} was scripted for a d{expected}, but rolled a d{sides}",
+            self.rolled + 1
+        );
+        self.script.pop_front();
+        self.rolled += 1;
+        Some(face)
+    }
+
+    /// Checks that every face was rolled, and no die was rolled after the
+    /// last one.
+    pub fn finish(mut self) -> Result<(), ScriptError> {
+        self.finished = true;
+        self.check()
+    }
+
+    fn check(&self) -> Result<(), ScriptError> {
+        if self.script.is_empty() && self.exhausted.is_empty() {
+            return Ok(());
+        }
+        Err(ScriptError {
+            unused: self.script.iter().copied().collect(),
+            exhausted: self.exhausted.clone(),
+        })
+    }
+}
+
+impl Drop for ScriptedFaces {
+    fn drop(&mut self) {
+        // a failed test already panicked, so this would only abort it
+        if self.finished || std::thread::panicking() {
+            return;
+        }
+        if let Err(e) = self.check() {
+            panic!("{e}");
+        }
+    }
+}
+
+/// Why a [`ScriptedFaces`] didn't go as scripted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    /// The `(sides, face)` of every die that was never rolled.
+    pub unused: Vec<(i64, i64)>,
+    /// The sides of every die rolled after the last face.
+    pub exhausted: Vec<i64>,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        if !self.unused.is_empty() {
+            write!(f, "scripted faces were never rolled:")?;
+            for (sides, face) in &self.unused {
+                write!(f, " {face} on a d{sides}")?;
+            }
+            sep = "; ";
+        }
+        if !self.exhausted.is_empty() {
+            write!(f, "{sep}dice were rolled after the script ran out:")?;
+            for sides in &self.exhausted {
+                write!(f, " d{sides}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ScriptError {}
@@ -34,8 +34,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         let to_parse = boxed_big_expr.to_string();
         group.bench_with_input(BenchmarkId::new("parse", depth), &to_parse, |b, p| {
             b.iter(|| {
-                let mut pairs = DiceParser::parse(Rule::equation, &to_parse).unwrap();
-                let ast = try_parse_to_ast(pairs.next().unwrap().into_inner()).unwrap();
+                let mut pairs = DiceParser::parse(Rule::equation, p).unwrap();
+                try_parse_to_ast(pairs.next().unwrap().into_inner()).unwrap();
             });
         });
         group.bench_with_input(
@@ -0,0 +1,40 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use dice_parser::eval::DiceRoller;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("roll");
+    for (count, sides) in [(1000, 6), (1000, 20), (1000, 1000), (100_000, 1000)] {
+        let id = format!("{count}d{sides}");
+
+        group.bench_with_input(
+            BenchmarkId::new("sum", &id),
+            &(count, sides),
+            |b, &(c, s)| {
+                let mut r = DiceRoller::new(StdRng::seed_from_u64(0));
+                b.iter(|| r.roll(c, s).unwrap());
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("faces", &id),
+            &(count, sides),
+            |b, &(c, s)| {
+                let mut r = DiceRoller::new(StdRng::seed_from_u64(0));
+                b.iter(|| r.roll_faces(c, s).unwrap());
+            },
+        );
+        // one `random_range` per die, which is what `roll` used to do
+        group.bench_with_input(
+            BenchmarkId::new("per-die", &id),
+            &(count, sides),
+            |b, &(c, s)| {
+                let mut rng = StdRng::seed_from_u64(0);
+                b.iter(|| (0..c).fold(0i64, |acc, _| acc + rng.random_range(1..=s)));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
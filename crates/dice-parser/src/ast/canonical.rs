@@ -0,0 +1,332 @@
+//! Canonical forms for an [`Expr`], and checks for semantic equivalence.
+//!
+//! Two trees like `2 + 1d6` and `1d6+2` describe the same roll, but
+//! they aren't equal under [`PartialEq`]. [`Expr::canonical`] rewrites a tree
+//! so that expressions which only differ by commutativity or associativity
+//! have the same shape, and [`equivalent`] compares two trees that way.
+//!
+//! # Rewrite rules
+//!
+//! - Chains of `+` and `-` are flattened into a list of terms, and chains of `*`
+//!   are flattened into a list of factors.
+//! - Terms and factors are sorted with [`Ord`], and integer constants are folded
+//!   together. Constants are placed last, so `2 + 1d6` becomes `1d6 + 2`.
+//! - Negations are pushed into sums and pulled out of products and quotients,
+//!   so `-(1d6 - 2)` becomes `-1d6 + 2` and `-1d4 * 3` becomes `-(1d4 * 3)`.
+//! - Division is neither commutative nor associative, so it is left alone.
+//! - A label is kept as an opaque term around its canonical sub-expression.
+//! - So is a list operation, like `4d6s`. If its operand is a sum which would
+//!   become a die term, like `(4d6 + 0)s`, the `+ 0` is kept, since a list
+//!   operation reads the faces of a die term, but not of a sum.
+//!
+//! The rewrites are exact for expressions that evaluate without an [`ArithmeticError`],
+//! but they can move where an overflow happens.
+//!
+//! [`ArithmeticError`]: crate::eval::ArithmeticError
+
+use recursion::{CollapsibleExt as _, Expandable as _};
+
+use super::{Expr, ExprFrame, recurse::WithLabels};
+use crate::eval::distribution::{Distribution, DistributionError};
+
+impl<'s> Expr<'s> {
+    /// Rewrites the expression into its canonical form.
+    ///
+    /// See the [module documentation](self) for the rewrite rules.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::Expr;
+    ///
+    /// // 2 + 1d6
+    /// let lhs = Expr::add(Expr::int(2), Expr::dice(1, 6));
+    /// // 1d6 + 2
+    /// let rhs = Expr::add(Expr::dice(1, 6), Expr::int(2));
+    ///
+    /// assert_ne!(lhs, rhs);
+    /// assert_eq!(lhs.canonical(), rhs.canonical());
+    /// assert_eq!(lhs.canonical().to_string(), "1d6 + 2");
+    /// ```
+    pub fn canonical(&self) -> Expr<'s> {
+        // each canonical sub-expression, and whether it was a pool of faces before
+        let (canonical, _) = WithLabels(self).collapse_frames(|frame| {
+            let pool = match &frame {
+                ExprFrame::Dice(..) | ExprFrame::CritDice(..) | ExprFrame::List(..) => true,
+                ExprFrame::Label((_, pool), _) => *pool,
+                _ => false,
+            };
+            let canonical = match frame {
+                ExprFrame::List((lhs, was_pool), op) if !was_pool && is_pool(&lhs) => {
+                    Expr::list(Expr::add(lhs, Expr::Int(0)), op)
+                }
+                frame => match frame.map(|(e, _)| e) {
+                    ExprFrame::Not(rhs) => negate(rhs),
+                    ExprFrame::Add(lhs, rhs) => sum(terms(lhs).into_iter().chain(terms(rhs))),
+                    ExprFrame::Sub(lhs, rhs) => sum(terms(lhs)
+                        .into_iter()
+                        .chain(terms(rhs).into_iter().map(negate))),
+                    ExprFrame::Mul(lhs, rhs) => product([lhs, rhs]),
+                    ExprFrame::Div(lhs, rhs) => quotient(lhs, rhs),
+                    frame => Expr::from_frame(frame),
+                },
+            };
+            (canonical, pool)
+        });
+        canonical
+    }
+
+    /// Removes every [`Expr::Label`] from the expression.
+    pub fn strip_labels(&self) -> Expr<'s> {
+        WithLabels(self).collapse_frames(|frame| match frame {
+            ExprFrame::Label(lhs, _) => lhs,
+            frame => Expr::from_frame(frame),
+        })
+    }
+}
+
+/// Returns `true` if `a` and `b` have the same canonical form, ignoring labels.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::ast::{Expr, canonical::equivalent};
+///
+/// // (1d20 + 5)[to hit] - 1
+/// let lhs = Expr::sub(
+///     Expr::label(Expr::add(Expr::dice(1, 20), Expr::int(5)), "to hit"),
+///     Expr::int(1),
+/// );
+/// // 4 + 1d20
+/// let rhs = Expr::add(Expr::int(4), Expr::dice(1, 20));
+///
+/// assert!(equivalent(&lhs, &rhs));
+/// ```
+pub fn equivalent(a: &Expr<'_>, b: &Expr<'_>) -> bool {
+    a.strip_labels().canonical() == b.strip_labels().canonical()
+}
+
+/// Like [`equivalent`], but falls back to comparing the exact [`Distribution`]
+/// of both expressions when their canonical forms differ.
+///
+/// This catches rolls that have the same odds but a different shape, like
+/// `1d6 + 1d6` and `2d6`.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::ast::{Expr, canonical::{equivalent, equivalent_distribution}};
+///
+/// let lhs = Expr::add(Expr::dice(1, 6), Expr::dice(1, 6));
+/// let rhs = Expr::dice(2, 6);
+///
+/// assert!(!equivalent(&lhs, &rhs));
+/// assert_eq!(equivalent_distribution(&lhs, &rhs), Ok(true));
+/// ```
+pub fn equivalent_distribution(a: &Expr<'_>, b: &Expr<'_>) -> Result<bool, DistributionError> {
+    if equivalent(a, b) {
+        Ok(true)
+    } else {
+        Ok(Distribution::of(a)? == Distribution::of(b)?)
+    }
+}
+
+/// Returns `true` if a list operation would read the faces of `e`.
+fn is_pool(e: &Expr<'_>) -> bool {
+    match e {
+        Expr::Dice(..) | Expr::CritDice(..) | Expr::List(..) => true,
+        Expr::Label(lhs, _) => is_pool(lhs),
+        _ => false,
+    }
+}
+
+/// Splits a canonical sum back into its terms.
+fn terms(e: Expr<'_>) -> Vec<Expr<'_>> {
+    let mut terms = Vec::new();
+    let mut rest = e;
+    loop {
+        match rest {
+            Expr::Add(lhs, rhs) => {
+                terms.push(*rhs);
+                rest = *lhs;
+            }
+            Expr::Sub(lhs, rhs) => {
+                terms.push(negate(*rhs));
+                rest = *lhs;
+            }
+            e => {
+                terms.push(e);
+                break terms;
+            }
+        }
+    }
+}
+
+/// Negates a canonical expression.
+fn negate(e: Expr<'_>) -> Expr<'_> {
+    match e {
+        Expr::Int(x) => x.checked_neg().map_or(Expr::not(Expr::Int(x)), Expr::Int),
+        Expr::Not(rhs) => *rhs,
+        e @ (Expr::Add(..) | Expr::Sub(..)) => sum(terms(e).into_iter().map(negate)),
+        e => Expr::not(e),
+    }
+}
+
+/// Builds a canonical sum from a list of canonical terms.
+fn sum<'s>(terms: impl IntoIterator<Item = Expr<'s>>) -> Expr<'s> {
+    let mut constant: i64 = 0;
+    let mut positive = Vec::new();
+    let mut negative = Vec::new();
+    for term in terms {
+        match term {
+            Expr::Int(x) => match constant.checked_add(x) {
+                Some(c) => constant = c,
+                None => positive.push(Expr::Int(x)),
+            },
+            Expr::Not(rhs) => negative.push(*rhs),
+            term => positive.push(term),
+        }
+    }
+    positive.sort();
+    negative.sort();
+
+    let mut acc = positive.into_iter().reduce(Expr::add);
+    for term in negative {
+        acc = Some(match acc {
+            Some(acc) => Expr::sub(acc, term),
+            None => Expr::not(term),
+        });
+    }
+
+    match (acc, constant) {
+        (None, c) => Expr::Int(c),
+        (Some(acc), 0) => acc,
+        (Some(acc), c) => match c {
+            ..0 if c != i64::MIN => Expr::sub(acc, Expr::Int(-c)),
+            c => Expr::add(acc, Expr::Int(c)),
+        },
+    }
+}
+
+/// Builds a canonical product from a list of canonical expressions.
+fn product<'s>(factors: impl IntoIterator<Item = Expr<'s>>) -> Expr<'s> {
+    let mut negative = false;
+    let mut coefficient: i64 = 1;
+    let mut sorted = Vec::new();
+
+    let mut stack: Vec<Expr<'s>> = factors.into_iter().collect();
+    while let Some(factor) = stack.pop() {
+        match factor {
+            Expr::Mul(lhs, rhs) => stack.extend([*lhs, *rhs]),
+            Expr::Not(rhs) => {
+                negative = !negative;
+                stack.push(*rhs);
+            }
+            Expr::Int(x) => match x.checked_abs().and_then(|a| coefficient.checked_mul(a)) {
+                Some(c) => {
+                    negative ^= x < 0;
+                    coefficient = c;
+                }
+                _ => sorted.push(Expr::Int(x)),
+            },
+            factor => sorted.push(factor),
+        }
+    }
+    sorted.sort();
+
+    let acc = match (sorted.into_iter().reduce(Expr::mul), coefficient) {
+        (None, c) => Expr::Int(c),
+        (Some(acc), 1) => acc,
+        (Some(acc), c) => Expr::mul(acc, Expr::Int(c)),
+    };
+    if negative { negate(acc) } else { acc }
+}
+
+/// Builds a canonical quotient, pulling out the signs of `lhs` and `rhs`.
+///
+/// This works because integer division truncates towards zero.
+fn quotient<'s>(lhs: Expr<'s>, rhs: Expr<'s>) -> Expr<'s> {
+    fn unsign(e: Expr<'_>) -> (bool, Expr<'_>) {
+        match e {
+            Expr::Not(rhs) => (true, *rhs),
+            Expr::Int(x) if x < 0 && x != i64::MIN => (true, Expr::Int(-x)),
+            e => (false, e),
+        }
+    }
+
+    let (lneg, lhs) = unsign(lhs);
+    let (rneg, rhs) = unsign(rhs);
+    let acc = Expr::div(lhs, rhs);
+    if lneg != rneg { negate(acc) } else { acc }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::list::ListOp;
+    use Expr as e;
+
+    #[test]
+    fn test_canonical_sums() {
+        // 2 + (1d6 - 3) + 1d4
+        let tree = e::add(
+            e::add(e::int(2), e::sub(e::dice(1, 6), e::int(3))),
+            e::dice(1, 4),
+        );
+        assert_eq!(tree.canonical().to_string(), "1d4 + 1d6 - 1");
+
+        // -(1d6 - 2)
+        let tree = e::not(e::sub(e::dice(1, 6), e::int(2)));
+        assert_eq!(tree.canonical().to_string(), "-1d6 + 2");
+
+        // 1 - 1
+        let tree = e::sub(e::int(1), e::int(1));
+        assert_eq!(tree.canonical(), e::int(0));
+    }
+
+    #[test]
+    fn test_canonical_products() {
+        // 3 * -1d4 * 2
+        let tree = e::mul(e::mul(e::int(3), e::not(e::dice(1, 4))), e::int(2));
+        assert_eq!(tree.canonical().to_string(), "-(1d4 * 6)");
+
+        // -8 / -(1d4)
+        let tree = e::div(e::int(-8), e::not(e::dice(1, 4)));
+        assert_eq!(tree.canonical().to_string(), "8 / 1d4");
+    }
+
+    #[test]
+    fn test_canonical_lists() {
+        // (2 + 4d6 - 2)s
+        let tree = e::list(
+            e::sub(e::add(e::int(2), e::dice(4, 6)), e::int(2)),
+            ListOp::Sort,
+        );
+        assert_eq!(tree.canonical().to_string(), "(4d6 + 0)s");
+        assert_eq!(tree.canonical().canonical(), tree.canonical());
+
+        // (1 + 6d6[fire]) unique
+        let tree = e::list(
+            e::add(e::int(1), e::label(e::dice(6, 6), "fire")),
+            ListOp::Unique,
+        );
+        assert_eq!(tree.canonical().to_string(), "(6d6[fire] + 1) unique");
+    }
+
+    #[test]
+    fn test_equivalent() {
+        // 2 * (1d8 + 1d6)[fire] and (1d6 + 1d8) * 2
+        let lhs = e::mul(
+            e::int(2),
+            e::label(e::add(e::dice(1, 8), e::dice(1, 6)), "fire"),
+        );
+        let rhs = e::mul(e::add(e::dice(1, 6), e::dice(1, 8)), e::int(2));
+        assert!(equivalent(&lhs, &rhs));
+
+        // 1d6 / 2 and 2 / 1d6
+        let lhs = e::div(e::dice(1, 6), e::int(2));
+        let rhs = e::div(e::int(2), e::dice(1, 6));
+        assert!(!equivalent(&lhs, &rhs));
+        assert_eq!(equivalent_distribution(&lhs, &rhs), Ok(false));
+    }
+}
@@ -0,0 +1,195 @@
+//! Critical hits and fumbles, like the `cs>=19` and `cf1` in `1d20cs>=19cf1`.
+//!
+//! A die term with [`Crits`] is parsed as an [`Expr::CritDice`]. It rolls like a
+//! normal [`Expr::Dice`], but every face is checked against its ranges, see
+//! [`crate::eval::DiceRoller::try_eval_info`].
+//!
+//! For doubling or maximizing the dice of a damage roll after a critical hit,
+//! see [`Expr::crit_damage`].
+
+use std::fmt::Display;
+
+use recursion::{CollapsibleExt as _, Expandable as _};
+
+use super::{Expr, ExprFrame, recurse::WithLabels};
+
+/// How a face is compared to a [`Threshold`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Compare {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    /// The string representation of the comparison.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Compare::Eq => "=",
+            Compare::Lt => "<",
+            Compare::Le => "<=",
+            Compare::Gt => ">",
+            Compare::Ge => ">=",
+        }
+    }
+
+    /// Returns `true` if `lhs` compared to `rhs` holds.
+    pub fn holds(&self, lhs: i64, rhs: i64) -> bool {
+        match self {
+            Compare::Eq => lhs == rhs,
+            Compare::Lt => lhs < rhs,
+            Compare::Le => lhs <= rhs,
+            Compare::Gt => lhs > rhs,
+            Compare::Ge => lhs >= rhs,
+        }
+    }
+}
+
+/// A range of faces, like `>=19`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Threshold {
+    pub compare: Compare,
+    pub value: i64,
+}
+
+impl Threshold {
+    /// Returns `true` if `face` is in the range.
+    pub fn matches(&self, face: i64) -> bool {
+        self.compare.holds(face, self.value)
+    }
+}
+
+impl Display for Threshold {
+    /// An [`Compare::Eq`] is printed as a bare number, like `cf1`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.compare {
+            Compare::Eq => write!(f, "{}", self.value),
+            compare => write!(f, "{}{}", compare.as_str(), self.value),
+        }
+    }
+}
+
+/// The critical success and critical failure ranges of a die term.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Crits {
+    /// The `cs` range.
+    pub success: Option<Threshold>,
+    /// The `cf` range.
+    pub failure: Option<Threshold>,
+}
+
+impl Crits {
+    /// Returns `true` if `face` is a critical success.
+    pub fn is_success(&self, face: i64) -> bool {
+        self.success.is_some_and(|t| t.matches(face))
+    }
+
+    /// Returns `true` if `face` is a critical failure.
+    pub fn is_failure(&self, face: i64) -> bool {
+        self.failure.is_some_and(|t| t.matches(face))
+    }
+}
+
+impl Display for Crits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(success) = self.success {
+            write!(f, "cs{}", success)?;
+        }
+        if let Some(failure) = self.failure {
+            write!(f, "cf{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// How [`Expr::crit_damage`] changes each die term.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CritRule {
+    /// Roll twice as many dice: `2d6` becomes `4d6`.
+    DoubleDice,
+    /// Every die rolls its highest face: `2d6` becomes `12`.
+    MaximizeDice,
+    /// Roll the dice normally, and add their highest faces: `2d6` becomes `2d6 + 12`.
+    MaximizeAndRoll,
+}
+
+impl<'s> Expr<'s> {
+    /// Applies a [`CritRule`] to every die term, leaving the rest of the expression alone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::{Expr, crit::CritRule};
+    ///
+    /// // 2d6 + 1d8[fire] + 3
+    /// let tree = Expr::add(
+    ///     Expr::add(Expr::dice(2, 6), Expr::label(Expr::dice(1, 8), "fire")),
+    ///     Expr::int(3),
+    /// );
+    ///
+    /// assert_eq!(tree.crit_damage(CritRule::DoubleDice).to_string(), "4d6 + 2d8[fire] + 3");
+    /// assert_eq!(tree.crit_damage(CritRule::MaximizeDice).to_string(), "12 + 8[fire] + 3");
+    /// ```
+    pub fn crit_damage(&self, rule: CritRule) -> Expr<'s> {
+        let apply = |c: i64, s: i64, dice: Expr<'s>| match rule {
+            CritRule::DoubleDice => match c.checked_mul(2) {
+                Some(c) => match dice {
+                    Expr::CritDice(_, s, crits) => Expr::CritDice(c, s, crits),
+                    _ => Expr::Dice(c, s),
+                },
+                None => Expr::add(dice.clone(), dice),
+            },
+            CritRule::MaximizeDice => maximum(c, s),
+            CritRule::MaximizeAndRoll => Expr::add(dice, maximum(c, s)),
+        };
+
+        WithLabels(self).collapse_frames(|frame| match frame {
+            ExprFrame::Dice(c, s) => apply(c, s, Expr::Dice(c, s)),
+            ExprFrame::CritDice(c, s, crits) => apply(c, s, Expr::CritDice(c, s, crits)),
+            frame => Expr::from_frame(frame),
+        })
+    }
+}
+
+/// The highest possible roll of `c` dice with `s` sides.
+fn maximum<'s>(c: i64, s: i64) -> Expr<'s> {
+    c.checked_mul(s)
+        .map_or(Expr::mul(Expr::Int(c), Expr::Int(s)), Expr::Int)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crit_damage() {
+        // -(1d20cs>=19 * 2)
+        let crits = Crits {
+            success: Some(Threshold {
+                compare: Compare::Ge,
+                value: 19,
+            }),
+            failure: None,
+        };
+        let tree = Expr::not(Expr::mul(Expr::CritDice(1, 20, crits), Expr::int(2)));
+
+        assert_eq!(tree.to_string(), "-(1d20cs>=19 * 2)");
+        assert_eq!(
+            tree.crit_damage(CritRule::DoubleDice).to_string(),
+            "-(2d20cs>=19 * 2)"
+        );
+        assert_eq!(
+            tree.crit_damage(CritRule::MaximizeAndRoll).to_string(),
+            "-((1d20cs>=19 + 20) * 2)"
+        );
+    }
+}
@@ -1,4 +1,11 @@
 //! Adapter types for displaying [`super::ExprFrame`] and [`super::Expr`].
+//!
+//! Besides the infix [`std::fmt::Display`] implementation for [`Expr`], there are
+//! adapters for other output formats:
+//! - [`Expr::latex`], for LaTeX math mode.
+//! - [`Expr::mathml`], for presentation MathML.
+//! - [`Expr::sexpr`], for an S-expression.
+//! - [`Expr::dot`], for a Graphviz DOT graph of the tree.
 
 // use std::fmt::Display;
 
@@ -19,17 +26,27 @@
 //     }
 // }
 
-use super::{Expr, precedence};
+use std::fmt::{self, Write as _};
+
+use recursion::CollapsibleExt as _;
+
+use super::{
+    Expr, ExprFrame,
+    crit::Compare,
+    list::ListOp,
+    precedence::{BinOp, Op},
+};
 
 impl std::fmt::Display for Expr<'_> {
     /// Prints the expression without any redundant parenthesis.
     ///
-    /// The internals of this method use the [`precedence`] module for most
+    /// The internals of this method use the [`super::precedence`] module for most
     /// of the tricky parts.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Expr::Int(x) => write!(f, "{}", x),
             Expr::Dice(c, s) => write!(f, "{}d{}", c, s),
+            Expr::CritDice(c, s, crits) => write!(f, "{}d{}{}", c, s, crits),
             Expr::Not(rhs) => {
                 if rhs.is_unit() {
                     write!(f, "-{}", rhs)
@@ -44,17 +61,39 @@ impl std::fmt::Display for Expr<'_> {
                     write!(f, "({})[{}]", lhs, s)
                 }
             }
+            Expr::List(lhs, op @ ListOp::Count(Some(t))) => {
+                write!(
+                    f,
+                    "{}({} {} {})",
+                    op.as_str(),
+                    lhs,
+                    t.compare.as_str(),
+                    t.value
+                )
+            }
+            Expr::List(lhs, op) if op.is_function() => write!(f, "{}({})", op.as_str(), lhs),
+            Expr::List(lhs, op) => {
+                // `s` is glued to a number or bracket, but not to a word like `unique`
+                let space = match (op, lhs.as_ref()) {
+                    (ListOp::Sort, Expr::List(_, inner)) if inner.is_function() => "",
+                    (ListOp::Sort, Expr::List(_, ListOp::Sort)) => "",
+                    (ListOp::Sort, Expr::List(..)) => " ",
+                    (ListOp::Sort, _) => "",
+                    _ => " ",
+                };
+                if is_list_operand(lhs) {
+                    write!(f, "{}{}{}", lhs, space, op.as_str())
+                } else {
+                    write!(f, "({}){}{}", lhs, space, op.as_str())
+                }
+            }
             Expr::Add(lhs, rhs)
             | Expr::Sub(lhs, rhs)
             | Expr::Mul(lhs, rhs)
             | Expr::Div(lhs, rhs) => {
-                let lop = precedence::Op::from_expr(lhs);
-                let rop = precedence::Op::from_expr(rhs);
-                let me = precedence::Op::from_expr(self)
-                    .as_binop()
-                    .expect("&self matched as a binary_op");
+                let (me, parens) = binop_parenthesis(self, lhs, rhs, Op::from_expr);
 
-                match precedence::BinOp::needs_parenthesis(me, lop, rop) {
+                match parens {
                     (true, true) => write!(f, "({}) {} ({})", lhs, me.as_str(), rhs),
                     (true, false) => write!(f, "({}) {} {}", lhs, me.as_str(), rhs),
                     (false, true) => write!(f, "{} {} ({})", lhs, me.as_str(), rhs),
@@ -65,9 +104,399 @@ impl std::fmt::Display for Expr<'_> {
     }
 }
 
+/// Returns `true` if `e` is displayed so that a list operation after it doesn't
+/// need parenthesis.
+///
+/// A negation binds looser than a postfix operation, so `-4d6s` is `-(4d6s)`,
+/// and `(-4d6)s` needs its parenthesis.
+fn is_list_operand(e: &Expr<'_>) -> bool {
+    match e {
+        Expr::Int(x) => *x >= 0,
+        Expr::Dice(..) | Expr::CritDice(..) | Expr::List(..) => true,
+        Expr::Label(lhs, _) => !lhs.is_unit() || is_list_operand(lhs),
+        _ => false,
+    }
+}
+
+/// Finds the [`BinOp`] of `me`, and whether its children need parenthesis.
+///
+/// `op` chooses the [`Op`] of the children, so renderers that draw some operations
+/// as a single unit (like a LaTeX `\frac`) can treat them like a leaf.
+fn binop_parenthesis(
+    me: &Expr<'_>,
+    lhs: &Expr<'_>,
+    rhs: &Expr<'_>,
+    op: impl Fn(&Expr<'_>) -> Op,
+) -> (BinOp, (bool, bool)) {
+    let me = Op::from_expr(me)
+        .as_binop()
+        .expect("&self matched as a binary_op");
+
+    (me, BinOp::needs_parenthesis(me, op(lhs), op(rhs)))
+}
+
+impl<'s> Expr<'s> {
+    /// Displays the expression in LaTeX math mode, using `\frac` for division.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::Expr;
+    ///
+    /// // (1d20 + 5) / 2
+    /// let tree = Expr::div(Expr::add(Expr::dice(1, 20), Expr::int(5)), Expr::int(2));
+    /// assert_eq!(tree.latex().to_string(), r"\frac{1\mathrm{d}20 + 5}{2}");
+    /// ```
+    pub fn latex(&self) -> Latex<'_, 's> {
+        Latex(self)
+    }
+
+    /// Displays the expression as presentation MathML, wrapped in a `<math>` element.
+    pub fn mathml(&self) -> MathMl<'_, 's> {
+        MathMl(self)
+    }
+
+    /// Displays the expression as an S-expression, like `(+ 1d20 5)`.
+    pub fn sexpr(&self) -> SExpr<'_, 's> {
+        SExpr(self)
+    }
+
+    /// Displays the expression as a Graphviz DOT `digraph`, with one node per [`Expr`].
+    pub fn dot(&self) -> Dot<'_, 's> {
+        Dot(self)
+    }
+}
+
+/// See [`Expr::latex`].
+#[derive(Debug, Clone, Copy)]
+pub struct Latex<'a, 's>(&'a Expr<'s>);
+
+impl Latex<'_, '_> {
+    /// Fractions are drawn as a single unit, so they never need parenthesis.
+    fn op(e: &Expr<'_>) -> Op {
+        match Op::from_expr(e) {
+            Op::Div => Op::Empty,
+            op => op,
+        }
+    }
+
+    /// See [`Expr::is_unit`].
+    fn is_unit(e: &Expr<'_>) -> bool {
+        e.collapse_frames(|frame| match frame {
+            ExprFrame::Int(_)
+            | ExprFrame::Dice(_, _)
+            | ExprFrame::CritDice(..)
+            | ExprFrame::Div(_, _) => true,
+            ExprFrame::List(_, op) if op.is_function() => true,
+            ExprFrame::Not(expr) | ExprFrame::Label(expr, _) | ExprFrame::List(expr, _) => expr,
+            _ => false,
+        })
+    }
+}
+
+impl fmt::Display for Latex<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Expr::Int(x) => write!(f, "{}", x),
+            Expr::Dice(c, s) => write!(f, r"{}\mathrm{{d}}{}", c, s),
+            Expr::CritDice(c, s, crits) => {
+                write!(f, r"{}\mathrm{{d}}{}", c, s)?;
+                for (name, t) in [("cs", crits.success), ("cf", crits.failure)] {
+                    if let Some(t) = t {
+                        let compare = match t.compare {
+                            Compare::Eq => "=",
+                            Compare::Lt => "<",
+                            Compare::Le => r"\leq ",
+                            Compare::Gt => ">",
+                            Compare::Ge => r"\geq ",
+                        };
+                        write!(f, r"\,\mathrm{{{}}}{}{}", name, compare, t.value)?;
+                    }
+                }
+                Ok(())
+            }
+            Expr::Not(rhs) => {
+                if Self::is_unit(rhs) {
+                    write!(f, "-{}", rhs.latex())
+                } else {
+                    write!(f, r"-\left({}\right)", rhs.latex())
+                }
+            }
+            Expr::Label(lhs, s) => {
+                write!(f, r"\underbrace{{{}}}_{{\text{{", lhs.latex())?;
+                for c in s.chars() {
+                    match c {
+                        '\\' => f.write_str(r"\textbackslash{}")?,
+                        '~' => f.write_str(r"\textasciitilde{}")?,
+                        '^' => f.write_str(r"\textasciicircum{}")?,
+                        '#' | '$' | '%' | '&' | '_' | '{' | '}' => write!(f, r"\{}", c)?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_str("}}")
+            }
+            Expr::List(lhs, op) if op.is_function() => {
+                let name = match op {
+                    ListOp::Max | ListOp::Min => format!(r"\{}", op.as_str()),
+                    op => format!(r"\operatorname{{{}}}", op.as_str()),
+                };
+                write!(f, r"{}\left({}", name, lhs.latex())?;
+                if let ListOp::Count(Some(t)) = op {
+                    let compare = match t.compare {
+                        Compare::Le => r"\leq",
+                        Compare::Ge => r"\geq",
+                        compare => compare.as_str(),
+                    };
+                    write!(f, " {} {}", compare, t.value)?;
+                }
+                f.write_str(r"\right)")
+            }
+            Expr::List(lhs, op) => {
+                if Self::is_unit(lhs) {
+                    write!(f, r"{}\,\mathrm{{{}}}", lhs.latex(), op.as_str())
+                } else {
+                    write!(
+                        f,
+                        r"\left({}\right)\,\mathrm{{{}}}",
+                        lhs.latex(),
+                        op.as_str()
+                    )
+                }
+            }
+            Expr::Div(lhs, rhs) => write!(f, r"\frac{{{}}}{{{}}}", lhs.latex(), rhs.latex()),
+            Expr::Add(lhs, rhs) | Expr::Sub(lhs, rhs) | Expr::Mul(lhs, rhs) => {
+                let (me, (lparen, rparen)) = binop_parenthesis(self.0, lhs, rhs, Self::op);
+                let me = match me {
+                    BinOp::Mul => r"\times",
+                    me => me.as_str(),
+                };
+
+                if lparen {
+                    write!(f, r"\left({}\right)", lhs.latex())?;
+                } else {
+                    write!(f, "{}", lhs.latex())?;
+                }
+                write!(f, " {} ", me)?;
+                if rparen {
+                    write!(f, r"\left({}\right)", rhs.latex())
+                } else {
+                    write!(f, "{}", rhs.latex())
+                }
+            }
+        }
+    }
+}
+
+/// See [`Expr::mathml`].
+#[derive(Debug, Clone, Copy)]
+pub struct MathMl<'a, 's>(&'a Expr<'s>);
+
+impl MathMl<'_, '_> {
+    /// Writes the inner elements of an [`Expr`], without the `<math>` root.
+    fn inner(e: &Expr<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paren = |e: &Expr<'_>, f: &mut fmt::Formatter<'_>, needed: bool| {
+            if needed {
+                f.write_str("<mrow><mo>(</mo>")?;
+                Self::inner(e, f)?;
+                f.write_str("<mo>)</mo></mrow>")
+            } else {
+                Self::inner(e, f)
+            }
+        };
+
+        match e {
+            Expr::Int(x) => write!(f, "<mn>{}</mn>", x),
+            Expr::Dice(c, s) => write!(f, "<mrow><mn>{}</mn><mi>d</mi><mn>{}</mn></mrow>", c, s),
+            Expr::CritDice(c, s, crits) => {
+                write!(f, "<mrow><mn>{}</mn><mi>d</mi><mn>{}</mn>", c, s)?;
+                for (name, t) in [("cs", crits.success), ("cf", crits.failure)] {
+                    if let Some(t) = t {
+                        let compare = match t.compare {
+                            Compare::Eq => "=",
+                            Compare::Lt => "&lt;",
+                            Compare::Le => "&#x2264;",
+                            Compare::Gt => "&gt;",
+                            Compare::Ge => "&#x2265;",
+                        };
+                        write!(
+                            f,
+                            "<mi>{}</mi><mo>{}</mo><mn>{}</mn>",
+                            name, compare, t.value
+                        )?;
+                    }
+                }
+                f.write_str("</mrow>")
+            }
+            Expr::Not(rhs) => {
+                f.write_str("<mrow><mo>-</mo>")?;
+                paren(rhs, f, !Latex::is_unit(rhs))?;
+                f.write_str("</mrow>")
+            }
+            Expr::Label(lhs, s) => {
+                f.write_str("<munder><mrow>")?;
+                Self::inner(lhs, f)?;
+                f.write_str("</mrow><mtext>")?;
+                for c in s.chars() {
+                    match c {
+                        '&' => f.write_str("&amp;")?,
+                        '<' => f.write_str("&lt;")?,
+                        '>' => f.write_str("&gt;")?,
+                        '"' => f.write_str("&quot;")?,
+                        '\'' => f.write_str("&apos;")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_str("</mtext></munder>")
+            }
+            Expr::List(lhs, op) if op.is_function() => {
+                write!(f, "<mrow><mi>{}</mi><mo>(</mo>", op.as_str())?;
+                Self::inner(lhs, f)?;
+                if let ListOp::Count(Some(t)) = op {
+                    let compare = match t.compare {
+                        Compare::Eq => "=",
+                        Compare::Lt => "&lt;",
+                        Compare::Le => "&#x2264;",
+                        Compare::Gt => "&gt;",
+                        Compare::Ge => "&#x2265;",
+                    };
+                    write!(f, "<mo>{}</mo><mn>{}</mn>", compare, t.value)?;
+                }
+                f.write_str("<mo>)</mo></mrow>")
+            }
+            Expr::List(lhs, op) => {
+                f.write_str("<mrow>")?;
+                paren(lhs, f, !Latex::is_unit(lhs))?;
+                write!(f, "<mi>{}</mi></mrow>", op.as_str())
+            }
+            Expr::Div(lhs, rhs) => {
+                f.write_str("<mfrac><mrow>")?;
+                Self::inner(lhs, f)?;
+                f.write_str("</mrow><mrow>")?;
+                Self::inner(rhs, f)?;
+                f.write_str("</mrow></mfrac>")
+            }
+            Expr::Add(lhs, rhs) | Expr::Sub(lhs, rhs) | Expr::Mul(lhs, rhs) => {
+                let (me, (lparen, rparen)) = binop_parenthesis(e, lhs, rhs, Latex::op);
+                let me = match me {
+                    BinOp::Mul => "&#xD7;",
+                    me => me.as_str(),
+                };
+
+                f.write_str("<mrow>")?;
+                paren(lhs, f, lparen)?;
+                write!(f, "<mo>{}</mo>", me)?;
+                paren(rhs, f, rparen)?;
+                f.write_str("</mrow>")
+            }
+        }
+    }
+}
+
+impl fmt::Display for MathMl<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#)?;
+        Self::inner(self.0, f)?;
+        f.write_str("</math>")
+    }
+}
+
+/// A string in double quotes, as written in an S-expression or a DOT graph,
+/// where only `"` and `\` are escaped.
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' | '\\' => write!(f, "\\{}", c)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// See [`Expr::sexpr`].
+#[derive(Debug, Clone, Copy)]
+pub struct SExpr<'a, 's>(&'a Expr<'s>);
+
+impl fmt::Display for SExpr<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Expr::Int(x) => write!(f, "{}", x),
+            Expr::Dice(c, s) => write!(f, "{}d{}", c, s),
+            Expr::CritDice(c, s, crits) => write!(f, "{}d{}{}", c, s, crits),
+            Expr::Not(rhs) => write!(f, "(- {})", rhs.sexpr()),
+            Expr::Label(lhs, s) => write!(f, "(label {} {})", lhs.sexpr(), Quoted(s)),
+            Expr::List(lhs, ListOp::Count(Some(t))) => write!(
+                f,
+                "(count {} {} {})",
+                lhs.sexpr(),
+                t.compare.as_str(),
+                t.value
+            ),
+            Expr::List(lhs, op) => write!(f, "({} {})", op.as_str(), lhs.sexpr()),
+            Expr::Add(lhs, rhs)
+            | Expr::Sub(lhs, rhs)
+            | Expr::Mul(lhs, rhs)
+            | Expr::Div(lhs, rhs) => {
+                let me = Op::from_expr(self.0)
+                    .as_binop()
+                    .expect("&self matched as a binary_op");
+                write!(f, "({} {} {})", me.as_str(), lhs.sexpr(), rhs.sexpr())
+            }
+        }
+    }
+}
+
+/// See [`Expr::dot`].
+#[derive(Debug, Clone, Copy)]
+pub struct Dot<'a, 's>(&'a Expr<'s>);
+
+impl fmt::Display for Dot<'_, '_> {
+    /// Nodes are numbered in the order they're collapsed, so children are
+    /// always written before their parent, and the root is written last.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph expr {{")?;
+
+        let mut next_id = 0usize;
+        self.0.try_collapse_frames(|frame| {
+            let id = next_id;
+            next_id += 1;
+
+            let (label, children) = match frame {
+                ExprFrame::Int(x) => (x.to_string(), vec![]),
+                ExprFrame::Dice(c, s) => (format!("{}d{}", c, s), vec![]),
+                ExprFrame::CritDice(c, s, crits) => (format!("{}d{}{}", c, s, crits), vec![]),
+                ExprFrame::Not(rhs) => (Op::Not.to_string(), vec![rhs]),
+                ExprFrame::Label(lhs, s) => (format!("[{}]", s), vec![lhs]),
+                ExprFrame::List(lhs, ListOp::Count(Some(t))) => (
+                    format!("count {}{}", t.compare.as_str(), t.value),
+                    vec![lhs],
+                ),
+                ExprFrame::List(lhs, op) => (op.as_str().to_string(), vec![lhs]),
+                ExprFrame::Add(lhs, rhs) => (Op::Add.to_string(), vec![lhs, rhs]),
+                ExprFrame::Sub(lhs, rhs) => (Op::Sub.to_string(), vec![lhs, rhs]),
+                ExprFrame::Mul(lhs, rhs) => (Op::Mul.to_string(), vec![lhs, rhs]),
+                ExprFrame::Div(lhs, rhs) => (Op::Div.to_string(), vec![lhs, rhs]),
+            };
+
+            writeln!(f, "    n{} [label={}];", id, Quoted(&label))?;
+            for child in children {
+                writeln!(f, "    n{} -> n{};", id, child)?;
+            }
+            Ok::<_, fmt::Error>(id)
+        })?;
+
+        write!(f, "}}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::crit::Threshold;
     use Expr as e;
 
     #[test]
@@ -88,4 +517,134 @@ mod tests {
 
         assert_eq!(tree.to_string(), "4 * (1 + 3) / 7 / ((8 + 9) * 2)");
     }
+
+    #[test]
+    fn test_lists() {
+        // (-4d6)s + -4d6s + 6d6[a] unique s
+        let tree = e::add(
+            e::add(
+                e::list(e::not(e::dice(4, 6)), ListOp::Sort),
+                e::not(e::list(e::dice(4, 6), ListOp::Sort)),
+            ),
+            e::list(
+                e::list(e::label(e::dice(6, 6), "a"), ListOp::Unique),
+                ListOp::Sort,
+            ),
+        );
+
+        assert_eq!(tree.to_string(), "(-4d6)s + -4d6s + 6d6[a] unique s");
+        assert_eq!(
+            tree.sexpr().to_string(),
+            r#"(+ (+ (s (- 4d6)) (- (s 4d6))) (s (unique (label 6d6 "a"))))"#
+        );
+        assert_eq!(
+            e::list(e::dice(6, 6), ListOp::Group).latex().to_string(),
+            r"6\mathrm{d}6\,\mathrm{group}"
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        // max(4d6)s + count(-(8d10) >= 7)
+        let at_least = Threshold {
+            compare: Compare::Ge,
+            value: 7,
+        };
+        let tree = e::add(
+            e::list(e::list(e::dice(4, 6), ListOp::Max), ListOp::Sort),
+            e::list(e::not(e::dice(8, 10)), ListOp::Count(Some(at_least))),
+        );
+
+        assert_eq!(tree.to_string(), "max(4d6)s + count(-8d10 >= 7)");
+        assert_eq!(
+            tree.sexpr().to_string(),
+            "(+ (s (max 4d6)) (count (- 8d10) >= 7))"
+        );
+        assert_eq!(
+            tree.latex().to_string(),
+            r"\max\left(4\mathrm{d}6\right)\,\mathrm{s} + \operatorname{count}\left(-8\mathrm{d}10 \geq 7\right)"
+        );
+    }
+
+    #[test]
+    fn test_latex() {
+        // 4 * (1 + 3) / 7 - -(1d6 + 1)[a_b]
+        let tree = e::sub(
+            e::div(e::mul(e::int(4), e::add(e::int(1), e::int(3))), e::int(7)),
+            e::not(e::label(e::add(e::dice(1, 6), e::int(1)), "a_b")),
+        );
+
+        assert_eq!(
+            tree.latex().to_string(),
+            r"\frac{4 \times \left(1 + 3\right)}{7} - -\left(\underbrace{1\mathrm{d}6 + 1}_{\text{a\_b}}\right)"
+        );
+    }
+
+    #[test]
+    fn test_mathml() {
+        // (1 + 2) * 3[<b>]
+        let tree = e::mul(e::add(e::int(1), e::int(2)), e::label(e::int(3), "<b>"));
+
+        assert_eq!(
+            tree.mathml().to_string(),
+            concat!(
+                r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#,
+                "<mrow><mrow><mo>(</mo><mrow><mn>1</mn><mo>+</mo><mn>2</mn></mrow><mo>)</mo></mrow>",
+                "<mo>&#xD7;</mo>",
+                "<munder><mrow><mn>3</mn></mrow><mtext>&lt;b&gt;</mtext></munder></mrow>",
+                "</math>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_sexpr() {
+        // 1d20 - -(5 / 2)["to hit"]
+        let tree = e::sub(
+            e::dice(1, 20),
+            e::label(e::not(e::div(e::int(5), e::int(2))), "\"to hit\""),
+        );
+
+        assert_eq!(
+            tree.sexpr().to_string(),
+            r#"(- 1d20 (label (- (/ 5 2)) "\"to hit\""))"#
+        );
+
+        // only `"` and `\` are escaped, unlike in a Rust string
+        let tree = e::label(e::int(1), r"épée\n 'x'");
+        assert_eq!(tree.sexpr().to_string(), r#"(label 1 "épée\\n 'x'")"#);
+    }
+
+    #[test]
+    fn test_dot() {
+        // 1d20 + 5
+        let tree = e::add(e::dice(1, 20), e::int(5));
+
+        assert_eq!(
+            tree.dot().to_string(),
+            concat!(
+                "digraph expr {\n",
+                "    n0 [label=\"5\"];\n",
+                "    n1 [label=\"1d20\"];\n",
+                "    n2 [label=\"+\"];\n",
+                "    n2 -> n1;\n",
+                "    n2 -> n0;\n",
+                "}"
+            )
+        );
+
+        // 1["épée\"], where Graphviz reads `\\` as a backslash
+        let tree = e::label(e::int(1), r#"épée\""#);
+        assert_eq!(
+            tree.dot().to_string(),
+            concat!(
+                "digraph expr {\n",
+                "    n0 [label=\"1\"];\n",
+                r#"    n1 [label="[épée\\\"]"];"#,
+                "\n",
+                "    n1 -> n0;\n",
+                "}"
+            )
+        );
+    }
 }
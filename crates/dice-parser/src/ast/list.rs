@@ -0,0 +1,89 @@
+//! Operations on the faces of a roll, rather than its sum, like the `s` in `4d6s`
+//! or the `max` in `max(4d6)`.
+//!
+//! A [`ListOp`] reads the faces of a *pool*: a die term like `6d6`, or another
+//! list operation like `6d6 unique`, optionally with a label. Any other
+//! operand, like `(1d6 + 2)`, is read as a pool with its total as the only face.
+//!
+//! Everywhere else, a pool is the sum of its faces, see [`crate::eval::value`].
+
+use super::{Expr, crit::Threshold};
+
+/// An operation on the faces of a pool. Most are written after it, like
+/// `4d6s`, but a few are written as a function of it, like `max(4d6)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ListOp {
+    /// Sorts the faces from lowest to highest, like `4d6s`. The total doesn't change.
+    Sort,
+    /// Keeps one of each face, like `6d6 unique`, sorted from lowest to highest.
+    Unique,
+    /// Counts the matches, like `6d6 matches`: each face rolled more than
+    /// once is one match, however many times it's rolled. The result is a
+    /// number, not a pool.
+    Matches,
+    /// Keeps the largest group of equal faces, like `6d6 group`. Between
+    /// groups of the same size, the one with the highest face is kept.
+    Group,
+    /// The sum of the faces, like `sum(3d6)`. This is what a pool is
+    /// anywhere else, so `sum(3d6) * 2` is the same as `3d6 * 2`.
+    Sum,
+    /// The highest face, like `max(4d6)`.
+    Max,
+    /// The lowest face, like `min(4d6)`.
+    Min,
+    /// The number of faces, like `count(4d6 unique)`, or only those in a
+    /// range, like `count(8d10 >= 7)`.
+    Count(Option<Threshold>),
+}
+
+impl ListOp {
+    /// The string representation of the operation, as it's parsed.
+    ///
+    /// When an [`Expr`] is displayed, a `s` is written right after a number or
+    /// a bracket, like `4d6s`, and every other operation after a space.
+    /// A function is written around its pool, see [`Self::is_function`].
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ListOp::Sort => "s",
+            ListOp::Unique => "unique",
+            ListOp::Matches => "matches",
+            ListOp::Group => "group",
+            ListOp::Sum => "sum",
+            ListOp::Max => "max",
+            ListOp::Min => "min",
+            ListOp::Count(_) => "count",
+        }
+    }
+
+    /// Returns `true` if the operation is written as a function, like `max(4d6)`.
+    pub const fn is_function(&self) -> bool {
+        matches!(
+            self,
+            ListOp::Sum | ListOp::Max | ListOp::Min | ListOp::Count(_)
+        )
+    }
+}
+
+impl Expr<'_> {
+    /// Creates a [`Expr::List`] with a [`Box`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::{Expr, list::ListOp};
+    ///
+    /// let tree = Expr::list(Expr::list(Expr::dice(6, 6), ListOp::Unique), ListOp::Sort);
+    /// assert_eq!(tree.to_string(), "6d6 unique s");
+    ///
+    /// let tree = Expr::list(Expr::add(Expr::dice(1, 6), Expr::int(2)), ListOp::Sort);
+    /// assert_eq!(tree.to_string(), "(1d6 + 2)s");
+    ///
+    /// let tree = Expr::mul(Expr::list(Expr::dice(3, 6), ListOp::Sum), Expr::int(2));
+    /// assert_eq!(tree.to_string(), "sum(3d6) * 2");
+    /// ```
+    pub fn list(lhs: Self, op: ListOp) -> Self {
+        Expr::List(Box::new(lhs), op)
+    }
+}
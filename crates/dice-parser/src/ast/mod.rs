@@ -3,23 +3,40 @@
 pub mod precedence;
 pub(crate) mod util;
 
+pub mod canonical;
+pub mod crit;
 pub mod display;
+pub mod list;
 pub mod recurse;
 
-use recursion::CollapsibleExt as _;
+use std::borrow::Cow;
+
+use recursion::{CollapsibleExt as _, Expandable as _};
+
+use crit::Crits;
+use list::ListOp;
 
 /// The [`Expr`] is the main type. It's a recursive [`Box`] enum over
 /// all possible expressions in the grammar.
 ///
 /// The `'s` lifetime is tied to the lifetime of the parsed
-/// string.
-#[derive(Debug, Clone, PartialEq)]
+/// string. Labels borrow from it when possible, but an [`OwnedExpr`]
+/// can be made with [`Expr::into_owned`] to outlive the input.
+///
+/// The [`Ord`] implementation is only structural, and is used
+/// to sort operands in [`Expr::canonical`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr<'s> {
-    Int(i32),
-    Dice(i32, i32),
+    Int(i64),
+    Dice(i64, i64),
+    /// A die term with critical success and failure ranges, like `1d20cs>=19cf1`.
+    CritDice(i64, i64, Crits),
     Not(Box<Expr<'s>>),
-    Label(Box<Expr<'s>>, &'s str),
+    Label(Box<Expr<'s>>, Cow<'s, str>),
+    /// An operation on the faces of a pool, like `4d6s` or `6d6 unique`.
+    List(Box<Expr<'s>>, ListOp),
     Add(Box<Expr<'s>>, Box<Expr<'s>>),
     Sub(Box<Expr<'s>>, Box<Expr<'s>>),
     Mul(Box<Expr<'s>>, Box<Expr<'s>>),
@@ -30,23 +47,46 @@ impl Expr<'_> {
     /// Returns `true` if there are no binary operations from `self` until the leaf.
     fn is_unit(&self) -> bool {
         self.collapse_frames(|frame| match frame {
-            ExprFrame::Int(_) | ExprFrame::Dice(_, _) => true,
-            ExprFrame::Not(expr) | ExprFrame::Label(expr, _) => expr,
+            ExprFrame::Int(_) | ExprFrame::Dice(_, _) | ExprFrame::CritDice(..) => true,
+            ExprFrame::List(_, op) if op.is_function() => true,
+            ExprFrame::Not(expr) | ExprFrame::Label(expr, _) | ExprFrame::List(expr, _) => expr,
             _ => false,
         })
     }
+
+    /// Copies every label, so the expression no longer borrows from the parsed string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::{Expr, OwnedExpr};
+    ///
+    /// let owned: OwnedExpr = {
+    ///     let input = String::from("fire");
+    ///     Expr::label(Expr::dice(1, 8), input.as_str()).into_owned()
+    /// };
+    /// assert_eq!(owned.to_string(), "1d8[fire]");
+    /// ```
+    pub fn into_owned(self) -> OwnedExpr {
+        self.collapse_frames(|frame| Expr::from_frame(frame.into_owned()))
+    }
 }
 
+/// An [`Expr`] which owns all of its labels.
+pub type OwnedExpr = Expr<'static>;
+
 /// A single level in the [`Expr`] tree.
 ///
 /// See [`recursion::MappableFrame`] for more details.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum ExprFrame<'s, A> {
-    Int(i32),
-    Dice(i32, i32),
+    Int(i64),
+    Dice(i64, i64),
+    CritDice(i64, i64, Crits),
     Not(A),
-    Label(A, &'s str),
+    Label(A, Cow<'s, str>),
+    List(A, ListOp),
     Add(A, A),
     Sub(A, A),
     Mul(A, A),
@@ -16,11 +16,13 @@ type Prec = i32;
 
 /// The operations defined in the grammar.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Op {
     /// Corresponds to a no-op (e.g. a dice roll or integer).
     Empty,
     Not,
     Label,
+    List,
     Add,
     Sub,
     Mul,
@@ -33,6 +35,7 @@ impl Display for Op {
             Op::Empty => "noop",
             Op::Not => "negate",
             Op::Label => "[label]",
+            Op::List => "list",
             Op::Add => "+",
             Op::Sub => "-",
             Op::Mul => "*",
@@ -45,6 +48,7 @@ impl Display for Op {
 
 /// Only binary operations in the grammar.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinOp {
     Add,
     Sub,
@@ -56,9 +60,10 @@ impl Op {
     /// Turns an [`Expr`] into an [`Op`].
     pub const fn from_expr(expr: &Expr<'_>) -> Self {
         match expr {
-            Expr::Int(_) | Expr::Dice(_, _) => Self::Empty,
+            Expr::Int(_) | Expr::Dice(_, _) | Expr::CritDice(..) => Self::Empty,
             Expr::Not(..) => Self::Not,
             Expr::Label(..) => Self::Label,
+            Expr::List(..) => Self::List,
             Expr::Add(..) => Self::Add,
             Expr::Sub(..) => Self::Sub,
             Expr::Mul(..) => Self::Mul,
@@ -72,7 +77,7 @@ impl Op {
     pub const fn prec(&self) -> Prec {
         match self {
             Op::Empty => 0,
-            Op::Label => 10,
+            Op::Label | Op::List => 10,
             Op::Not => 20,
             Op::Add => 40,
             Op::Sub => 40,
@@ -84,7 +89,7 @@ impl Op {
     /// Turns an [`Op`] into a [`BinOp`], or [`Option::None`] if it's not a binary operation.
     pub const fn as_binop(&self) -> Option<BinOp> {
         match self {
-            Op::Empty | Op::Not | Op::Label => None,
+            Op::Empty | Op::Not | Op::Label | Op::List => None,
             Op::Add => Some(BinOp::Add),
             Op::Sub => Some(BinOp::Sub),
             Op::Mul => Some(BinOp::Mul),
@@ -1,5 +1,7 @@
 //! Implementations of [`recursion`] traits, and a generic [`ExprFrame::map`] method.
 
+use std::borrow::Cow;
+
 use recursion::{Collapsible, Expandable, MappableFrame, PartiallyApplied};
 
 use super::{Expr, ExprFrame};
@@ -14,7 +16,7 @@ impl<'s, T> ExprFrame<'s, T> {
     /// use dice_parser::ast::{Expr, ExprFrame};
     ///
     /// // Returns the max roll possible
-    /// fn roll_max(c: i32, s: i32) -> i64 { (0..c).fold(0, |acc, _| acc + s as i64) }
+    /// fn roll_max(c: i64, s: i64) -> i64 { (0..c).fold(0, |acc, _| acc + s) }
     ///
     /// // Result of mapping our single frame.
     /// #[derive(Debug, PartialEq)]
@@ -52,14 +54,32 @@ impl<'s, T> ExprFrame<'s, T> {
         match self {
             ExprFrame::Int(x) => ExprFrame::Int(x),
             ExprFrame::Dice(c, s) => ExprFrame::Dice(c, s),
+            ExprFrame::CritDice(c, s, crits) => ExprFrame::CritDice(c, s, crits),
             ExprFrame::Not(rhs) => ExprFrame::Not(f(rhs)),
             ExprFrame::Label(lhs, msg) => ExprFrame::Label(f(lhs), msg),
+            ExprFrame::List(lhs, op) => ExprFrame::List(f(lhs), op),
             ExprFrame::Add(lhs, rhs) => ExprFrame::Add(f(lhs), f(rhs)),
             ExprFrame::Sub(lhs, rhs) => ExprFrame::Sub(f(lhs), f(rhs)),
             ExprFrame::Mul(lhs, rhs) => ExprFrame::Mul(f(lhs), f(rhs)),
             ExprFrame::Div(lhs, rhs) => ExprFrame::Div(f(lhs), f(rhs)),
         }
     }
+
+    /// Copies the label of an [`ExprFrame::Label`], if there is one.
+    pub fn into_owned(self) -> ExprFrame<'static, T> {
+        match self {
+            ExprFrame::Int(x) => ExprFrame::Int(x),
+            ExprFrame::Dice(c, s) => ExprFrame::Dice(c, s),
+            ExprFrame::CritDice(c, s, crits) => ExprFrame::CritDice(c, s, crits),
+            ExprFrame::Not(rhs) => ExprFrame::Not(rhs),
+            ExprFrame::Label(lhs, msg) => ExprFrame::Label(lhs, Cow::Owned(msg.into_owned())),
+            ExprFrame::List(lhs, op) => ExprFrame::List(lhs, op),
+            ExprFrame::Add(lhs, rhs) => ExprFrame::Add(lhs, rhs),
+            ExprFrame::Sub(lhs, rhs) => ExprFrame::Sub(lhs, rhs),
+            ExprFrame::Mul(lhs, rhs) => ExprFrame::Mul(lhs, rhs),
+            ExprFrame::Div(lhs, rhs) => ExprFrame::Div(lhs, rhs),
+        }
+    }
 }
 
 // Trait impls for `recursion` crate.
@@ -72,15 +92,19 @@ impl<'s> MappableFrame for ExprFrame<'s, PartiallyApplied> {
     }
 }
 
-impl<'a> Collapsible for &'a Expr<'a> {
+/// The frames borrow their labels from the tree, so collapsing an owned
+/// tree doesn't copy them.
+impl<'a, 's: 'a> Collapsible for &'a Expr<'s> {
     type FrameToken = ExprFrame<'a, PartiallyApplied>;
 
     fn into_frame(self) -> <Self::FrameToken as MappableFrame>::Frame<Self> {
         match self {
             Expr::Int(x) => ExprFrame::Int(*x),
             Expr::Dice(c, s) => ExprFrame::Dice(*c, *s),
+            Expr::CritDice(c, s, crits) => ExprFrame::CritDice(*c, *s, *crits),
             Expr::Not(rhs) => ExprFrame::Not(rhs.as_ref()),
-            Expr::Label(lhs, s) => ExprFrame::Label(lhs.as_ref(), *s),
+            Expr::Label(lhs, s) => ExprFrame::Label(lhs.as_ref(), Cow::Borrowed(s)),
+            Expr::List(lhs, op) => ExprFrame::List(lhs.as_ref(), *op),
             Expr::Add(lhs, rhs) => ExprFrame::Add(lhs.as_ref(), rhs.as_ref()),
             Expr::Sub(lhs, rhs) => ExprFrame::Sub(lhs.as_ref(), rhs.as_ref()),
             Expr::Mul(lhs, rhs) => ExprFrame::Mul(lhs.as_ref(), rhs.as_ref()),
@@ -89,6 +113,30 @@ impl<'a> Collapsible for &'a Expr<'a> {
     }
 }
 
+/// Collapses a tree into frames which keep the `'s` lifetime of its labels,
+/// for building a new tree or map from them. Unlike collapsing `&Expr`,
+/// this clones every owned label.
+pub(crate) struct WithLabels<'a, 's>(pub(crate) &'a Expr<'s>);
+
+impl<'a, 's: 'a> Collapsible for WithLabels<'a, 's> {
+    type FrameToken = ExprFrame<'s, PartiallyApplied>;
+
+    fn into_frame(self) -> <Self::FrameToken as MappableFrame>::Frame<Self> {
+        match self.0 {
+            Expr::Int(x) => ExprFrame::Int(*x),
+            Expr::Dice(c, s) => ExprFrame::Dice(*c, *s),
+            Expr::CritDice(c, s, crits) => ExprFrame::CritDice(*c, *s, *crits),
+            Expr::Not(rhs) => ExprFrame::Not(WithLabels(rhs)),
+            Expr::Label(lhs, s) => ExprFrame::Label(WithLabels(lhs), s.clone()),
+            Expr::List(lhs, op) => ExprFrame::List(WithLabels(lhs), *op),
+            Expr::Add(lhs, rhs) => ExprFrame::Add(WithLabels(lhs), WithLabels(rhs)),
+            Expr::Sub(lhs, rhs) => ExprFrame::Sub(WithLabels(lhs), WithLabels(rhs)),
+            Expr::Mul(lhs, rhs) => ExprFrame::Mul(WithLabels(lhs), WithLabels(rhs)),
+            Expr::Div(lhs, rhs) => ExprFrame::Div(WithLabels(lhs), WithLabels(rhs)),
+        }
+    }
+}
+
 impl<'s> Expandable for Expr<'s> {
     type FrameToken = ExprFrame<'s, PartiallyApplied>;
 
@@ -96,8 +144,10 @@ impl<'s> Expandable for Expr<'s> {
         match val {
             ExprFrame::Int(x) => Expr::Int(x),
             ExprFrame::Dice(c, s) => Expr::Dice(c, s),
+            ExprFrame::CritDice(c, s, crits) => Expr::CritDice(c, s, crits),
             ExprFrame::Not(rhs) => Expr::Not(Box::new(rhs)),
             ExprFrame::Label(lhs, s) => Expr::Label(Box::new(lhs), s),
+            ExprFrame::List(lhs, op) => Expr::List(Box::new(lhs), op),
             ExprFrame::Add(lhs, rhs) => Expr::Add(Box::new(lhs), Box::new(rhs)),
             ExprFrame::Sub(lhs, rhs) => Expr::Sub(Box::new(lhs), Box::new(rhs)),
             ExprFrame::Mul(lhs, rhs) => Expr::Mul(Box::new(lhs), Box::new(rhs)),
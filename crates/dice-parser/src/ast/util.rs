@@ -1,15 +1,18 @@
 //! `util` provides helpful primitives for constructing [`Box`]-based [`Expr`] trees.
 
+use std::borrow::Cow;
+
 use super::Expr;
 
+#[allow(clippy::should_implement_trait)]
 impl Expr<'_> {
     /// Creates an [`Expr::Int`] from `x`.
-    pub const fn int(x: i32) -> Self {
+    pub const fn int(x: i64) -> Self {
         Expr::Int(x)
     }
 
     /// Creates an [`Expr::Dice`] from `count` and `sides`.
-    pub const fn dice(count: i32, sides: i32) -> Self {
+    pub const fn dice(count: i64, sides: i64) -> Self {
         Expr::Dice(count, sides)
     }
 
@@ -41,8 +44,8 @@ impl Expr<'_> {
 
 impl<'s> Expr<'s> {
     /// Creates a [`Expr::Label`] with a [`Box`].
-    pub fn label(lhs: Self, msg: &'s str) -> Self {
-        Expr::Label(Box::new(lhs), msg)
+    pub fn label(lhs: Self, msg: impl Into<Cow<'s, str>>) -> Self {
+        Expr::Label(Box::new(lhs), msg.into())
     }
 }
 
@@ -61,7 +64,7 @@ mod tests {
                 Box::new(Expr::Int(1)),
                 Box::new(Expr::Not(Box::new(Expr::Int(5)))),
             )),
-            Box::new(Expr::Label(Box::new(Expr::Int(10)), "mult 10")),
+            Box::new(Expr::Label(Box::new(Expr::Int(10)), "mult 10".into())),
         );
 
         let tree2 = {
@@ -0,0 +1,529 @@
+//! Exact probability distributions for an [`Expr`].
+//!
+//! A [`Distribution`] counts the number of ways each outcome of an
+//! expression can happen. The counts are kept in a [`u128`] and reduced
+//! after every step, so two expressions with the same odds always produce
+//! the same [`Distribution`], regardless of the shape of their trees.
+
+use std::collections::BTreeMap;
+
+use recursion::CollapsibleExt as _;
+
+use super::{
+    ArithmeticError, check_die, checked_binop, checked_neg,
+    value::{Value, has_list},
+};
+use crate::ast::{Expr, ExprFrame, list::ListOp, precedence::BinOp};
+
+/// The largest number of distinct outcomes a [`Distribution`] may hold.
+pub const MAX_OUTCOMES: usize = 1 << 16;
+
+/// The exact distribution of outcomes for a roll.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::ast::Expr;
+/// use dice_parser::eval::distribution::Distribution;
+///
+/// let two_d6 = Distribution::of(&Expr::dice(2, 6)).unwrap();
+/// assert_eq!(two_d6.weight(7), 6);
+/// assert_eq!(two_d6.total(), 36);
+/// assert_eq!(two_d6.mean(), 7.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawDistribution"))]
+pub struct Distribution {
+    /// The number of ways each outcome can happen.
+    outcomes: BTreeMap<i64, u128>,
+    /// The sum of every weight in `outcomes`.
+    total: u128,
+}
+
+impl Distribution {
+    /// A distribution that always produces `x`.
+    pub fn constant(x: i64) -> Self {
+        Self {
+            outcomes: BTreeMap::from([(x, 1)]),
+            total: 1,
+        }
+    }
+
+    /// The distribution of rolling an `s` sided die `c` times, and summing the faces.
+    ///
+    /// The sums are `c..=c * s`, so this fails before any work is done if
+    /// there are more than [`MAX_OUTCOMES`] of them.
+    pub fn dice(c: i64, s: i64) -> Result<Self, DistributionError> {
+        check_die(c, s)?;
+        if c as u128 * (s as u128 - 1) + 1 > MAX_OUTCOMES as u128 {
+            return Err(DistributionError::TooManyOutcomes);
+        }
+        if s == 1 {
+            return Ok(Self::constant(c));
+        }
+        // this also keeps `c` below 128, since `s` is at least 2
+        let total = u32::try_from(c)
+            .ok()
+            .and_then(|c| (s as u128).checked_pow(c))
+            .ok_or(DistributionError::TooManyCombinations)?;
+
+        // add one die at a time, where the weight of each sum is the sum of the
+        // last `s` weights before it, so `weights[k]` is the weight of `k + c`
+        let s = s as usize;
+        let mut weights: Vec<u128> = vec![1];
+        for _ in 0..c {
+            let mut next = Vec::with_capacity(weights.len() + s - 1);
+            let mut window = 0;
+            for k in 0..weights.len() + s - 1 {
+                window += weights.get(k).copied().unwrap_or(0);
+                if k >= s {
+                    window -= weights[k - s];
+                }
+                next.push(window);
+            }
+            weights = next;
+        }
+
+        let outcomes = (c..).zip(weights).collect();
+        Ok(Self { outcomes, total }.reduced())
+    }
+
+    /// Computes the distribution of an [`Expr`].
+    ///
+    /// Labels are ignored, and every dice roll is treated as independent.
+    /// If there is any chance of an [`ArithmeticError`], that error is returned.
+    ///
+    /// A list operation like `4d6s` needs every combination of faces of its
+    /// pool, so those count towards [`MAX_OUTCOMES`] too.
+    pub fn of(e: &Expr<'_>) -> Result<Self, DistributionError> {
+        if has_list(e) {
+            return e
+                .try_collapse_frames(|frame| match frame {
+                    ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => Pools::dice(c, s),
+                    ExprFrame::Label(lhs, _) => Ok(lhs),
+                    ExprFrame::List(lhs, op) => lhs.list(op),
+                    frame => Self::of_frame(frame.map(Pools::into_distribution)).map(Pools::Sums),
+                })
+                .map(Pools::into_distribution);
+        }
+
+        e.try_collapse_frames(Self::of_frame)
+    }
+
+    /// Computes the distribution of a single [`ExprFrame`], from the distributions of its children.
+    fn of_frame(frame: ExprFrame<'_, Self>) -> Result<Self, DistributionError> {
+        match frame {
+            ExprFrame::Int(x) => Ok(Self::constant(x)),
+            ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => Self::dice(c, s),
+            ExprFrame::Not(rhs) => rhs.map(checked_neg),
+            ExprFrame::Label(lhs, _) => Ok(lhs),
+            ExprFrame::List(lhs, op) => Ok(Pools::Sums(lhs).list(op)?.into_distribution()),
+            ExprFrame::Add(lhs, rhs) => lhs.combine(&rhs, add),
+            ExprFrame::Sub(lhs, rhs) => lhs.combine(&rhs, |l, r| checked_binop(BinOp::Sub, l, r)),
+            ExprFrame::Mul(lhs, rhs) => lhs.combine(&rhs, |l, r| checked_binop(BinOp::Mul, l, r)),
+            ExprFrame::Div(lhs, rhs) => lhs.combine(&rhs, |l, r| checked_binop(BinOp::Div, l, r)),
+        }
+    }
+
+    /// Applies `f` to every outcome.
+    pub fn map(
+        &self,
+        mut f: impl FnMut(i64) -> Result<i64, ArithmeticError>,
+    ) -> Result<Self, DistributionError> {
+        let mut outcomes = BTreeMap::new();
+        for (&x, &weight) in &self.outcomes {
+            insert(&mut outcomes, f(x)?, weight)?;
+        }
+
+        Ok(Self {
+            outcomes,
+            total: self.total,
+        }
+        .reduced())
+    }
+
+    /// Combines every pair of outcomes from two independent distributions with `f`.
+    ///
+    /// Every pair could be a different outcome, so this fails before any work
+    /// is done if there are more than [`MAX_OUTCOMES`] pairs.
+    pub fn combine(
+        &self,
+        other: &Self,
+        mut f: impl FnMut(i64, i64) -> Result<i64, ArithmeticError>,
+    ) -> Result<Self, DistributionError> {
+        if self.outcomes.len() as u128 * other.outcomes.len() as u128 > MAX_OUTCOMES as u128 {
+            return Err(DistributionError::TooManyOutcomes);
+        }
+        let total = self
+            .total
+            .checked_mul(other.total)
+            .ok_or(DistributionError::TooManyCombinations)?;
+
+        let mut outcomes = BTreeMap::new();
+        for (&lhs, &lweight) in &self.outcomes {
+            for (&rhs, &rweight) in &other.outcomes {
+                let weight = lweight
+                    .checked_mul(rweight)
+                    .ok_or(DistributionError::TooManyCombinations)?;
+                insert(&mut outcomes, f(lhs, rhs)?, weight)?;
+            }
+        }
+
+        Ok(Self { outcomes, total }.reduced())
+    }
+
+    /// The sum of all weights, see [`Self::weight`].
+    pub fn total(&self) -> u128 {
+        self.total
+    }
+
+    /// The number of ways `x` can be rolled, out of [`Self::total`].
+    pub fn weight(&self, x: i64) -> u128 {
+        self.outcomes.get(&x).copied().unwrap_or(0)
+    }
+
+    /// The chance of rolling exactly `x`.
+    pub fn probability(&self, x: i64) -> f64 {
+        self.weight(x) as f64 / self.total as f64
+    }
+
+    /// Every possible outcome and its weight, from lowest to highest.
+    pub fn outcomes(&self) -> impl Iterator<Item = (i64, u128)> + '_ {
+        self.outcomes.iter().map(|(&x, &weight)| (x, weight))
+    }
+
+    /// The lowest possible outcome.
+    pub fn min(&self) -> i64 {
+        *self.outcomes.keys().next().expect("never empty")
+    }
+
+    /// The highest possible outcome.
+    pub fn max(&self) -> i64 {
+        *self.outcomes.keys().next_back().expect("never empty")
+    }
+
+    /// The expected value.
+    pub fn mean(&self) -> f64 {
+        self.outcomes()
+            .map(|(x, weight)| x as f64 * weight as f64)
+            .sum::<f64>()
+            / self.total as f64
+    }
+
+    /// Divides every weight by their greatest common divisor, so that
+    /// equal distributions have equal representations.
+    fn reduced(mut self) -> Self {
+        let divisor = self
+            .outcomes
+            .values()
+            .fold(self.total, |acc, &w| gcd(acc, w));
+        if divisor > 1 {
+            self.outcomes.values_mut().for_each(|w| *w /= divisor);
+            self.total /= divisor;
+        }
+        self
+    }
+}
+
+/// A [`Distribution`] which may keep the faces of a pool, for a list operation.
+enum Pools {
+    Sums(Distribution),
+    /// Every sorted list of faces that can be rolled, with the number of ways
+    /// to roll it, and the sum of every weight.
+    Faces(BTreeMap<Vec<i64>, u128>, u128),
+}
+
+impl Pools {
+    /// Every sorted list of faces from rolling an `s` sided die `c` times.
+    fn dice(c: i64, s: i64) -> Result<Self, DistributionError> {
+        check_die(c, s)?;
+        // every face is kept, so a long pool is too big even when it only has
+        // one combination, like `1000000000d1`
+        if c as u64 > MAX_OUTCOMES as u64 {
+            return Err(DistributionError::TooManyOutcomes);
+        }
+        let total = u32::try_from(c)
+            .ok()
+            .and_then(|c| (s as u128).checked_pow(c))
+            .ok_or(DistributionError::TooManyCombinations)?;
+
+        // walk every non-decreasing list of faces, like an odometer
+        let mut pools = BTreeMap::new();
+        let mut faces = vec![1; c as usize];
+        loop {
+            insert_pool(&mut pools, faces.clone(), orderings(&faces)?)?;
+
+            let Some(i) = faces.iter().rposition(|&f| f < s) else {
+                break Ok(Self::Faces(pools, total));
+            };
+            let face = faces[i] + 1;
+            faces[i..].fill(face);
+        }
+    }
+
+    /// Applies `op` to every pool, where a sum is a pool with one face.
+    fn list(self, op: ListOp) -> Result<Self, DistributionError> {
+        let (pools, total) = match self {
+            Pools::Faces(pools, total) => (pools, total),
+            Pools::Sums(d) => (
+                d.outcomes.into_iter().map(|(x, w)| (vec![x], w)).collect(),
+                d.total,
+            ),
+        };
+
+        let mut sums = BTreeMap::new();
+        let mut faces = BTreeMap::new();
+        for (pool, weight) in pools {
+            match Value::Pool(pool).list(op)? {
+                Value::Int(x) => insert(&mut sums, x, weight)?,
+                Value::Pool(pool) => insert_pool(&mut faces, pool, weight)?,
+            }
+        }
+
+        if faces.is_empty() {
+            Ok(Pools::Sums(
+                Distribution {
+                    outcomes: sums,
+                    total,
+                }
+                .reduced(),
+            ))
+        } else {
+            Ok(Pools::Faces(faces, total))
+        }
+    }
+
+    /// Sums the faces of every pool.
+    ///
+    /// There are never more sums than pools, and their weights add up to `total`,
+    /// so this can't fail.
+    fn into_distribution(self) -> Distribution {
+        match self {
+            Pools::Sums(d) => d,
+            Pools::Faces(pools, total) => {
+                let mut outcomes = BTreeMap::new();
+                for (pool, weight) in pools {
+                    *outcomes.entry(Value::Pool(pool).total()).or_insert(0) += weight;
+                }
+                Distribution { outcomes, total }.reduced()
+            }
+        }
+    }
+}
+
+/// The unchecked form of a [`Distribution`], which is validated after deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawDistribution {
+    outcomes: BTreeMap<i64, u128>,
+    total: u128,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawDistribution> for Distribution {
+    type Error = &'static str;
+
+    fn try_from(value: RawDistribution) -> Result<Self, Self::Error> {
+        let sum = value
+            .outcomes
+            .values()
+            .try_fold(0u128, |acc, &w| acc.checked_add(w));
+        if value.outcomes.is_empty() || value.outcomes.values().any(|&w| w == 0) {
+            Err("a distribution needs at least one outcome, and no zero weights")
+        } else if sum != Some(value.total) {
+            Err("a distribution's `total` must be the sum of its weights")
+        } else {
+            Ok(Self {
+                outcomes: value.outcomes,
+                total: value.total,
+            }
+            .reduced())
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistributionError {
+    #[error("{0}")]
+    Arithmetic(#[from] ArithmeticError),
+    #[error("distribution has more than {MAX_OUTCOMES} outcomes")]
+    TooManyOutcomes,
+    #[error("too many combinations to count exactly")]
+    TooManyCombinations,
+}
+
+fn insert(
+    outcomes: &mut BTreeMap<i64, u128>,
+    x: i64,
+    weight: u128,
+) -> Result<(), DistributionError> {
+    let entry = outcomes.entry(x).or_insert(0);
+    *entry = entry
+        .checked_add(weight)
+        .ok_or(DistributionError::TooManyCombinations)?;
+
+    if outcomes.len() > MAX_OUTCOMES {
+        Err(DistributionError::TooManyOutcomes)
+    } else {
+        Ok(())
+    }
+}
+
+fn insert_pool(
+    pools: &mut BTreeMap<Vec<i64>, u128>,
+    faces: Vec<i64>,
+    weight: u128,
+) -> Result<(), DistributionError> {
+    let entry = pools.entry(faces).or_insert(0);
+    *entry = entry
+        .checked_add(weight)
+        .ok_or(DistributionError::TooManyCombinations)?;
+
+    if pools.len() > MAX_OUTCOMES {
+        Err(DistributionError::TooManyOutcomes)
+    } else {
+        Ok(())
+    }
+}
+
+/// The number of ways to roll the sorted `faces` in any order.
+fn orderings(faces: &[i64]) -> Result<u128, DistributionError> {
+    let mut ways: u128 = 1;
+    let mut left = faces.len() as u128;
+    for run in faces.chunk_by(|a, b| a == b) {
+        // choose where this face's dice go, out of those `left`
+        for k in 0..run.len() as u128 {
+            ways = ways
+                .checked_mul(left - k)
+                .ok_or(DistributionError::TooManyCombinations)?
+                / (k + 1);
+        }
+        left -= run.len() as u128;
+    }
+    Ok(ways)
+}
+
+fn add(lhs: i64, rhs: i64) -> Result<i64, ArithmeticError> {
+    checked_binop(BinOp::Add, lhs, rhs)
+}
+
+pub(crate) const fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::crit::{Compare, Threshold};
+
+    #[test]
+    fn test_dice() {
+        let d = Distribution::dice(3, 6).unwrap();
+        assert_eq!(d.total(), 216);
+        assert_eq!(d.min(), 3);
+        assert_eq!(d.max(), 18);
+        assert_eq!(d.weight(10), 27);
+        assert_eq!(d.outcomes().map(|(_, w)| w).sum::<u128>(), d.total());
+
+        let d = Distribution::dice(2, 32768).unwrap();
+        assert_eq!(d.outcomes().count(), MAX_OUTCOMES - 1);
+        assert_eq!((d.weight(32769), d.total()), (32768, 1 << 30));
+        assert_eq!(
+            Distribution::dice(999_999_999, 1),
+            Ok(Distribution::constant(999_999_999))
+        );
+    }
+
+    #[test]
+    fn test_same_odds_are_equal() {
+        // 2 * 1d2 == 1d2 * 2
+        let lhs = Distribution::of(&Expr::mul(Expr::int(2), Expr::dice(1, 2))).unwrap();
+        let rhs = Distribution::of(&Expr::mul(Expr::dice(1, 2), Expr::int(2))).unwrap();
+        assert_eq!(lhs, rhs);
+
+        // 1d6 + 1d6 == 2d6
+        let lhs = Distribution::of(&Expr::add(Expr::dice(1, 6), Expr::dice(1, 6))).unwrap();
+        let rhs = Distribution::of(&Expr::dice(2, 6)).unwrap();
+        assert_eq!(lhs, rhs);
+
+        // 1d6 / 7 == 0
+        let lhs = Distribution::of(&Expr::div(Expr::dice(1, 6), Expr::int(7))).unwrap();
+        assert_eq!(lhs, Distribution::constant(0));
+    }
+
+    #[test]
+    fn test_list() {
+        // 2d6 matches: 6 doubles out of 36
+        let d = Distribution::of(&Expr::list(Expr::dice(2, 6), ListOp::Matches)).unwrap();
+        assert_eq!((d.weight(1), d.total()), (1, 6));
+
+        // 3d6s has the same odds as 3d6
+        let lhs = Distribution::of(&Expr::list(Expr::dice(3, 6), ListOp::Sort)).unwrap();
+        assert_eq!(lhs, Distribution::dice(3, 6).unwrap());
+
+        // 2d2 unique: [1], [1, 2], [1, 2] or [2]
+        let tree = Expr::add(Expr::list(Expr::dice(2, 2), ListOp::Unique), Expr::int(1));
+        let d = Distribution::of(&tree).unwrap();
+        assert_eq!(d.outcomes().collect::<Vec<_>>(), [(2, 1), (3, 1), (4, 2)]);
+
+        // count(2d6 >= 5): 4 of 36 rolls have two, and 16 have one
+        let at_least = ListOp::Count(Some(Threshold {
+            compare: Compare::Ge,
+            value: 5,
+        }));
+        let d = Distribution::of(&Expr::list(Expr::dice(2, 6), at_least)).unwrap();
+        assert_eq!((d.weight(2), d.weight(1), d.total()), (1, 4, 9));
+
+        // max(2d6) * 2 is twice the highest of two dice
+        let tree = Expr::mul(Expr::list(Expr::dice(2, 6), ListOp::Max), Expr::int(2));
+        let d = Distribution::of(&tree).unwrap();
+        assert_eq!((d.weight(12), d.total()), (11, 36));
+
+        // a sum is a pool of one face
+        let tree = Expr::list(Expr::add(Expr::dice(1, 2), Expr::dice(1, 2)), ListOp::Group);
+        assert_eq!(Distribution::of(&tree), Distribution::dice(2, 2));
+
+        assert_eq!(
+            Distribution::of(&Expr::list(Expr::dice(10, 20), ListOp::Sort)),
+            Err(DistributionError::TooManyOutcomes)
+        );
+        assert_eq!(
+            Distribution::of(&Expr::list(Expr::dice(100, 100), ListOp::Sort)),
+            Err(DistributionError::TooManyCombinations)
+        );
+        assert_eq!(
+            Distribution::of(&Expr::list(Expr::dice(999_999_999, 1), ListOp::Sort)),
+            Err(DistributionError::TooManyOutcomes)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let tree = Expr::div(Expr::int(1), Expr::sub(Expr::dice(1, 4), Expr::int(2)));
+        assert_eq!(
+            Distribution::of(&tree),
+            Err(ArithmeticError::DivideByZero(1).into())
+        );
+
+        assert_eq!(
+            Distribution::dice(200, 200),
+            Err(DistributionError::TooManyCombinations)
+        );
+
+        // both fail before any work is done
+        assert_eq!(
+            Distribution::dice(9999, 9999),
+            Err(DistributionError::TooManyOutcomes)
+        );
+        let d300 = Distribution::dice(1, 300).unwrap();
+        assert_eq!(
+            d300.combine(&d300, add),
+            Err(DistributionError::TooManyOutcomes)
+        );
+    }
+}
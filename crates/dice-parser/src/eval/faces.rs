@@ -0,0 +1,474 @@
+//! Where die faces come from.
+//!
+//! A [`DiceRoller`](super::DiceRoller) asks a [`FaceSource`] for every face it
+//! rolls. Any [`rand`] RNG is a [`FaceSource`], and this module provides a few more:
+//!
+//! - [`Fixed`] replays a list of faces, for tests and demos.
+//! - [`Max`], [`Min`] and [`Average`] always roll the same value, like for
+//!   maximized crit damage or "take the average" damage.
+//! - [`FromFn`] asks a closure, like a prompt for physical dice.
+
+use rand::{Rng, TryCryptoRng};
+
+/// Produces die faces for a [`DiceRoller`](super::DiceRoller).
+pub trait FaceSource {
+    /// Rolls one die with `sides` sides, returning a face in `1..=sides`.
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError>;
+
+    /// Rolls one die with `sides` sides for every element of `faces`.
+    ///
+    /// The default calls [`Self::face`] for each element. Override it when
+    /// many faces can be produced faster than one at a time.
+    fn fill_faces(&mut self, sides: i64, faces: &mut [i64]) -> Result<(), FaceError> {
+        for face in faces {
+            *face = self.face(sides)?;
+        }
+        Ok(())
+    }
+
+    /// Rolls `count` dice with `sides` sides, returning their sum.
+    ///
+    /// The default calls [`Self::face`] `count` times. Override it when the
+    /// sum can be produced faster, or differs from the sum of single faces.
+    ///
+    /// A [`DiceRoller`](super::DiceRoller) only calls this when `count * sides`
+    /// fits in an [`i64`], but a source may still return faces outside of
+    /// `1..=sides`, so the default fails with a [`FaceError::Overflow`] instead
+    /// of overflowing.
+    fn sum(&mut self, count: i64, sides: i64) -> Result<i64, FaceError> {
+        (0..count).try_fold(0i64, |acc, _| {
+            acc.checked_add(self.face(sides)?)
+                .ok_or(FaceError::Overflow { count, sides })
+        })
+    }
+}
+
+/// Why a [`FaceSource`] couldn't produce a face.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FaceError {
+    #[error("face `{face}` can't be rolled on a d{sides}")]
+    OutOfRange { face: i64, sides: i64 },
+    #[error("no face was given for a d{sides}")]
+    Missing { sides: i64 },
+    #[error("the faces of {count}d{sides} overflow when summed")]
+    Overflow { count: i64, sides: i64 },
+    /// An RNG kept producing words that would bias the faces, see [`MAX_REJECTIONS`].
+    #[error("the RNG produced {MAX_REJECTIONS} biased words in a row")]
+    Degenerate,
+}
+
+/// Checks that `face` can be rolled on a die with `sides` sides.
+pub(crate) fn on_die(face: i64, sides: i64) -> Result<i64, FaceError> {
+    if (1..=sides).contains(&face) {
+        Ok(face)
+    } else {
+        Err(FaceError::OutOfRange { face, sides })
+    }
+}
+
+/// Every [`rand`] RNG rolls uniform faces, packing as many dice into each
+/// `u64` it draws as the product of their sides allows.
+impl<R: TryCryptoRng + Rng> FaceSource for R {
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError> {
+        let mut face = 0;
+        batched(self, 1, sides, |f| face = f)?;
+        Ok(face)
+    }
+
+    fn fill_faces(&mut self, sides: i64, faces: &mut [i64]) -> Result<(), FaceError> {
+        let mut i = 0;
+        batched(self, faces.len() as u64, sides, |f| {
+            faces[i] = f;
+            i += 1;
+        })
+    }
+
+    fn sum(&mut self, count: i64, sides: i64) -> Result<i64, FaceError> {
+        let mut sum = Some(0i64);
+        batched(self, count as u64, sides, |f| {
+            sum = sum.and_then(|sum| sum.checked_add(f));
+        })?;
+        sum.ok_or(FaceError::Overflow { count, sides })
+    }
+}
+
+/// Lets a [`DiceRoller`](super::DiceRoller) borrow a source chosen at runtime.
+impl<'a> FaceSource for &mut (dyn FaceSource + 'a) {
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError> {
+        (**self).face(sides)
+    }
+
+    fn fill_faces(&mut self, sides: i64, faces: &mut [i64]) -> Result<(), FaceError> {
+        (**self).fill_faces(sides, faces)
+    }
+
+    fn sum(&mut self, count: i64, sides: i64) -> Result<i64, FaceError> {
+        (**self).sum(count, sides)
+    }
+}
+
+/// The largest product of sides packed into a single `u64`.
+///
+/// A word is rejected less than `product / 2^64` of the time, so this keeps
+/// rejections below 1 in 16.
+const MAX_PRODUCT: u128 = 1 << 60;
+
+/// How many biased words in a row an RNG may draw for a batch of dice before
+/// it gives up with a [`FaceError::Degenerate`].
+///
+/// When `sides` is at most `2^60`, a working RNG gets this far less
+/// than once in `2^256` tries. Larger dice get a batch of one, which is rejected
+/// less than half of the time, so that is still less than once in `2^64` tries.
+/// A broken RNG, like a mock that always returns the same word, would loop forever.
+pub const MAX_REJECTIONS: u32 = 64;
+
+/// Rolls `count` uniform dice with `sides` sides, passing each face to `f`.
+///
+/// This is Lemire's nearly divisionless method, batched: when `sides^k` fits
+/// in a word, one `u64` is multiplied by `sides` `k` times, and the high half
+/// of each product is a face. The word is rejected when the low half left
+/// over is below `2^64 mod sides^k`, exactly like a single draw below
+/// `sides^k`, so every face stays uniform. See Brackett-Rozinsky and Lemire,
+/// *Batched Ranged Random Integer Generation* (2024).
+///
+/// The dice are batched from the first one, so filling `n` faces and
+/// summing `n` faces consume the RNG the same way.
+fn batched<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: u64,
+    sides: i64,
+    mut f: impl FnMut(i64),
+) -> Result<(), FaceError> {
+    debug_assert!(sides > 0, "a die must have at least one side");
+    let n = sides as u128;
+    if n == 1 {
+        (0..count).for_each(|_| f(1));
+        return Ok(());
+    }
+
+    // the size of a full batch, and the product of its sides
+    let (mut k, mut product) = (1, n);
+    while product * n <= MAX_PRODUCT {
+        product *= n;
+        k += 1;
+    }
+    // `2^64 mod product`, which is only needed when a leftover is small
+    let mut threshold = None;
+
+    let mut digits = [0u64; 64];
+    let mut remaining = count;
+    while remaining > 0 {
+        if remaining < k {
+            k = remaining;
+            product = n.pow(k as u32);
+            threshold = None;
+        }
+
+        let digits = &mut digits[..k as usize];
+        let mut rejections = 0;
+        loop {
+            let mut word = rng.next_u64();
+            for digit in digits.iter_mut() {
+                let m = word as u128 * n;
+                *digit = (m >> 64) as u64;
+                word = m as u64;
+            }
+
+            let leftover = word as u128;
+            if leftover >= product
+                || leftover >= *threshold.get_or_insert_with(|| (1u128 << 64) % product)
+            {
+                break;
+            }
+
+            rejections += 1;
+            if rejections == MAX_REJECTIONS {
+                return Err(FaceError::Degenerate);
+            }
+        }
+
+        digits.iter().for_each(|&d| f(d as i64 + 1));
+        remaining -= k;
+    }
+    Ok(())
+}
+
+/// Replays a list of faces, starting over after the last one.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::eval::{DiceRoller, faces::Fixed};
+///
+/// let mut roller = DiceRoller::new(Fixed::new([6, 1]));
+/// assert_eq!(roller.roll(3, 6), Ok(6 + 1 + 6));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixed {
+    faces: Vec<i64>,
+    next: usize,
+}
+
+impl Fixed {
+    /// # Panics
+    ///
+    /// If `faces` is empty.
+    pub fn new(faces: impl IntoIterator<Item = i64>) -> Self {
+        let faces: Vec<i64> = faces.into_iter().collect();
+        assert!(!faces.is_empty(), "Fixed needs at least one face");
+        Self { faces, next: 0 }
+    }
+}
+
+impl FaceSource for Fixed {
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError> {
+        let face = self.faces[self.next];
+        self.next = (self.next + 1) % self.faces.len();
+        on_die(face, sides)
+    }
+}
+
+/// Every die rolls its highest face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Max;
+
+impl FaceSource for Max {
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError> {
+        Ok(sides)
+    }
+
+    fn sum(&mut self, count: i64, sides: i64) -> Result<i64, FaceError> {
+        Ok(count * sides)
+    }
+}
+
+/// Every die rolls a `1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Min;
+
+impl FaceSource for Min {
+    fn face(&mut self, _sides: i64) -> Result<i64, FaceError> {
+        Ok(1)
+    }
+
+    fn sum(&mut self, count: i64, _sides: i64) -> Result<i64, FaceError> {
+        Ok(count)
+    }
+}
+
+/// Every group of dice rolls its average, rounded down.
+///
+/// The rounding happens once per group rather than once per die, so `2d6`
+/// rolls `7` even though a single `1d6` rolls `3`.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::eval::{DiceRoller, faces::Average};
+///
+/// let mut roller = DiceRoller::new(Average);
+/// assert_eq!(roller.roll(1, 6), Ok(3));
+/// assert_eq!(roller.roll(2, 6), Ok(7));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Average;
+
+impl FaceSource for Average {
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError> {
+        self.sum(1, sides)
+    }
+
+    fn sum(&mut self, count: i64, sides: i64) -> Result<i64, FaceError> {
+        // `count * sides` fits, but `count * (sides + 1)` might not
+        Ok((count as i128 * (sides as i128 + 1) / 2) as i64)
+    }
+}
+
+/// Asks a closure for every face, see [`from_fn`].
+#[derive(Clone)]
+pub struct FromFn<F>(F);
+
+/// Asks `f` for every face, given the number of sides.
+///
+/// Returning [`None`] fails the roll with a [`FaceError::Missing`], and
+/// returning a face that isn't on the die fails with a [`FaceError::OutOfRange`].
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::ast::Expr;
+/// use dice_parser::eval::{ArithmeticError, DiceRoller, faces::{FaceError, from_fn}};
+///
+/// // what did you roll?
+/// let mut answers = vec![20, 4].into_iter();
+/// let mut roller = DiceRoller::new(from_fn(|_sides| answers.next()));
+///
+/// let tree = Expr::add(Expr::dice(1, 20), Expr::dice(1, 20));
+/// assert_eq!(roller.try_eval(&tree), Ok(24));
+/// assert_eq!(
+///     roller.try_eval(&tree),
+///     Err(ArithmeticError::Face(FaceError::Missing { sides: 20 }))
+/// );
+/// ```
+pub fn from_fn<F: FnMut(i64) -> Option<i64>>(f: F) -> FromFn<F> {
+    FromFn(f)
+}
+
+impl<F: FnMut(i64) -> Option<i64>> FaceSource for FromFn<F> {
+    fn face(&mut self, sides: i64) -> Result<i64, FaceError> {
+        let face = (self.0)(sides).ok_or(FaceError::Missing { sides })?;
+        on_die(face, sides)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::Expr, eval::DiceRoller};
+    use dice_mocks::{MockCryptoRng, ScriptError, ScriptedFaces};
+    use rand::{SeedableRng as _, rngs::StdRng};
+
+    #[test]
+    fn test_batched_is_uniform() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+
+        for sides in [2, 6, 7, 20, 1000, (1 << 31) + 1] {
+            let buckets = sides.min(1000) as usize;
+            let rolls = buckets * 1000;
+
+            let mut faces = vec![0; rolls];
+            rng.fill_faces(sides, &mut faces).unwrap();
+
+            let mut counts = vec![0.0; buckets];
+            for face in faces {
+                assert!((1..=sides).contains(&face));
+                counts[((face - 1) * buckets as i64 / sides) as usize] += 1.0;
+            }
+
+            // a chi-squared test, allowing for 6 standard deviations
+            let chi2: f64 = counts
+                .iter()
+                .map(|c| (c - 1000.0) * (c - 1000.0) / 1000.0)
+                .sum();
+            let df = buckets as f64 - 1.0;
+            assert!(
+                chi2 < df + 6.0 * (2.0 * df).sqrt(),
+                "d{sides}: chi2 = {chi2}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_batched_rejects_biased_words() {
+        // a leftover of `0` is below `2^64 mod 3 = 1`, so the first word is rejected
+        let mut roller = DiceRoller::new(MockCryptoRng::new(&[0, u64::MAX]));
+        assert_eq!(roller.roll(1, 3), Ok(3));
+
+        // an RNG which only returns rejected words gives up instead of looping
+        let mut roller = DiceRoller::new(MockCryptoRng::new(&[0]));
+        assert_eq!(roller.roll(1, 3), Err(FaceError::Degenerate.into()));
+
+        // filling and summing consume the RNG the same way
+        let seed = [0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210, 42];
+        let faces = DiceRoller::new(MockCryptoRng::new(&seed))
+            .roll_faces(100, 6)
+            .unwrap();
+        let sum = DiceRoller::new(MockCryptoRng::new(&seed)).roll(100, 6);
+        assert_eq!(Ok(faces.iter().sum()), sum);
+    }
+
+    #[test]
+    fn test_constant_sources() {
+        // 2d6 + 1d20
+        let tree = Expr::add(Expr::dice(2, 6), Expr::dice(1, 20));
+
+        assert_eq!(DiceRoller::new(Max).try_eval(&tree), Ok(32));
+        assert_eq!(DiceRoller::new(Min).try_eval(&tree), Ok(3));
+        assert_eq!(DiceRoller::new(Average).try_eval(&tree), Ok(7 + 10));
+    }
+
+    #[test]
+    fn test_fixed_out_of_range() {
+        let mut roller = DiceRoller::new(Fixed::new([4, 7]));
+
+        assert_eq!(roller.roll(1, 6), Ok(4));
+        assert_eq!(
+            roller.roll(1, 6),
+            Err(FaceError::OutOfRange { face: 7, sides: 6 }.into())
+        );
+    }
+
+    #[test]
+    fn test_default_sum_overflow() {
+        /// A source which doesn't check its faces against the die.
+        struct Unchecked;
+        impl FaceSource for Unchecked {
+            fn face(&mut self, _sides: i64) -> Result<i64, FaceError> {
+                Ok(i64::MAX)
+            }
+        }
+
+        assert_eq!(Unchecked.sum(1, 6), Ok(i64::MAX));
+        assert_eq!(
+            Unchecked.sum(2, 6),
+            Err(FaceError::Overflow { count: 2, sides: 6 })
+        );
+
+        // an RNG is only checked by a `DiceRoller`, not when called directly
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            rng.sum(64, i64::MAX),
+            Err(FaceError::Overflow {
+                count: 64,
+                sides: i64::MAX
+            })
+        );
+    }
+
+    #[test]
+    fn test_scripted_faces() {
+        // 2d6 + 1d20, where the right operand rolls first
+        let tree = Expr::add(Expr::dice(2, 6), Expr::dice(1, 20));
+
+        let mut script = ScriptedFaces::new().then(20, [17]).then(6, [3, 4]);
+        let mut roller = DiceRoller::new(from_fn(|sides| script.face(sides)));
+        assert_eq!(roller.try_eval(&tree), Ok(17 + 3 + 4));
+        assert_eq!(script.finish(), Ok(()));
+
+        // a roll after the last face fails, and is reported
+        let mut script = ScriptedFaces::new().then(20, [17]).then(6, [3]);
+        let mut roller = DiceRoller::new(from_fn(|sides| script.face(sides)));
+        assert_eq!(
+            roller.try_eval(&tree),
+            Err(FaceError::Missing { sides: 6 }.into())
+        );
+        assert_eq!(
+            script.finish(),
+            Err(ScriptError {
+                unused: vec![],
+                exhausted: vec![6],
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "roll #1 was scripted for a d20, but rolled a d6")]
+    fn test_scripted_faces_wrong_die() {
+        let mut script = ScriptedFaces::new().then(20, [17]);
+        let _ = DiceRoller::new(from_fn(|sides| script.face(sides))).roll(1, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "face `7` can't be rolled on a d6")]
+    fn test_scripted_faces_off_the_die() {
+        let _ = ScriptedFaces::new().then(6, [7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "scripted faces were never rolled: 4 on a d6")]
+    fn test_scripted_faces_unused() {
+        let mut script = ScriptedFaces::new().then(6, [3, 4]);
+        let _ = DiceRoller::new(from_fn(|sides| script.face(sides))).roll(1, 6);
+    }
+}
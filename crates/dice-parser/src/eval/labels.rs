@@ -0,0 +1,312 @@
+//! Splitting the total of a roll by its labels, like `2d6[slashing] + 1d8[fire]`.
+//!
+//! See [`DiceRoller::try_eval_labeled`], which returns a [`Breakdown`].
+//!
+//! # Semantics
+//!
+//! Every value is split into an unlabeled part and a part for each label,
+//! which always add up to its total.
+//!
+//! - A label claims the unlabeled part of its sub-expression. Nested labels
+//!   keep what they already claimed, so in `(1d6[fire] + 1d4)[magic]` the `1d6`
+//!   counts as `fire` and only the `1d4` counts as `magic`.
+//! - `+`, `-` and negation apply to every part separately, and parts with the
+//!   same label are summed.
+//! - `*` scales every part of one side by the total of the other side, as long
+//!   as the other side has no labels. In `(1d6[fire] + 2) * 2`, `fire` is twice the `1d6`.
+//! - `/` divides every part of the left side by the total of the right side,
+//!   as long as the right side has no labels. Each part is truncated, and whatever
+//!   is lost to rounding is unlabeled.
+//! - When both sides of a `*`, or the right side of a `/`, have labels, there
+//!   is no sensible way to split the result, so the whole result is unlabeled.
+//! - A list operation which keeps the total, like `4d6s`, keeps every part.
+//!   One which changes it, like `6d6[fire] unique`, keeps the label only when
+//!   a single label claimed the whole pool. Otherwise, the result is unlabeled.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use recursion::CollapsibleExt as _;
+
+use super::{
+    ArithmeticError, DiceRoller, checked_binop, checked_neg,
+    faces::FaceSource,
+    value::{Value, has_list},
+};
+use crate::ast::{Expr, ExprFrame, precedence::BinOp, recurse::WithLabels};
+
+/// The result of [`DiceRoller::try_eval_labeled`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Breakdown<'s> {
+    /// The grand total, the same as [`DiceRoller::try_eval`] would return.
+    pub total: i64,
+    /// The part of the total which isn't claimed by any label.
+    pub unlabeled: i64,
+    /// The part of the total claimed by each label.
+    pub labels: BTreeMap<Cow<'s, str>, i64>,
+}
+
+impl<'s> Breakdown<'s> {
+    /// A value without any labels.
+    fn from_total(x: i64) -> Self {
+        Self {
+            total: x,
+            unlabeled: x,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Applies `f` to the total and every part.
+    fn map(
+        self,
+        mut f: impl FnMut(i64) -> Result<i64, ArithmeticError>,
+    ) -> Result<Self, ArithmeticError> {
+        Ok(Self {
+            total: f(self.total)?,
+            unlabeled: f(self.unlabeled)?,
+            labels: self
+                .labels
+                .into_iter()
+                .map(|(label, x)| Ok((label, f(x)?)))
+                .collect::<Result<_, ArithmeticError>>()?,
+        })
+    }
+
+    /// Applies `op` to the totals and the matching parts of both sides.
+    fn zip(self, op: BinOp, rhs: Self) -> Result<Self, ArithmeticError> {
+        let mut labels = self.labels;
+        for (label, x) in rhs.labels {
+            let lhs = labels.remove(&label).unwrap_or(0);
+            labels.insert(label, checked_binop(op, lhs, x)?);
+        }
+
+        Ok(Self {
+            total: checked_binop(op, self.total, rhs.total)?,
+            unlabeled: checked_binop(op, self.unlabeled, rhs.unlabeled)?,
+            labels,
+        })
+    }
+
+    /// Takes the unlabeled part of this value, and adds it to `label`.
+    fn claim(mut self, label: Cow<'s, str>) -> Result<Self, ArithmeticError> {
+        let unlabeled = std::mem::take(&mut self.unlabeled);
+        let x = self.labels.entry(label).or_insert(0);
+        *x = checked_binop(BinOp::Add, *x, unlabeled)?;
+        Ok(self)
+    }
+
+    /// Changes the total after a list operation, keeping a label that claimed all of it.
+    fn retotal(self, total: i64) -> Self {
+        if total == self.total {
+            self
+        } else if self.unlabeled == 0 && self.labels.len() == 1 {
+            let label = self.labels.into_keys().next().expect("there is one label");
+            Self {
+                total,
+                unlabeled: 0,
+                labels: BTreeMap::from([(label, total)]),
+            }
+        } else {
+            Self::from_total(total)
+        }
+    }
+
+    /// Applies an arithmetic [`ExprFrame`] to its already split operands.
+    fn combine(frame: ExprFrame<'s, Self>) -> Result<Self, ArithmeticError> {
+        match frame {
+            ExprFrame::Int(x) => Ok(Breakdown::from_total(x)),
+            ExprFrame::Not(rhs) => rhs.map(checked_neg),
+            ExprFrame::Add(lhs, rhs) => lhs.zip(BinOp::Add, rhs),
+            ExprFrame::Sub(lhs, rhs) => lhs.zip(BinOp::Sub, rhs),
+            ExprFrame::Mul(lhs, rhs) => {
+                if rhs.labels.is_empty() {
+                    lhs.map(|x| checked_binop(BinOp::Mul, x, rhs.total))
+                } else if lhs.labels.is_empty() {
+                    rhs.map(|x| checked_binop(BinOp::Mul, lhs.total, x))
+                } else {
+                    Ok(Breakdown::from_total(checked_binop(
+                        BinOp::Mul,
+                        lhs.total,
+                        rhs.total,
+                    )?))
+                }
+            }
+            ExprFrame::Div(lhs, rhs) => {
+                let total = checked_binop(BinOp::Div, lhs.total, rhs.total)?;
+                if rhs.labels.is_empty() {
+                    let labels = lhs
+                        .labels
+                        .into_iter()
+                        .map(|(label, x)| Ok((label, checked_binop(BinOp::Div, x, rhs.total)?)))
+                        .collect::<Result<BTreeMap<_, _>, ArithmeticError>>()?;
+                    let unlabeled = labels
+                        .values()
+                        .try_fold(total, |acc, &x| checked_binop(BinOp::Sub, acc, x))?;
+
+                    Ok(Breakdown {
+                        total,
+                        unlabeled,
+                        labels,
+                    })
+                } else {
+                    Ok(Breakdown::from_total(total))
+                }
+            }
+            ExprFrame::Dice(..)
+            | ExprFrame::CritDice(..)
+            | ExprFrame::Label(..)
+            | ExprFrame::List(..) => {
+                unreachable!("rolls, labels and lists are split by `try_eval_labeled`")
+            }
+        }
+    }
+}
+
+impl<R: FaceSource> DiceRoller<R> {
+    /// Like [`Self::try_eval`], but also splits the total by label.
+    ///
+    /// See the [module documentation](self) for how labels are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::Expr;
+    /// use dice_parser::eval::DiceRoller;
+    ///
+    /// // (2d6[slashing] + 3) * 2 + 1d1[fire]
+    /// let tree = Expr::add(
+    ///     Expr::mul(
+    ///         Expr::add(Expr::label(Expr::dice(2, 6), "slashing"), Expr::int(3)),
+    ///         Expr::int(2),
+    ///     ),
+    ///     Expr::label(Expr::dice(1, 1), "fire"),
+    /// );
+    ///
+    /// let breakdown = DiceRoller::default().try_eval_labeled(&tree).unwrap();
+    /// assert_eq!(breakdown.labels["fire"], 1);
+    /// assert_eq!(breakdown.unlabeled, 6);
+    /// assert_eq!(breakdown.total, breakdown.labels["slashing"] + 6 + 1);
+    /// ```
+    pub fn try_eval_labeled<'s>(&mut self, e: &Expr<'s>) -> Result<Breakdown<'s>, ArithmeticError> {
+        self.limits.check(e)?;
+        // faces are only kept for list operations, like `try_eval` does
+        let keep_faces = has_list(e);
+        let (breakdown, _) = WithLabels(e).try_collapse_frames(|frame| match frame {
+            ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) if keep_faces => {
+                let faces = self.roll_faces(c, s)?;
+                Ok::<_, ArithmeticError>((Breakdown::from_total(faces.iter().sum()), Some(faces)))
+            }
+            ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => {
+                Ok((Breakdown::from_total(self.roll(c, s)?), None))
+            }
+            ExprFrame::Label((lhs, faces), label) => Ok((lhs.claim(label)?, faces)),
+            ExprFrame::List((lhs, faces), op) => {
+                let value = match faces {
+                    Some(faces) => Value::Pool(faces),
+                    None => Value::Int(lhs.total),
+                }
+                .list(op)?;
+                let total = value.total();
+                let faces = match value {
+                    Value::Int(_) => None,
+                    Value::Pool(faces) => Some(faces),
+                };
+                Ok((lhs.retotal(total), faces))
+            }
+            frame => Ok((Breakdown::combine(frame.map(|(part, _)| part))?, None)),
+        })?;
+        Ok(breakdown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::list::ListOp;
+    use Expr as e;
+    use dice_mocks::MockCryptoRng;
+
+    fn breakdown<'s>(e: &Expr<'s>) -> Breakdown<'s> {
+        let mut dr = DiceRoller::new(MockCryptoRng::default());
+        let breakdown = dr.try_eval_labeled(e).unwrap();
+
+        let mut dr = DiceRoller::new(MockCryptoRng::default());
+        assert_eq!(dr.try_eval(e), Ok(breakdown.total));
+        assert_eq!(
+            breakdown.total,
+            breakdown.unlabeled + breakdown.labels.values().sum::<i64>()
+        );
+        breakdown
+    }
+
+    #[test]
+    fn test_nested_labels() {
+        // (4[fire] + 3 - 2[fire])[magic] + 1[magic]
+        let tree = e::add(
+            e::label(
+                e::sub(
+                    e::add(e::label(e::int(4), "fire"), e::int(3)),
+                    e::label(e::int(2), "fire"),
+                ),
+                "magic",
+            ),
+            e::label(e::int(1), "magic"),
+        );
+
+        let b = breakdown(&tree);
+        assert_eq!(b.total, 6);
+        assert_eq!(b.unlabeled, 0);
+        assert_eq!(b.labels["fire"], 2);
+        assert_eq!(b.labels["magic"], 4);
+    }
+
+    #[test]
+    fn test_mul_div() {
+        // (7[fire] + 7[cold] + 1) / 2 * 3
+        let tree = e::mul(
+            e::div(
+                e::add(
+                    e::add(e::label(e::int(7), "fire"), e::label(e::int(7), "cold")),
+                    e::int(1),
+                ),
+                e::int(2),
+            ),
+            e::int(3),
+        );
+
+        let b = breakdown(&tree);
+        assert_eq!(b.total, 21);
+        assert_eq!(b.labels["fire"], 9);
+        assert_eq!(b.labels["cold"], 9);
+        assert_eq!(b.unlabeled, 3);
+
+        // 2[a] * 3[b]
+        let tree = e::mul(e::label(e::int(2), "a"), e::label(e::int(3), "b"));
+        let b = breakdown(&tree);
+        assert_eq!(b.unlabeled, 6);
+        assert!(b.labels.is_empty());
+    }
+
+    #[test]
+    fn test_list() {
+        // 6d6[fire] unique + 6d6[cold]s + (1 + 2[acid]) unique
+        let tree = e::add(
+            e::add(
+                e::list(e::label(e::dice(6, 6), "fire"), ListOp::Unique),
+                e::list(e::label(e::dice(6, 6), "cold"), ListOp::Sort),
+            ),
+            e::list(
+                e::add(e::int(1), e::label(e::int(2), "acid")),
+                ListOp::Unique,
+            ),
+        );
+
+        let b = breakdown(&tree);
+        assert_eq!(b.total, 1 + 6 + 3);
+        assert_eq!(b.labels["fire"], 1);
+        assert_eq!(b.labels["cold"], 6);
+        // `3` is a pool with one face, so its total doesn't change
+        assert_eq!(b.labels["acid"], 2);
+        assert_eq!(b.unlabeled, 1);
+    }
+}
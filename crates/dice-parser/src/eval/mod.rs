@@ -1,24 +1,63 @@
 //! Helper functions for computing the result of an [`crate::ast::Expr`].
 //!
-//! There are only two methods in this module:
-//! 1. The [`eval`] function, which panics on division by zero.
-//! 2. The [`try_eval`] function, which returns a [`DivideByZeroError`].
+//! A [`DiceRoller`] rolls a tree in one of these ways:
+//! - [`DiceRoller::eval`] returns the total, and panics on division by zero.
+//! - [`DiceRoller::try_eval`] returns an [`ArithmeticError`] instead, and checks
+//!   the tree against the roller's [`Limits`] first.
+//! - [`DiceRoller::try_eval_value`] keeps the faces of a pool, see [`value`].
+//! - [`DiceRoller::try_eval_labeled`] splits the total by its labels, see [`labels`].
+//! - [`DiceRoller::try_eval_info`] keeps every face and checks it for critical
+//!   hits, see [`visualize`].
+//!
+//! [`DiceRoller::roll`] and [`DiceRoller::roll_faces`] roll a single term like `2d6`.
+//! Faces come from a [`faces::FaceSource`], which is any [`rand`] RNG by default.
+//!
+//! To compute the odds of every outcome instead of rolling, see [`distribution`],
+//! or [`simulate`] when there are too many outcomes to compute exactly.
+//! To ask how likely a comparison like `1d20 + 7 >= 16` is, see [`query`].
+//!
+//! A die term only keeps its faces when a list operation like the `s` in `4d6s`
+//! reads them, see [`value`]. Otherwise, it's summed right away.
 
-mod visualize;
+pub mod distribution;
+pub mod faces;
+pub mod labels;
+pub mod query;
+pub mod simulate;
+pub mod value;
+pub mod visualize;
 
-use rand::{Rng, TryCryptoRng, rngs::ThreadRng};
+use faces::{FaceError, FaceSource};
+use rand::rngs::ThreadRng;
 use recursion::CollapsibleExt as _;
+use value::{Value, has_list};
 
-use crate::ast::{Expr, ExprFrame, precedence::Op};
+use crate::{
+    ast::{
+        Expr, ExprFrame,
+        list::ListOp,
+        precedence::{BinOp, Op},
+    },
+    limits::{LimitError, Limits},
+};
 
-/// A container for a [`rand::CryptoRng`], which handles
+/// A container for a [`FaceSource`], like a [`rand::CryptoRng`], which handles
 /// all requests for dice rolls and expression evaluation.
 #[derive(Debug)]
-pub struct DiceRoller<R: TryCryptoRng = ThreadRng> {
+pub struct DiceRoller<R: FaceSource = ThreadRng> {
     rng: R,
+    limits: Limits,
 }
 
-impl<R: TryCryptoRng + Rng> DiceRoller<R> {
+impl<R: FaceSource> DiceRoller<R> {
+    /// The roller checks the [`Limits::default`], which are meant for untrusted
+    /// input. To roll without any limits, see [`Self::with_limits`].
+    ///
+    /// Earlier versions rolled without any limits, so a tree or a roll that
+    /// used to succeed, like one with more than [`Limits::max_dice`] dice, now
+    /// fails with an [`ArithmeticError::Limit`]. Trusted input keeps the old
+    /// behaviour with `with_limits(Limits::UNLIMITED)`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -26,11 +65,49 @@ impl<R: TryCryptoRng + Rng> DiceRoller<R> {
     /// let mut roller = DiceRoller::new(rand::rng());
     /// // or simply use DiceRoller::default()
     ///
-    /// println!("{}", roller.roll(1, 20));
+    /// println!("{:?}", roller.roll(1, 20));
     /// ```
     #[must_use]
     pub fn new(rng: R) -> Self {
-        Self { rng }
+        Self {
+            rng,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Makes every `try_eval*` method check its tree against `limits` before
+    /// rolling anything, and fail with an [`ArithmeticError::Limit`] instead.
+    /// [`Self::roll`] and [`Self::roll_faces`] check their count against
+    /// [`Limits::max_dice`] too.
+    ///
+    /// Trusted input can opt out of every limit with [`Limits::UNLIMITED`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::Expr;
+    /// use dice_parser::eval::{ArithmeticError, DiceRoller};
+    /// use dice_parser::limits::{LimitError, Limits};
+    ///
+    /// let tree = Expr::mul(Expr::dice(9999, 9999), Expr::dice(9999, 9999));
+    ///
+    /// assert_eq!(
+    ///     DiceRoller::default().try_eval(&tree),
+    ///     Err(ArithmeticError::Limit(LimitError::Dice { dice: 19998, max: 10_000 }))
+    /// );
+    ///
+    /// let mut roller = DiceRoller::default().with_limits(Limits::UNLIMITED);
+    /// assert!(roller.try_eval(&tree).is_ok());
+    /// ```
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The [`Limits`] checked by every `try_eval*` method.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
     }
 
     /// Rolls an `s` sided die `c` times.
@@ -38,72 +115,91 @@ impl<R: TryCryptoRng + Rng> DiceRoller<R> {
     /// # Examples
     ///
     /// ```
-    /// use dice_parser::eval::DiceRoller;
+    /// use dice_parser::eval::{ArithmeticError, DiceRoller};
     ///
     /// let mut dice_roller = DiceRoller::default();
     /// let result = dice_roller.roll(1, 1); // 1d1
-    /// assert_eq!(result, 1);
+    /// assert_eq!(result, Ok(1));
     ///
-    /// let result = dice_roller.roll(2, 20); // 2d20
+    /// let result = dice_roller.roll(2, 20).unwrap(); // 2d20
     /// assert!(result >= 2 && result <= 40);
+    ///
+    /// assert_eq!(dice_roller.roll(0, 20), Err(ArithmeticError::ZeroCount(20)));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// More dice than [`Limits::max_dice`] are an [`ArithmeticError::Limit`],
+    /// checked before any of them are rolled.
+    ///
+    /// Besides dice that can't be rolled, this returns an [`ArithmeticError::Overflow`]
+    /// when the highest possible roll, `c * s`, doesn't fit in an [`i64`]. This
+    /// doesn't depend on the faces that would have been rolled.
+    ///
+    /// ```
+    /// use dice_parser::ast::precedence::Op;
+    /// use dice_parser::eval::{ArithmeticError, DiceRoller};
+    ///
+    /// assert_eq!(
+    ///     DiceRoller::default().roll(2, i64::MAX),
+    ///     Err(ArithmeticError::Overflow { lhs: Some(2), op: Op::Mul, rhs: Some(i64::MAX) })
+    /// );
     /// ```
     #[inline(always)]
-    pub fn roll(&mut self, c: i32, s: i32) -> i64 {
-        (0..c).fold(0 as i64, |acc, _| acc + self.rng.random_range(1..=s) as i64)
+    pub fn roll(&mut self, c: i64, s: i64) -> Result<i64, ArithmeticError> {
+        check_die(c, s)?;
+        self.check_count(c)?;
+        checked_binop(BinOp::Mul, c, s)?;
+        Ok(self.rng.sum(c, s)?)
+    }
+
+    /// Like [`Self::roll`], but returns every face instead of their sum.
+    ///
+    /// Unless the [`FaceSource`] overrides [`FaceSource::sum`], the faces add
+    /// up to what [`Self::roll`] would return from the same state.
+    pub fn roll_faces(&mut self, c: i64, s: i64) -> Result<Vec<i64>, ArithmeticError> {
+        check_die(c, s)?;
+        self.check_count(c)?;
+        checked_binop(BinOp::Mul, c, s)?;
+
+        let mut faces = vec![0; c as usize];
+        self.rng.fill_faces(s, &mut faces)?;
+        Ok(faces)
+    }
+
+    /// Checks a positive count of dice against [`Limits::max_dice`].
+    fn check_count(&self, c: i64) -> Result<(), LimitError> {
+        let dice = c as u64;
+        if dice > self.limits.max_dice {
+            Err(LimitError::Dice {
+                dice,
+                max: self.limits.max_dice,
+            })
+        } else {
+            Ok(())
+        }
     }
 
     /// This is a non-panicking version of [`Self::eval`].
     pub fn try_eval(&mut self, e: &Expr) -> Result<i64, ArithmeticError> {
+        self.limits.check(e)?;
+        if has_list(e) {
+            return Ok(self.eval_value(e)?.total());
+        }
+
         e.try_collapse_frames(|frame| match frame {
-            ExprFrame::Int(x) => Ok(x as i64),
-            ExprFrame::Dice(c, s) => {
-                if c <= 0 || s <= 0 {
-                    Err(ArithmeticError::NegativeDie(c, s))
-                } else {
-                    Ok(self.roll(c, s))
-                }
-            }
-            ExprFrame::Not(rhs) => Ok(-rhs),
+            ExprFrame::Int(x) => Ok(x),
+            ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => self.roll(c, s),
+            ExprFrame::Not(rhs) => checked_neg(rhs),
             ExprFrame::Label(lhs, _) => Ok(lhs),
-            ExprFrame::Add(lhs, rhs) => lhs.checked_add(rhs).map_or_else(
-                || {
-                    Err(ArithmeticError::Overflow {
-                        lhs: Some(lhs),
-                        op: Op::Add,
-                        rhs: Some(rhs),
-                    })
-                },
-                |x| Ok(x),
-            ),
-            ExprFrame::Sub(lhs, rhs) => lhs.checked_sub(rhs).map_or_else(
-                || {
-                    Err(ArithmeticError::Overflow {
-                        lhs: Some(lhs),
-                        op: Op::Sub,
-                        rhs: Some(rhs),
-                    })
-                },
-                |x| Ok(x),
-            ),
-            ExprFrame::Mul(lhs, rhs) => lhs.checked_mul(rhs).map_or_else(
-                || {
-                    Err(ArithmeticError::Overflow {
-                        lhs: Some(lhs),
-                        op: Op::Mul,
-                        rhs: Some(rhs),
-                    })
-                },
-                |x| Ok(x),
-            ),
-            ExprFrame::Div(lhs, rhs) => {
-                if rhs != 0 {
-                    Ok(lhs / rhs)
-                } else {
-                    Err(ArithmeticError::DivideByZero(lhs))
-                }
-            }
+            ExprFrame::List(..) => unreachable!("trees with a list are evaluated by value"),
+            ExprFrame::Add(lhs, rhs) => checked_binop(BinOp::Add, lhs, rhs),
+            ExprFrame::Sub(lhs, rhs) => checked_binop(BinOp::Sub, lhs, rhs),
+            ExprFrame::Mul(lhs, rhs) => checked_binop(BinOp::Mul, lhs, rhs),
+            ExprFrame::Div(lhs, rhs) => checked_binop(BinOp::Div, lhs, rhs),
         })
     }
+
     /// Evaluates a parse tree and returns its result.
     ///
     /// For a version that does not panic on divide-by-zero,
@@ -111,16 +207,38 @@ impl<R: TryCryptoRng + Rng> DiceRoller<R> {
     ///
     /// # Panics
     ///
-    /// There is no check for division by zero. It may also
+    /// There is no check for division by zero, and it panics if a die can't
+    /// be rolled, like a `0d6` or a [`FaceError`]. It may also
     /// panic if the program runs out of memory, but the function
     /// is stack safe as it is not recursively defined.
     /// ```
     pub fn eval(&mut self, e: &Expr) -> i64 {
+        if has_list(e) {
+            return e
+                .collapse_frames(|frame: ExprFrame<'_, Value>| match frame {
+                    ExprFrame::Int(x) => Value::Int(x),
+                    ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => {
+                        Value::Pool(self.roll_faces(c, s).expect("die should be rollable"))
+                    }
+                    ExprFrame::Not(rhs) => Value::Int(-rhs.total()),
+                    ExprFrame::Label(lhs, _) => lhs,
+                    ExprFrame::List(lhs, op) => lhs.list(op).expect("a rolled pool is never empty"),
+                    ExprFrame::Add(lhs, rhs) => Value::Int(lhs.total() + rhs.total()),
+                    ExprFrame::Sub(lhs, rhs) => Value::Int(lhs.total() - rhs.total()),
+                    ExprFrame::Mul(lhs, rhs) => Value::Int(lhs.total() * rhs.total()),
+                    ExprFrame::Div(lhs, rhs) => Value::Int(lhs.total() / rhs.total()),
+                })
+                .total();
+        }
+
         e.collapse_frames(|frame: ExprFrame<'_, i64>| match frame {
-            ExprFrame::Int(x) => x as i64,
-            ExprFrame::Dice(c, s) => self.roll(c, s),
+            ExprFrame::Int(x) => x,
+            ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => {
+                self.roll(c, s).expect("die should be rollable")
+            }
             ExprFrame::Not(rhs) => -rhs,
             ExprFrame::Label(lhs, _) => lhs,
+            ExprFrame::List(..) => unreachable!("trees with a list are evaluated by value"),
             ExprFrame::Add(lhs, rhs) => lhs + rhs,
             ExprFrame::Sub(lhs, rhs) => lhs - rhs,
             ExprFrame::Mul(lhs, rhs) => lhs * rhs,
@@ -132,14 +250,58 @@ impl<R: TryCryptoRng + Rng> DiceRoller<R> {
 impl Default for DiceRoller {
     /// We use the [`rand::rng`] by default.
     fn default() -> Self {
-        Self { rng: rand::rng() }
+        Self::new(rand::rng())
     }
 }
 
+/// Checks that `c` dice with `s` sides can be rolled.
+pub(crate) fn check_die(c: i64, s: i64) -> Result<(), ArithmeticError> {
+    if c == 0 {
+        Err(ArithmeticError::ZeroCount(s))
+    } else if s == 0 {
+        Err(ArithmeticError::ZeroSides(c))
+    } else if c < 0 || s < 0 {
+        Err(ArithmeticError::NegativeDie(c, s))
+    } else {
+        Ok(())
+    }
+}
+
+/// Negates `x`, returning an [`ArithmeticError`] instead of overflowing.
+pub(crate) fn checked_neg(x: i64) -> Result<i64, ArithmeticError> {
+    x.checked_neg().ok_or(ArithmeticError::Overflow {
+        lhs: None,
+        op: Op::Not,
+        rhs: Some(x),
+    })
+}
+
+/// Applies `op`, returning an [`ArithmeticError`] instead of overflowing or panicking.
+pub(crate) fn checked_binop(op: BinOp, lhs: i64, rhs: i64) -> Result<i64, ArithmeticError> {
+    let result = match op {
+        BinOp::Add => lhs.checked_add(rhs),
+        BinOp::Sub => lhs.checked_sub(rhs),
+        BinOp::Mul => lhs.checked_mul(rhs),
+        BinOp::Div if rhs == 0 => return Err(ArithmeticError::DivideByZero(lhs)),
+        BinOp::Div => lhs.checked_div(rhs),
+    };
+
+    result.ok_or(ArithmeticError::Overflow {
+        lhs: Some(lhs),
+        op: op.as_op(),
+        rhs: Some(rhs),
+    })
+}
+
 #[derive(thiserror::Error, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ArithmeticError {
     #[error("tried to roll a negative die: `{0}d{1}`")]
-    NegativeDie(i32, i32),
+    NegativeDie(i64, i64),
+    #[error("tried to roll zero dice: `0d{0}`")]
+    ZeroCount(i64),
+    #[error("tried to roll a zero-sided die: `{0}d0`")]
+    ZeroSides(i64),
     #[error("tried to divide `{0}` by 0")]
     DivideByZero(i64),
     #[error("overflow performing `{op}` on lhs: `{lhs:?}` and rhs: `{rhs:?}`")]
@@ -148,10 +310,17 @@ pub enum ArithmeticError {
         op: Op,
         rhs: Option<i64>,
     },
+    #[error(transparent)]
+    Limit(#[from] LimitError),
+    #[error(transparent)]
+    Face(#[from] FaceError),
+    #[error("tried to apply `{}` to an empty pool", .0.as_str())]
+    EmptyPool(ListOp),
 }
 
 /// Represents what we attempted to divide by zero.
 #[derive(thiserror::Error, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[error("tried to divide {0} by 0")]
 pub struct DivideByZeroError(i64);
 impl DivideByZeroError {
@@ -170,8 +339,8 @@ mod test {
         let mock_crypto_rng = MockCryptoRng::default();
         let mut dr = DiceRoller::new(mock_crypto_rng);
 
-        assert_eq!(dr.roll(1, 20), 1);
-        assert_eq!(dr.roll(100, 20), 100);
+        assert_eq!(dr.roll(1, 20), Ok(1));
+        assert_eq!(dr.roll(100, 20), Ok(100));
     }
 
     #[test]
@@ -193,6 +362,12 @@ mod test {
         let mut dr = DiceRoller::new(mock_rng);
 
         assert_eq!(dr.try_eval(&tree), Ok(21));
+
+        // 17 + (-4 * (0 - (1 + 2 + 3 + 4 + 5))), where the right operand rolls first
+        let mut script = ScriptedFaces::new().then(20, [1, 2, 3, 4, 5, 17]);
+        let mut dr = DiceRoller::new(faces::from_fn(|sides| script.face(sides)));
+
+        assert_eq!(dr.try_eval(&tree), Ok(77));
     }
 
     #[test]
@@ -204,6 +379,56 @@ mod test {
         assert_eq!(dr.try_eval(&tree), Err(ArithmeticError::DivideByZero(1)))
     }
 
+    #[test]
+    fn test_try_eval_overflow() {
+        let mut dr = DiceRoller::new(MockCryptoRng::default());
+
+        // -(i64::MIN)
+        let tree = Expr::not(Expr::int(i64::MIN));
+        assert_eq!(
+            dr.try_eval(&tree),
+            Err(ArithmeticError::Overflow {
+                lhs: None,
+                op: Op::Not,
+                rhs: Some(i64::MIN)
+            })
+        );
+
+        // i64::MIN / -1
+        let tree = Expr::div(Expr::int(i64::MIN), Expr::int(-1));
+        assert!(matches!(
+            dr.try_eval(&tree),
+            Err(ArithmeticError::Overflow { op: Op::Div, .. })
+        ));
+
+        // 3d(i64::MAX / 2)
+        let tree = Expr::dice(3, i64::MAX / 2);
+        assert!(matches!(
+            dr.try_eval(&tree),
+            Err(ArithmeticError::Overflow { op: Op::Mul, .. })
+        ));
+    }
+
+    #[test]
+    fn test_roll_limits() {
+        let mut dr = DiceRoller::new(MockCryptoRng::default());
+        let err = Err(ArithmeticError::Limit(LimitError::Dice {
+            dice: 999_999_999,
+            max: 10_000,
+        }));
+
+        // like `999999999d999999999 s`, which is within the default `max_int`
+        assert_eq!(dr.roll_faces(999_999_999, 999_999_999), err);
+        assert_eq!(dr.roll(999_999_999, 999_999_999).map(|_| vec![]), err);
+        assert_eq!(
+            dr.roll_faces(10_000, 6).map(|faces| faces.len()),
+            Ok(10_000)
+        );
+
+        let mut dr = dr.with_limits(Limits::UNLIMITED);
+        assert_eq!(dr.roll(20_000, 1), Ok(20_000));
+    }
+
     #[test]
     #[should_panic(expected = "attempt to divide by zero")]
     fn test_eval_div_zero_panics() {
@@ -0,0 +1,278 @@
+//! Asking how likely a comparison is, like `1d20 + 7 >= 16` or `2d6 > 1d12`.
+//!
+//! A [`Query`] compares two independent expressions. It is answered exactly
+//! with a [`Distribution`] when possible, and estimated with a [`Simulation`]
+//! otherwise. Either way, the [`Answer`] states how far off it may be.
+
+use std::fmt::Display;
+
+use super::{
+    ArithmeticError,
+    distribution::{Distribution, DistributionError, gcd},
+    simulate::Simulation,
+};
+use crate::ast::{Expr, crit::Compare};
+
+/// A comparison between two independent expressions.
+///
+/// Both sides are rolled separately, so in `1d6 >= 1d6` the two dice are different.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::ast::{Expr, crit::Compare};
+/// use dice_parser::eval::query::{Method, Query};
+///
+/// // 1d20 + 7 >= 16
+/// let query = Query {
+///     lhs: Expr::add(Expr::dice(1, 20), Expr::int(7)),
+///     compare: Compare::Ge,
+///     rhs: Expr::int(16),
+/// };
+///
+/// let answer = query.exact().unwrap();
+/// assert_eq!(answer.probability, 0.6);
+/// assert_eq!(answer.method, Method::Exact { numerator: 3, denominator: 5 });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<'s> {
+    pub lhs: Expr<'s>,
+    pub compare: Compare,
+    pub rhs: Expr<'s>,
+}
+
+/// The answer to a [`Query`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Answer {
+    /// The probability that the comparison holds.
+    pub probability: f64,
+    /// How far [`Self::probability`] may be from the true probability, see [`Method`].
+    pub error: f64,
+    pub method: Method,
+}
+
+/// How an [`Answer`] was found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Method {
+    /// The probability is exactly `numerator / denominator`, in lowest terms.
+    /// The [`Answer::error`] is `0`.
+    Exact { numerator: u128, denominator: u128 },
+    /// The probability was estimated from `trials` simulated rolls. The true
+    /// probability is within [`Answer::error`] of the estimate with a
+    /// probability of at least `confidence`.
+    Simulated { trials: u64, confidence: f64 },
+}
+
+impl Query<'_> {
+    /// Answers the query exactly, or fails if either side has too many outcomes.
+    ///
+    /// Each die and each pair of outcomes is checked against
+    /// [`MAX_OUTCOMES`](super::distribution::MAX_OUTCOMES) before it's
+    /// counted, so a side like `9999d9999` fails straight away.
+    pub fn exact(&self) -> Result<Answer, DistributionError> {
+        let lhs = Distribution::of(&self.lhs)?;
+        let rhs = Distribution::of(&self.rhs)?;
+
+        // the weights of the right-hand outcomes, and the sum of every weight before each
+        let mut below = 0;
+        let rhs: Vec<(i64, u128, u128)> = rhs
+            .outcomes()
+            .map(|(y, weight)| {
+                let before = below;
+                below += weight;
+                (y, weight, before)
+            })
+            .collect();
+        let total = below;
+
+        let mut numerator: u128 = 0;
+        for (x, weight) in lhs.outcomes() {
+            let (eq, lt) = match rhs.binary_search_by_key(&x, |&(y, ..)| y) {
+                Ok(i) => (rhs[i].1, rhs[i].2),
+                Err(i) => (0, rhs.get(i).map_or(total, |&(.., before)| before)),
+            };
+            let gt = total - lt - eq;
+
+            // the weight of every `y` where `x OP y` holds
+            let matching = match self.compare {
+                Compare::Eq => eq,
+                Compare::Lt => gt,
+                Compare::Le => gt + eq,
+                Compare::Gt => lt,
+                Compare::Ge => lt + eq,
+            };
+            numerator = weight
+                .checked_mul(matching)
+                .and_then(|w| numerator.checked_add(w))
+                .ok_or(DistributionError::TooManyCombinations)?;
+        }
+
+        let denominator = lhs
+            .total()
+            .checked_mul(total)
+            .ok_or(DistributionError::TooManyCombinations)?;
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+
+        Ok(Answer {
+            probability: numerator as f64 / denominator as f64,
+            error: 0.0,
+            method: Method::Exact {
+                numerator,
+                denominator,
+            },
+        })
+    }
+
+    /// Estimates the answer by rolling both sides in every trial, and counting
+    /// how often they compare this way.
+    ///
+    /// The error is a Hoeffding bound, which holds for any expression:
+    /// the estimate is within `sqrt(ln(2 / (1 - confidence)) / (2 * trials))`
+    /// of the true probability with a probability of at least `confidence`.
+    ///
+    /// # Panics
+    ///
+    /// If `confidence` is not strictly between `0` and `1`.
+    pub fn simulate(&self, sim: &Simulation, confidence: f64) -> Result<Answer, ArithmeticError> {
+        assert!(
+            0.0 < confidence && confidence < 1.0,
+            "confidence must be in (0, 1), got {confidence}"
+        );
+
+        sim.limits.check(&self.lhs)?;
+        sim.limits.check(&self.rhs)?;
+        // each trial is `1` when the comparison holds, and `0` otherwise
+        let summary = sim.run_trials(|roller| {
+            let lhs = roller.try_eval(&self.lhs)?;
+            let rhs = roller.try_eval(&self.rhs)?;
+            Ok(self.compare.holds(lhs, rhs).into())
+        })?;
+        let hits = summary.histogram.get(&1).copied().unwrap_or(0);
+
+        let trials = summary.trials;
+        Ok(Answer {
+            probability: hits as f64 / trials as f64,
+            error: ((2.0 / (1.0 - confidence)).ln() / (2.0 * trials as f64)).sqrt(),
+            method: Method::Simulated { trials, confidence },
+        })
+    }
+
+    /// Answers the query exactly if possible, and falls back to [`Self::simulate`]
+    /// when either side has too many outcomes to count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::{Expr, crit::Compare};
+    /// use dice_parser::eval::{query::{Method, Query}, simulate::Simulation};
+    ///
+    /// // 1000d1000 > 500500, its mean
+    /// let query = Query {
+    ///     lhs: Expr::dice(1000, 1000),
+    ///     compare: Compare::Gt,
+    ///     rhs: Expr::int(500_500),
+    /// };
+    ///
+    /// let answer = query.answer(&Simulation::new(10_000, 1), 0.999).unwrap();
+    /// assert!(matches!(answer.method, Method::Simulated { trials: 10_000, .. }));
+    /// assert!((answer.probability - 0.5).abs() < answer.error);
+    /// ```
+    pub fn answer(&self, sim: &Simulation, confidence: f64) -> Result<Answer, ArithmeticError> {
+        match self.exact() {
+            Ok(answer) => Ok(answer),
+            Err(DistributionError::Arithmetic(err)) => Err(err),
+            Err(DistributionError::TooManyOutcomes | DistributionError::TooManyCombinations) => {
+                self.simulate(sim, confidence)
+            }
+        }
+    }
+}
+
+impl Display for Query<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.lhs, self.compare.as_str(), self.rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Expr as e;
+
+    fn query<'s>(lhs: Expr<'s>, compare: Compare, rhs: Expr<'s>) -> Query<'s> {
+        Query { lhs, compare, rhs }
+    }
+
+    #[test]
+    fn test_exact_versus() {
+        // 2d6 > 8 and 1d12 > 8
+        let two_d6 = query(e::dice(2, 6), Compare::Gt, e::int(8))
+            .exact()
+            .unwrap();
+        let one_d12 = query(e::dice(1, 12), Compare::Gt, e::int(8))
+            .exact()
+            .unwrap();
+        assert_eq!(two_d6.probability, 10.0 / 36.0);
+        assert_eq!(one_d12.probability, 4.0 / 12.0);
+
+        // 1d6 against 1d6, by every comparison
+        let d6 = || e::dice(1, 6);
+        let exact = |compare| match query(d6(), compare, d6()).exact().unwrap().method {
+            Method::Exact {
+                numerator,
+                denominator,
+            } => (numerator, denominator),
+            method => panic!("expected an exact answer, got {method:?}"),
+        };
+        assert_eq!(exact(Compare::Eq), (1, 6));
+        assert_eq!(exact(Compare::Lt), (5, 12));
+        assert_eq!(exact(Compare::Le), (7, 12));
+        assert_eq!(exact(Compare::Gt), (5, 12));
+        assert_eq!(exact(Compare::Ge), (7, 12));
+    }
+
+    #[test]
+    fn test_simulate_within_error() {
+        // 2d6 > 1d12
+        let query = query(e::dice(2, 6), Compare::Gt, e::dice(1, 12));
+        let exact = query.exact().unwrap();
+        let estimate = query.simulate(&Simulation::new(20_000, 3), 0.999).unwrap();
+
+        assert!((exact.probability - estimate.probability).abs() < estimate.error);
+        assert!(estimate.error < 0.02);
+    }
+
+    #[test]
+    fn test_simulate_extreme_sides() {
+        // `lhs - rhs` would overflow, but each side fits in an `i64`
+        let query = query(e::int(i64::MAX), Compare::Gt, e::int(-1));
+        let estimate = query.simulate(&Simulation::new(100, 0), 0.99).unwrap();
+        assert_eq!(estimate.probability, 1.0);
+
+        let query = Query {
+            compare: Compare::Lt,
+            ..query
+        };
+        let estimate = query.simulate(&Simulation::new(100, 0), 0.99).unwrap();
+        assert_eq!(estimate.probability, 0.0);
+    }
+
+    #[test]
+    fn test_answer_falls_back_quickly() {
+        // `9999d9999` has about 10^8 outcomes, which are never counted
+        let query = query(e::dice(9999, 9999), Compare::Gt, e::int(5));
+        assert_eq!(query.exact(), Err(DistributionError::TooManyOutcomes));
+
+        let start = std::time::Instant::now();
+        let answer = query.answer(&Simulation::new(100, 0), 0.99).unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert!(matches!(
+            answer.method,
+            Method::Simulated { trials: 100, .. }
+        ));
+        assert_eq!(answer.probability, 1.0);
+    }
+}
@@ -0,0 +1,318 @@
+//! Estimating the outcomes of a roll by evaluating it many times.
+//!
+//! When the exact [`Distribution`](super::distribution::Distribution) has too
+//! many outcomes to compute, a [`Simulation`] rolls the expression over and
+//! over instead, and returns a [`Summary`] of the results.
+//!
+//! # Reproducibility
+//!
+//! The trials are split into fixed-size chunks, and every chunk rolls with its
+//! own RNG, derived from the seed and the index of the chunk. The threads only
+//! decide *who* rolls a chunk, never *what* it rolls, so the same seed gives
+//! the same [`Summary`] no matter how many threads run.
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+use rand::{SeedableRng as _, rngs::StdRng};
+
+use super::{ArithmeticError, DiceRoller};
+use crate::{ast::Expr, limits::Limits};
+
+/// The number of trials rolled with the same RNG.
+const CHUNK: u64 = 1024;
+
+/// Settings for a Monte Carlo simulation of an [`Expr`].
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::ast::Expr;
+/// use dice_parser::eval::simulate::Simulation;
+///
+/// // 2d6
+/// let tree = Expr::dice(2, 6);
+/// let summary = Simulation::new(100_000, 42).run(&tree).unwrap();
+///
+/// let (low, high) = summary.confidence_interval(0.999);
+/// assert!(low < 7.0 && 7.0 < high);
+/// assert_eq!(summary.histogram.keys().copied().collect::<Vec<_>>(), (2..=12).collect::<Vec<_>>());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Simulation {
+    /// How many times the expression is evaluated.
+    pub trials: u64,
+    /// The seed every RNG is derived from.
+    pub seed: u64,
+    /// How many threads roll at once.
+    pub threads: NonZeroUsize,
+    /// Checked once before the first trial, see [`Limits::check`], and by
+    /// every roll after that.
+    pub limits: Limits,
+}
+
+impl Simulation {
+    /// A simulation of `trials` evaluations, using every available thread.
+    #[must_use]
+    pub fn new(trials: u64, seed: u64) -> Self {
+        Self {
+            trials,
+            seed,
+            threads: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            limits: Limits::UNLIMITED,
+        }
+    }
+
+    /// Runs the simulation on `threads` threads instead.
+    #[must_use]
+    pub fn with_threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Checks the expression against `limits` before running.
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Evaluates `e` [`Self::trials`] times, and summarizes the results.
+    ///
+    /// If any trial fails, this returns the error of the earliest failing
+    /// chunk, so errors are just as reproducible as results.
+    pub fn run(&self, e: &Expr) -> Result<Summary, ArithmeticError> {
+        self.limits.check(e)?;
+        self.run_trials(|roller| roller.try_eval(e))
+    }
+
+    /// Runs `trial` [`Self::trials`] times, and summarizes what it returns.
+    ///
+    /// Every trial is passed the roller of its chunk, like [`Self::run`] does.
+    pub(crate) fn run_trials(
+        &self,
+        trial: impl Fn(&mut DiceRoller<StdRng>) -> Result<i64, ArithmeticError> + Sync,
+    ) -> Result<Summary, ArithmeticError> {
+        let chunks = self.trials.div_ceil(CHUNK);
+        let next = AtomicU64::new(0);
+        let failed = AtomicBool::new(false);
+        let results = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads.get() {
+                scope.spawn(|| {
+                    // chunks are claimed in order, so once a chunk fails, every
+                    // earlier chunk has already been claimed and will finish
+                    while !failed.load(Ordering::Relaxed) {
+                        let chunk = next.fetch_add(1, Ordering::Relaxed);
+                        if chunk >= chunks {
+                            break;
+                        }
+
+                        let result = self.run_chunk(&trial, chunk);
+                        failed.fetch_or(result.is_err(), Ordering::Relaxed);
+                        results.lock().unwrap().push((chunk, result));
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(chunk, _)| *chunk);
+
+        let mut histogram = BTreeMap::new();
+        for (_, result) in results {
+            for (x, n) in result? {
+                *histogram.entry(x).or_insert(0) += n;
+            }
+        }
+        Ok(Summary::from_histogram(histogram))
+    }
+
+    /// Rolls every trial of a single chunk, with the RNG of that chunk.
+    fn run_chunk(
+        &self,
+        trial: impl Fn(&mut DiceRoller<StdRng>) -> Result<i64, ArithmeticError>,
+        chunk: u64,
+    ) -> Result<BTreeMap<i64, u64>, ArithmeticError> {
+        let mut seed = [0u8; 32];
+        seed[..8].copy_from_slice(&self.seed.to_le_bytes());
+        seed[8..16].copy_from_slice(&chunk.to_le_bytes());
+        let mut roller = DiceRoller::new(StdRng::from_seed(seed)).with_limits(self.limits);
+
+        let trials = CHUNK.min(self.trials - chunk * CHUNK);
+        let mut histogram = BTreeMap::new();
+        for _ in 0..trials {
+            *histogram.entry(trial(&mut roller)?).or_insert(0) += 1;
+        }
+        Ok(histogram)
+    }
+}
+
+/// The results of a [`Simulation`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    /// How many times the expression was evaluated.
+    pub trials: u64,
+    /// How many times each outcome was rolled.
+    pub histogram: BTreeMap<i64, u64>,
+    /// The sample mean, or [`f64::NAN`] without any trials.
+    pub mean: f64,
+    /// The sample standard deviation, or [`f64::NAN`] with fewer than two trials.
+    pub std_dev: f64,
+}
+
+impl Summary {
+    fn from_histogram(histogram: BTreeMap<i64, u64>) -> Self {
+        let trials: u64 = histogram.values().sum();
+
+        // the sum is exact, so the mean doesn't depend on the order of the trials
+        let sum: i128 = histogram.iter().map(|(&x, &n)| x as i128 * n as i128).sum();
+        let mean = sum as f64 / trials as f64;
+        let squares: f64 = histogram
+            .iter()
+            .map(|(&x, &n)| n as f64 * (x as f64 - mean).powi(2))
+            .sum();
+        let std_dev = (squares / (trials as f64 - 1.0)).sqrt();
+
+        Self {
+            trials,
+            histogram,
+            mean,
+            std_dev: if trials < 2 { f64::NAN } else { std_dev },
+        }
+    }
+
+    /// The fraction of trials which rolled `x`.
+    pub fn frequency(&self, x: i64) -> f64 {
+        self.histogram.get(&x).copied().unwrap_or(0) as f64 / self.trials as f64
+    }
+
+    /// The standard error of the mean.
+    pub fn std_error(&self) -> f64 {
+        self.std_dev / (self.trials as f64).sqrt()
+    }
+
+    /// A two-sided confidence interval for the true mean, like `0.95` for 95%.
+    ///
+    /// This uses the normal approximation, which is accurate for the
+    /// thousands of trials a simulation usually runs.
+    ///
+    /// # Panics
+    ///
+    /// If `confidence` is not strictly between `0` and `1`.
+    pub fn confidence_interval(&self, confidence: f64) -> (f64, f64) {
+        assert!(
+            0.0 < confidence && confidence < 1.0,
+            "confidence must be in (0, 1), got {confidence}"
+        );
+        let z = normal_quantile(0.5 + confidence / 2.0);
+        let margin = z * self.std_error();
+        (self.mean - margin, self.mean + margin)
+    }
+}
+
+/// The inverse of the standard normal CDF, for `p` in `(0, 1)`.
+///
+/// This is Peter Acklam's rational approximation, with a relative error
+/// below `1.15e-9`.
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Expr as e;
+
+    #[test]
+    fn test_same_seed_any_threads() {
+        // 3d6 * 2 - 1d4
+        let tree = e::sub(e::mul(e::dice(3, 6), e::int(2)), e::dice(1, 4));
+
+        let summaries: Vec<Summary> = [1, 3, 8]
+            .into_iter()
+            .map(|threads| {
+                Simulation::new(10_000, 7)
+                    .with_threads(NonZeroUsize::new(threads).unwrap())
+                    .run(&tree)
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(summaries[0], summaries[1]);
+        assert_eq!(summaries[0], summaries[2]);
+        assert_eq!(summaries[0].trials, 10_000);
+        assert_ne!(summaries[0], Simulation::new(10_000, 8).run(&tree).unwrap());
+    }
+
+    #[test]
+    fn test_errors_are_reproducible() {
+        // 100 / (1d2 - 1)
+        let tree = e::div(e::int(100), e::sub(e::dice(1, 2), e::int(1)));
+
+        for threads in [1, 4] {
+            let sim = Simulation::new(5000, 1).with_threads(NonZeroUsize::new(threads).unwrap());
+            assert_eq!(sim.run(&tree), Err(ArithmeticError::DivideByZero(100)));
+        }
+    }
+
+    #[test]
+    fn test_normal_quantile() {
+        assert!(normal_quantile(0.5).abs() < 1e-9);
+        assert!((normal_quantile(0.975) - 1.959_963_985).abs() < 1e-8);
+        assert!((normal_quantile(0.005) + 2.575_829_304).abs() < 1e-8);
+    }
+}
@@ -0,0 +1,248 @@
+//! The [`Value`] of an expression, which keeps the faces of a roll as a pool.
+//!
+//! A die term like `4d6` is a pool of its faces, which a [`ListOp`] like
+//! `max(4d6)` or `4d6s` can read. Anywhere else, like either side of a `+`,
+//! a pool is implicitly converted to the sum of its faces with [`Value::total`].
+//! So `3d6 * 2` is `sum(3d6) * 2`, and every expression without a [`ListOp`]
+//! has the same result as if each die term was summed right away.
+//!
+//! That's what [`DiceRoller::try_eval`] does when the tree has no [`Expr::List`],
+//! since keeping every face isn't free. See [`DiceRoller::try_eval_value`] to
+//! get the [`Value`] itself.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use recursion::CollapsibleExt as _;
+
+use super::{ArithmeticError, DiceRoller, checked_binop, checked_neg, faces::FaceSource};
+use crate::ast::{Expr, ExprFrame, list::ListOp, precedence::BinOp};
+
+/// The result of an expression: a number, or a pool of faces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Int(i64),
+    /// The faces of a die term, or of a [`ListOp`] on one.
+    ///
+    /// A rolled pool is never empty. One that is built by hand or deserialized
+    /// can be, see [`Value::list`].
+    Pool(Vec<i64>),
+}
+
+impl Value {
+    /// The value as a number, where a pool is the sum of its faces.
+    ///
+    /// This is the implicit conversion used by every operation other than a
+    /// [`ListOp`]. It never overflows, since a pool is never larger than the
+    /// die term it came from, and rolling that checks for overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::eval::value::Value;
+    ///
+    /// assert_eq!(Value::Pool(vec![3, 1, 4]).total(), 8);
+    /// assert_eq!(i64::from(Value::Int(-2)), -2);
+    /// ```
+    pub fn total(&self) -> i64 {
+        match self {
+            Value::Int(x) => *x,
+            Value::Pool(faces) => faces.iter().sum(),
+        }
+    }
+
+    /// Applies `op` to the faces, where a number is a pool with one face.
+    ///
+    /// # Errors
+    ///
+    /// [`ListOp::Group`], [`ListOp::Max`] and [`ListOp::Min`] need at least one
+    /// face, so they fail with an [`ArithmeticError::EmptyPool`] on an empty pool.
+    ///
+    /// ```
+    /// use dice_parser::ast::list::ListOp;
+    /// use dice_parser::eval::{ArithmeticError, value::Value};
+    ///
+    /// assert_eq!(Value::Pool(vec![]).list(ListOp::Count(None)), Ok(Value::Int(0)));
+    /// assert_eq!(
+    ///     Value::Pool(vec![]).list(ListOp::Max),
+    ///     Err(ArithmeticError::EmptyPool(ListOp::Max))
+    /// );
+    /// ```
+    pub fn list(self, op: ListOp) -> Result<Self, ArithmeticError> {
+        let mut faces = match self {
+            Value::Int(x) => vec![x],
+            Value::Pool(faces) => faces,
+        };
+        faces.sort_unstable();
+        if faces.is_empty() && matches!(op, ListOp::Group | ListOp::Max | ListOp::Min) {
+            return Err(ArithmeticError::EmptyPool(op));
+        }
+
+        Ok(match op {
+            ListOp::Sort => Value::Pool(faces),
+            ListOp::Unique => {
+                faces.dedup();
+                Value::Pool(faces)
+            }
+            ListOp::Matches => {
+                let matches = counts(&faces).values().filter(|&&n| n > 1).count();
+                Value::Int(matches as i64)
+            }
+            ListOp::Group => {
+                // `max_by_key` keeps the last maximum, which is the highest face
+                let (face, n) = counts(&faces)
+                    .into_iter()
+                    .max_by_key(|&(_, n)| n)
+                    .expect("checked to be non-empty");
+                Value::Pool(vec![face; n])
+            }
+            ListOp::Sum => Value::Int(faces.iter().sum()),
+            ListOp::Max => Value::Int(*faces.last().expect("checked to be non-empty")),
+            ListOp::Min => Value::Int(faces[0]),
+            ListOp::Count(None) => Value::Int(faces.len() as i64),
+            ListOp::Count(Some(t)) => {
+                Value::Int(faces.iter().filter(|&&f| t.matches(f)).count() as i64)
+            }
+        })
+    }
+}
+
+impl From<Value> for i64 {
+    /// See [`Value::total`].
+    fn from(value: Value) -> Self {
+        value.total()
+    }
+}
+
+impl Display for Value {
+    /// A number, or a pool like `[1, 3, 4]`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(x) => write!(f, "{}", x),
+            Value::Pool(faces) => write!(f, "{:?}", faces),
+        }
+    }
+}
+
+impl<R: FaceSource> DiceRoller<R> {
+    /// Like [`Self::try_eval`], but keeps the faces of the result if it's a pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::{Expr, list::ListOp};
+    /// use dice_parser::eval::{DiceRoller, value::Value};
+    ///
+    /// let mut roller = DiceRoller::default();
+    /// assert_eq!(roller.try_eval_value(&Expr::dice(3, 1)), Ok(Value::Pool(vec![1, 1, 1])));
+    ///
+    /// // max(3d1) + 1
+    /// let tree = Expr::add(Expr::list(Expr::dice(3, 1), ListOp::Max), Expr::int(1));
+    /// assert_eq!(roller.try_eval_value(&tree), Ok(Value::Int(2)));
+    /// ```
+    pub fn try_eval_value(&mut self, e: &Expr) -> Result<Value, ArithmeticError> {
+        self.limits.check(e)?;
+        self.eval_value(e)
+    }
+
+    /// Like [`Self::try_eval_value`], without checking the limits.
+    pub(crate) fn eval_value(&mut self, e: &Expr) -> Result<Value, ArithmeticError> {
+        let int = |x| Ok(Value::Int(x?));
+        e.try_collapse_frames(|frame| match frame {
+            ExprFrame::Int(x) => Ok(Value::Int(x)),
+            ExprFrame::Dice(c, s) | ExprFrame::CritDice(c, s, _) => {
+                Ok(Value::Pool(self.roll_faces(c, s)?))
+            }
+            ExprFrame::Not(rhs) => int(checked_neg(rhs.total())),
+            ExprFrame::Label(lhs, _) => Ok(lhs),
+            ExprFrame::List(lhs, op) => lhs.list(op),
+            ExprFrame::Add(lhs, rhs) => int(checked_binop(BinOp::Add, lhs.total(), rhs.total())),
+            ExprFrame::Sub(lhs, rhs) => int(checked_binop(BinOp::Sub, lhs.total(), rhs.total())),
+            ExprFrame::Mul(lhs, rhs) => int(checked_binop(BinOp::Mul, lhs.total(), rhs.total())),
+            ExprFrame::Div(lhs, rhs) => int(checked_binop(BinOp::Div, lhs.total(), rhs.total())),
+        })
+    }
+}
+
+/// How many times each face appears.
+fn counts(faces: &[i64]) -> BTreeMap<i64, usize> {
+    let mut counts = BTreeMap::new();
+    for &face in faces {
+        *counts.entry(face).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Returns `true` if the tree has an [`Expr::List`], so faces need to be kept.
+pub(crate) fn has_list(e: &Expr<'_>) -> bool {
+    e.collapse_frames(|frame| match frame {
+        ExprFrame::List(..) => true,
+        ExprFrame::Int(_) | ExprFrame::Dice(..) | ExprFrame::CritDice(..) => false,
+        ExprFrame::Not(x) | ExprFrame::Label(x, _) => x,
+        ExprFrame::Add(x, y)
+        | ExprFrame::Sub(x, y)
+        | ExprFrame::Mul(x, y)
+        | ExprFrame::Div(x, y) => x || y,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::crit::{Compare, Threshold};
+
+    #[test]
+    fn test_list() {
+        let pool = || Value::Pool(vec![5, 2, 5, 1, 2, 5, 3]);
+
+        assert_eq!(
+            pool().list(ListOp::Sort),
+            Ok(Value::Pool(vec![1, 2, 2, 3, 5, 5, 5]))
+        );
+        assert_eq!(
+            pool().list(ListOp::Unique),
+            Ok(Value::Pool(vec![1, 2, 3, 5]))
+        );
+        assert_eq!(pool().list(ListOp::Matches), Ok(Value::Int(2)));
+        assert_eq!(pool().list(ListOp::Group), Ok(Value::Pool(vec![5, 5, 5])));
+        assert_eq!(pool().list(ListOp::Group).unwrap().total(), 15);
+
+        // ties go to the highest face
+        let tied = Value::Pool(vec![4, 1, 4, 1]);
+        assert_eq!(tied.list(ListOp::Group), Ok(Value::Pool(vec![4, 4])));
+
+        // a number is a pool of one face
+        assert_eq!(
+            Value::Int(-3).list(ListOp::Unique),
+            Ok(Value::Pool(vec![-3]))
+        );
+        assert_eq!(Value::Int(-3).list(ListOp::Matches), Ok(Value::Int(0)));
+
+        // only operations which pick a face fail on an empty pool
+        let empty = || Value::Pool(vec![]);
+        assert_eq!(empty().list(ListOp::Sort), Ok(empty()));
+        assert_eq!(empty().list(ListOp::Matches), Ok(Value::Int(0)));
+        assert_eq!(empty().list(ListOp::Sum), Ok(Value::Int(0)));
+        for op in [ListOp::Group, ListOp::Max, ListOp::Min] {
+            assert_eq!(empty().list(op), Err(ArithmeticError::EmptyPool(op)));
+        }
+    }
+
+    #[test]
+    fn test_functions() {
+        let pool = || Value::Pool(vec![7, 2, 10, 1, 7]);
+        let at_least = |value| {
+            ListOp::Count(Some(Threshold {
+                compare: Compare::Ge,
+                value,
+            }))
+        };
+
+        assert_eq!(pool().list(ListOp::Sum), Ok(Value::Int(27)));
+        assert_eq!(pool().list(ListOp::Max), Ok(Value::Int(10)));
+        assert_eq!(pool().list(ListOp::Min), Ok(Value::Int(1)));
+        assert_eq!(pool().list(ListOp::Count(None)), Ok(Value::Int(5)));
+        assert_eq!(pool().list(at_least(7)), Ok(Value::Int(3)));
+        assert_eq!(Value::Int(6).list(at_least(7)), Ok(Value::Int(0)));
+    }
+}
@@ -1,39 +1,207 @@
-use rand::{Rng, RngCore};
+//! Evaluating an [`Expr`] while keeping every face that was rolled.
+//!
+//! See [`DiceRoller::try_eval_info`], which returns an [`Info`].
 
-use super::ArithmeticError;
+use recursion::CollapsibleExt as _;
 
-pub enum Info {
-    Dice {
-        count: i32,
-        sides: i32,
+use super::{
+    ArithmeticError, DiceRoller, checked_binop, checked_neg,
+    faces::FaceSource,
+    value::{Value, has_list},
+};
+use crate::ast::{Expr, ExprFrame, crit::Crits, precedence::BinOp};
 
-        rolls: Vec<(i32, i64)>,
-        sum: i64,
-    },
-    Other(i64),
+/// The faces rolled for a single die term, like the `2d6` in `2d6 + 3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiceInfo {
+    pub count: i64,
+    pub sides: i64,
+    /// The critical ranges of an [`Expr::CritDice`], or the default for an [`Expr::Dice`].
+    pub crits: Crits,
+
+    /// Every face, in the order they were rolled.
+    pub faces: Vec<i64>,
+    pub sum: i64,
+    /// The number of faces in the critical success range.
+    pub successes: usize,
+    /// The number of faces in the critical failure range.
+    pub failures: usize,
 }
 
-impl Info {
-    pub fn roll<R: Rng>(rng: &mut R, count: i32, sides: i32) -> Result<Self, ArithmeticError> {
-        if count <= 0 || sides <= 0 {
-            Err(ArithmeticError::NegativeDie(count, sides))
-        } else {
-            let (rolls, sum) = {
-                let rolls: Vec<(i32, i64)> = (0..count)
-                    .map(|i| (i, rng.random_range(1..=sides) as i64))
-                    .collect();
-
-                let sum = rolls.iter().fold(0, |acc, (_, s)| acc + s);
-
-                (rolls, sum)
-            };
-
-            Ok(Info::Dice {
-                count,
-                sides,
-                rolls,
-                sum,
-            })
+impl DiceInfo {
+    fn new(count: i64, sides: i64, crits: Crits, faces: Vec<i64>) -> Self {
+        Self {
+            count,
+            sides,
+            crits,
+            sum: faces.iter().sum(),
+            successes: faces.iter().filter(|&&f| crits.is_success(f)).count(),
+            failures: faces.iter().filter(|&&f| crits.is_failure(f)).count(),
+            faces,
         }
     }
 }
+
+/// The result of [`DiceRoller::try_eval_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Info {
+    /// The grand total, the same as [`DiceRoller::try_eval`] would return.
+    pub total: i64,
+    /// Every die term, from left to right in the expression. The faces are
+    /// in the order they were rolled, even after a list operation like `4d6s`.
+    pub dice: Vec<DiceInfo>,
+}
+
+impl Info {
+    /// Returns `true` if any die rolled a critical success.
+    pub fn is_crit_success(&self) -> bool {
+        self.dice.iter().any(|d| d.successes > 0)
+    }
+
+    /// Returns `true` if any die rolled a critical failure.
+    pub fn is_crit_failure(&self) -> bool {
+        self.dice.iter().any(|d| d.failures > 0)
+    }
+
+    fn combine(mut self, rhs: Self, total: i64) -> Self {
+        self.dice.extend(rhs.dice);
+        self.total = total;
+        self
+    }
+}
+
+impl<R: FaceSource> DiceRoller<R> {
+    /// Like [`Self::try_eval`], but keeps every face and checks it for critical
+    /// successes and failures.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::{Expr, crit::{Compare, Crits, Threshold}};
+    /// use dice_parser::eval::DiceRoller;
+    ///
+    /// // 1d1cs1 + 2d6
+    /// let crits = Crits {
+    ///     success: Some(Threshold { compare: Compare::Eq, value: 1 }),
+    ///     failure: None,
+    /// };
+    /// let tree = Expr::add(Expr::CritDice(1, 1, crits), Expr::dice(2, 6));
+    ///
+    /// let info = DiceRoller::default().try_eval_info(&tree).unwrap();
+    /// assert!(info.is_crit_success());
+    /// assert_eq!(info.dice[0].faces, [1]);
+    /// assert_eq!(info.dice[1].faces.len(), 2);
+    /// ```
+    pub fn try_eval_info(&mut self, e: &Expr) -> Result<Info, ArithmeticError> {
+        self.limits.check(e)?;
+        // the faces of a pool are only kept for list operations, see `Value`
+        let keep_faces = has_list(e);
+        let (info, _) = e.try_collapse_frames(|frame| match frame {
+            ExprFrame::Int(x) => Ok((
+                Info {
+                    total: x,
+                    dice: vec![],
+                },
+                None,
+            )),
+            ExprFrame::Dice(c, s) => self.roll_info(c, s, Crits::default(), keep_faces),
+            ExprFrame::CritDice(c, s, crits) => self.roll_info(c, s, crits, keep_faces),
+            ExprFrame::Not((rhs, _)) => Ok((
+                Info {
+                    total: checked_neg(rhs.total)?,
+                    ..rhs
+                },
+                None,
+            )),
+            ExprFrame::Label(lhs, _) => Ok(lhs),
+            ExprFrame::List((lhs, faces), op) => {
+                let value = match faces {
+                    Some(faces) => Value::Pool(faces),
+                    None => Value::Int(lhs.total),
+                }
+                .list(op)?;
+                let info = Info {
+                    total: value.total(),
+                    ..lhs
+                };
+                match value {
+                    Value::Int(_) => Ok((info, None)),
+                    Value::Pool(faces) => Ok((info, Some(faces))),
+                }
+            }
+            ExprFrame::Add((lhs, _), (rhs, _)) => {
+                let total = checked_binop(BinOp::Add, lhs.total, rhs.total)?;
+                Ok((lhs.combine(rhs, total), None))
+            }
+            ExprFrame::Sub((lhs, _), (rhs, _)) => {
+                let total = checked_binop(BinOp::Sub, lhs.total, rhs.total)?;
+                Ok((lhs.combine(rhs, total), None))
+            }
+            ExprFrame::Mul((lhs, _), (rhs, _)) => {
+                let total = checked_binop(BinOp::Mul, lhs.total, rhs.total)?;
+                Ok((lhs.combine(rhs, total), None))
+            }
+            ExprFrame::Div((lhs, _), (rhs, _)) => {
+                let total = checked_binop(BinOp::Div, lhs.total, rhs.total)?;
+                Ok((lhs.combine(rhs, total), None))
+            }
+        })?;
+        Ok(info)
+    }
+
+    fn roll_info(
+        &mut self,
+        c: i64,
+        s: i64,
+        crits: Crits,
+        keep_faces: bool,
+    ) -> Result<(Info, Option<Vec<i64>>), ArithmeticError> {
+        let dice = DiceInfo::new(c, s, crits, self.roll_faces(c, s)?);
+        let faces = keep_faces.then(|| dice.faces.clone());
+        Ok((
+            Info {
+                total: dice.sum,
+                dice: vec![dice],
+            },
+            faces,
+        ))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::crit::{Compare, Threshold};
+    use Expr as e;
+    use dice_mocks::MockCryptoRng;
+
+    #[test]
+    fn test_info_matches_try_eval() {
+        // 2d20cf<3 * 3 - 1d4
+        let crits = Crits {
+            success: Some(Threshold {
+                compare: Compare::Eq,
+                value: 20,
+            }),
+            failure: Some(Threshold {
+                compare: Compare::Lt,
+                value: 3,
+            }),
+        };
+        let tree = e::sub(e::mul(e::CritDice(2, 20, crits), e::int(3)), e::dice(1, 4));
+
+        let info = DiceRoller::new(MockCryptoRng::default())
+            .try_eval_info(&tree)
+            .unwrap();
+        let total = DiceRoller::new(MockCryptoRng::default()).try_eval(&tree);
+        assert_eq!(Ok(info.total), total);
+
+        assert_eq!(info.dice.len(), 2);
+        assert_eq!((info.dice[0].count, info.dice[0].sides), (2, 20));
+        assert_eq!(info.dice[0].faces, [1, 1]);
+        assert_eq!(info.dice[0].failures, 2);
+        assert!(info.is_crit_failure());
+        assert!(!info.is_crit_success());
+    }
+}
@@ -1,5 +1,13 @@
 //! A companion library for parsing and calculating dice notation.
+//!
+//! # Features
+//!
+//! * `serde`: implements `Serialize` and `Deserialize` for [`ast::Expr`],
+//!   [`eval::ArithmeticError`], and the other evaluation results.
+//! * `arbitrary`: implements `Arbitrary` for [`ast::Expr`], for fuzzing.
 
 pub mod ast;
 pub mod eval;
+pub mod limits;
 pub mod parser;
+pub mod systems;
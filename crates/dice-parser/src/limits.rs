@@ -0,0 +1,268 @@
+//! Resource limits for parsing and evaluating untrusted input.
+//!
+//! A public bot can be fed `9999d9999*9999d9999`, or thousands of nested
+//! parentheses. [`Limits`] caps the work done for a single expression:
+//!
+//! - [`crate::parser::parse_with_limits`] checks the input length and the
+//!   nesting of parentheses *before* running the recursive [`pest`] parser,
+//!   then checks the finished tree.
+//! - A [`crate::eval::DiceRoller`] makes every `try_eval*` method check the
+//!   tree before rolling a single die, and every roll check its count. It
+//!   uses the [`Limits::default`] unless [`crate::eval::DiceRoller::with_limits`]
+//!   picks others.
+//!
+//! Every violation is a [`LimitError`], which names the limit that was hit.
+
+use recursion::CollapsibleExt as _;
+
+use crate::ast::{Expr, ExprFrame};
+
+/// Caps on the size of an expression and the dice it rolls.
+///
+/// The [`Default`] limits are meant for untrusted input, like chat messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+    /// The longest input, in bytes.
+    pub max_input_len: usize,
+    /// The deepest tree, where a single number or die term has a depth of `1`.
+    ///
+    /// Before parsing, this also caps how deeply parentheses are nested.
+    pub max_depth: usize,
+    /// The largest tree, counting every number, die term, operator and label.
+    pub max_nodes: usize,
+    /// The most dice rolled in total, like `8` for `4d6 + 4d6`.
+    pub max_dice: u64,
+    /// The largest number that can be written, including the count and sides
+    /// of a die term. This is only checked during parsing.
+    pub max_int: i64,
+}
+
+impl Limits {
+    /// No limits at all, for trusted input only.
+    ///
+    /// A [`crate::eval::DiceRoller`] checks the [`Limits::default`] unless it
+    /// opts into this with [`crate::eval::DiceRoller::with_limits`].
+    pub const UNLIMITED: Self = Self {
+        max_input_len: usize::MAX,
+        max_depth: usize::MAX,
+        max_nodes: usize::MAX,
+        max_dice: u64::MAX,
+        max_int: i64::MAX,
+    };
+
+    /// Checks the raw input, before it is parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::limits::{LimitError, Limits};
+    ///
+    /// let limits = Limits { max_depth: 2, ..Limits::default() };
+    ///
+    /// assert_eq!(limits.check_input("((1)) + (2)"), Ok(()));
+    /// assert_eq!(
+    ///     limits.check_input("(((1)))"),
+    ///     Err(LimitError::Depth { depth: 3, max: 2 })
+    /// );
+    /// ```
+    pub fn check_input(&self, input: &str) -> Result<(), LimitError> {
+        if input.len() > self.max_input_len {
+            return Err(LimitError::InputLength {
+                len: input.len(),
+                max: self.max_input_len,
+            });
+        }
+
+        // parentheses inside a label, like `[see (page 3)]`, don't nest anything
+        let mut in_label = false;
+        let mut depth = 0usize;
+        let mut deepest = 0usize;
+        for b in input.bytes() {
+            match b {
+                b'[' => in_label = true,
+                b']' => in_label = false,
+                b'(' if !in_label => {
+                    depth += 1;
+                    deepest = deepest.max(depth);
+                }
+                b')' if !in_label => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        if deepest > self.max_depth {
+            Err(LimitError::Depth {
+                depth: deepest,
+                max: self.max_depth,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the depth, node count and total dice of a tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::ast::Expr;
+    /// use dice_parser::limits::{LimitError, Limits};
+    ///
+    /// // 9999d9999 * 9999d9999
+    /// let tree = Expr::mul(Expr::dice(9999, 9999), Expr::dice(9999, 9999));
+    ///
+    /// assert_eq!(
+    ///     Limits::default().check(&tree),
+    ///     Err(LimitError::Dice { dice: 19998, max: 10_000 })
+    /// );
+    /// ```
+    pub fn check(&self, e: &Expr) -> Result<(), LimitError> {
+        let size = Size::of(e);
+
+        if size.nodes > self.max_nodes {
+            Err(LimitError::Nodes {
+                nodes: size.nodes,
+                max: self.max_nodes,
+            })
+        } else if size.depth > self.max_depth {
+            Err(LimitError::Depth {
+                depth: size.depth,
+                max: self.max_depth,
+            })
+        } else if size.dice > self.max_dice {
+            Err(LimitError::Dice {
+                dice: size.dice,
+                max: self.max_dice,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_input_len: 1024,
+            max_depth: 128,
+            max_nodes: 512,
+            max_dice: 10_000,
+            max_int: 1_000_000_000,
+        }
+    }
+}
+
+/// The limit that was hit, with the offending value and the maximum.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LimitError {
+    #[error("input is {len} bytes long, over the `max_input_len` limit of {max}")]
+    InputLength { len: usize, max: usize },
+    #[error("expression is nested {depth} deep, over the `max_depth` limit of {max}")]
+    Depth { depth: usize, max: usize },
+    #[error("expression has {nodes} nodes, over the `max_nodes` limit of {max}")]
+    Nodes { nodes: usize, max: usize },
+    #[error("expression rolls {dice} dice, over the `max_dice` limit of {max}")]
+    Dice { dice: u64, max: u64 },
+}
+
+/// The measurements checked by [`Limits::check`].
+struct Size {
+    depth: usize,
+    nodes: usize,
+    dice: u64,
+}
+
+impl Size {
+    fn of(e: &Expr) -> Self {
+        let leaf = |dice: u64| Size {
+            depth: 1,
+            nodes: 1,
+            dice,
+        };
+        let unary = |x: Size| Size {
+            depth: x.depth + 1,
+            nodes: x.nodes + 1,
+            dice: x.dice,
+        };
+        let binary = |x: Size, y: Size| Size {
+            depth: x.depth.max(y.depth) + 1,
+            nodes: x.nodes + y.nodes + 1,
+            dice: x.dice.saturating_add(y.dice),
+        };
+
+        e.collapse_frames(|frame| match frame {
+            ExprFrame::Int(_) => leaf(0),
+            // a negative count is an `ArithmeticError` later, so it rolls nothing
+            ExprFrame::Dice(c, _) | ExprFrame::CritDice(c, _, _) => leaf(c.max(0) as u64),
+            ExprFrame::Not(x) | ExprFrame::Label(x, _) | ExprFrame::List(x, _) => unary(x),
+            ExprFrame::Add(x, y)
+            | ExprFrame::Sub(x, y)
+            | ExprFrame::Mul(x, y)
+            | ExprFrame::Div(x, y) => binary(x, y),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Expr as e;
+
+    #[test]
+    fn test_check_input() {
+        let limits = Limits {
+            max_input_len: 16,
+            max_depth: 1,
+            ..Limits::UNLIMITED
+        };
+
+        assert_eq!(limits.check_input("(1)[see (p. 3)]"), Ok(()));
+        assert_eq!(
+            limits.check_input("(1 + 2) * (3)))"),
+            Ok(()),
+            "unbalanced parentheses are left to the parser"
+        );
+        assert_eq!(
+            limits.check_input("((1))"),
+            Err(LimitError::Depth { depth: 2, max: 1 })
+        );
+        assert_eq!(
+            limits.check_input("1 + 1 + 1 + 1 + 1"),
+            Err(LimitError::InputLength { len: 17, max: 16 })
+        );
+    }
+
+    #[test]
+    fn test_check_tree() {
+        // -(1d6[fire] + 2d8)
+        let tree = e::not(e::add(e::label(e::dice(1, 6), "fire"), e::dice(2, 8)));
+
+        assert_eq!(Limits::default().check(&tree), Ok(()));
+        assert_eq!(
+            Limits {
+                max_depth: 3,
+                ..Limits::UNLIMITED
+            }
+            .check(&tree),
+            Err(LimitError::Depth { depth: 4, max: 3 })
+        );
+        assert_eq!(
+            Limits {
+                max_nodes: 4,
+                ..Limits::UNLIMITED
+            }
+            .check(&tree),
+            Err(LimitError::Nodes { nodes: 5, max: 4 })
+        );
+        assert_eq!(
+            Limits {
+                max_dice: 2,
+                ..Limits::UNLIMITED
+            }
+            .check(&tree),
+            Err(LimitError::Dice { dice: 3, max: 2 })
+        );
+    }
+}
@@ -7,8 +7,20 @@ use std::fmt::Display;
 
 pub use pest::Parser;
 
-use crate::ast::Expr;
-use pest::{Span, iterators::Pairs, pratt_parser::PrattParser};
+use crate::{
+    ast::{
+        Expr,
+        crit::{Compare, Crits, Threshold},
+        list::ListOp,
+    },
+    eval::query::Query,
+    limits::{LimitError, Limits},
+};
+use pest::{
+    Span,
+    iterators::{Pair, Pairs},
+    pratt_parser::PrattParser,
+};
 
 #[derive(pest_derive::Parser)]
 #[grammar = "dice.pest"]
@@ -28,7 +40,7 @@ lazy_static::lazy_static! {
             .op(Op::infix(add, Left) | Op::infix(subtract, Left))
             .op(Op::infix(multiply, Left) | Op::infix(divide, Left))
             .op(Op::prefix(unary_minus))
-            .op(Op::postfix(label))
+            .op(Op::postfix(label) | Op::postfix(list_op))
     };
 }
 
@@ -87,13 +99,20 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> Expr {
                 let mut iter = primary.into_inner();
 
                 // required two fields: natural + d + natural
-                let count = iter.next().unwrap().as_str().parse::<i32>().unwrap();
-                let sides = iter.next().unwrap().as_str().parse::<i32>().unwrap();
+                let count = iter.next().unwrap().as_str().parse::<i64>().unwrap();
+                let sides = iter.next().unwrap().as_str().parse::<i64>().unwrap();
 
-                Expr::Dice(count, sides)
+                // optional fields: crit_success + crit_failure
+                dice_expr(count, sides, parse_crits(iter, sides, i64::MAX).unwrap())
             }
-            Rule::natural => Expr::Int(primary.as_str().parse::<i32>().unwrap()),
+            Rule::natural => Expr::Int(primary.as_str().parse::<i64>().unwrap()),
             Rule::expr => parse_expr(primary.into_inner()),
+            Rule::function => {
+                let mut iter = primary.into_inner();
+                let name = iter.next().unwrap();
+                let lhs = parse_expr(iter.next().unwrap().into_inner());
+                Expr::list(lhs, parse_function(name, iter, i64::MAX).unwrap())
+            }
             rule => unreachable!("Expr::parse expected primary, found {:?}", rule),
         })
         .map_infix(|lhs, op, rhs| match op.as_rule() {
@@ -115,9 +134,10 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> Expr {
                 if msg.is_empty() {
                     lhs
                 } else {
-                    Expr::Label(Box::new(lhs), msg)
+                    Expr::label(lhs, msg)
                 }
             }
+            Rule::list_op => Expr::list(lhs, parse_list_op(op)),
             rule => {
                 unreachable!("Expr::parse expected postfix operation, found {:?}", rule)
             }
@@ -125,6 +145,106 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> Expr {
         .parse(pairs)
 }
 
+/// Parses a [`Rule::list_op`].
+fn parse_list_op(pair: Pair<'_, Rule>) -> ListOp {
+    match pair.into_inner().next().map(|p| p.as_rule()) {
+        Some(Rule::sort) => ListOp::Sort,
+        Some(Rule::unique) => ListOp::Unique,
+        Some(Rule::matches) => ListOp::Matches,
+        Some(Rule::group) => ListOp::Group,
+        rule => unreachable!("expected a list operation, found {:?}", rule),
+    }
+}
+
+/// Parses the name of a [`Rule::function`], and the range of a `count` from the rest of its `pairs`.
+fn parse_function(
+    name: Pair<'_, Rule>,
+    mut pairs: Pairs<'_, Rule>,
+    max: i64,
+) -> Result<ListOp, ParseError> {
+    Ok(match name.as_rule() {
+        Rule::sum => ListOp::Sum,
+        Rule::max => ListOp::Max,
+        Rule::min => ListOp::Min,
+        Rule::count => match pairs.next() {
+            Some(compare) => ListOp::Count(Some(Threshold {
+                compare: parse_compare(&compare),
+                value: parse_int(pairs.next().unwrap(), max)?,
+            })),
+            None => ListOp::Count(None),
+        },
+        rule => unreachable!("expected a function, found {:?}", rule),
+    })
+}
+
+/// Builds an [`Expr::Dice`], or an [`Expr::CritDice`] when it has critical ranges.
+fn dice_expr<'s>(count: i64, sides: i64, crits: Crits) -> Expr<'s> {
+    if crits == Crits::default() {
+        Expr::Dice(count, sides)
+    } else {
+        Expr::CritDice(count, sides, crits)
+    }
+}
+
+/// Parses a [`Rule::natural`], which must be in `0..=max`.
+pub(crate) fn parse_int(pair: Pair<'_, Rule>, max: i64) -> Result<i64, ParseError> {
+    pair.as_str()
+        .parse::<i64>()
+        .ok()
+        .filter(|x| (0..=max).contains(x))
+        .ok_or_else(|| ParseError::OutOfRange {
+            span: pair.as_span().into(),
+            min: 0,
+            max,
+        })
+}
+
+/// Parses a [`Rule::compare`].
+fn parse_compare(pair: &Pair<'_, Rule>) -> Compare {
+    match pair.as_str() {
+        ">=" => Compare::Ge,
+        "<=" => Compare::Le,
+        ">" => Compare::Gt,
+        "<" => Compare::Lt,
+        _ => Compare::Eq,
+    }
+}
+
+/// Parses the [`Rule::crit_success`] and [`Rule::crit_failure`] of a [`Rule::dice`].
+fn parse_crits(pairs: Pairs<'_, Rule>, sides: i64, max: i64) -> Result<Crits, ParseError> {
+    let mut crits = Crits::default();
+    for pair in pairs {
+        let rule = pair.as_rule();
+
+        let mut compare = Compare::Eq;
+        let mut value = None;
+        for inner in pair.into_inner() {
+            match inner.as_rule() {
+                Rule::compare => compare = parse_compare(&inner),
+                Rule::natural => value = Some(parse_int(inner, max)?),
+                rule => unreachable!("expected a critical range, found {:?}", rule),
+            }
+        }
+
+        match rule {
+            Rule::crit_success => {
+                crits.success = Some(Threshold {
+                    compare,
+                    value: value.unwrap_or(sides),
+                })
+            }
+            Rule::crit_failure => {
+                crits.failure = Some(Threshold {
+                    compare,
+                    value: value.unwrap_or(1),
+                })
+            }
+            rule => unreachable!("expected a critical range, found {:?}", rule),
+        }
+    }
+    Ok(crits)
+}
+
 #[derive(Debug)]
 pub struct TokenLocation {
     input: String,
@@ -134,7 +254,13 @@ pub struct TokenLocation {
 
 impl Display for TokenLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", "")
+        write!(
+            f,
+            "`{}` at {}..{}",
+            &self.input[self.start..self.end],
+            self.start,
+            self.end
+        )
     }
 }
 
@@ -148,68 +274,150 @@ impl<'s> From<Span<'s>> for TokenLocation {
     }
 }
 
-// TODO: error handling during parsing of large numbers
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
-    #[error("number must be < 4 digits, got: `{span:?}`")]
-    IntTooLong { span: TokenLocation },
+    /// A number is outside of `min..=max`, see [`Limits::max_int`].
+    #[error("number {span} is out of range, expected {min}..={max}")]
+    OutOfRange {
+        span: TokenLocation,
+        min: i64,
+        max: i64,
+    },
+    /// A die term like `0d6`.
+    #[error("dice {span} must roll at least one die")]
+    ZeroCount { span: TokenLocation },
+    /// A die term like `2d0`.
+    #[error("dice {span} must have at least one side")]
+    ZeroSides { span: TokenLocation },
     #[error("{0}")]
-    ParseIntError(#[from] std::num::ParseIntError),
-    #[error("number `{0}` is <= 0")]
-    OutOfRange(i32),
+    Syntax(#[from] Box<pest::error::Error<Rule>>),
+    #[error(transparent)]
+    Limit(#[from] LimitError),
+}
+
+/// Parses a string into an [`Expr`], enforcing `limits` along the way.
+///
+/// The input length and the nesting of parentheses are checked before
+/// [`DiceParser::parse`] runs, since it recurses once per parenthesis.
+/// The finished tree is then checked with [`Limits::check`].
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::limits::{LimitError, Limits};
+/// use dice_parser::parser::{ParseError, parse_with_limits};
+///
+/// let limits = Limits::default();
+/// assert_eq!(parse_with_limits("2d6 + 3", &limits).unwrap().to_string(), "2d6 + 3");
+///
+/// let nested = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+/// assert!(matches!(
+///     parse_with_limits(&nested, &limits),
+///     Err(ParseError::Limit(LimitError::Depth { depth: 200, max: 128 }))
+/// ));
+/// ```
+pub fn parse_with_limits<'s>(input: &'s str, limits: &Limits) -> Result<Expr<'s>, ParseError> {
+    limits.check_input(input)?;
+
+    let mut pairs = DiceParser::parse(Rule::equation, input).map_err(Box::new)?;
+    let expr = try_parse_to_ast_with(pairs.next().unwrap().into_inner(), limits)?;
+
+    limits.check(&expr)?;
+    Ok(expr)
 }
 
-pub fn try_parse_to_ast(pairs: Pairs<Rule>) -> Result<Expr<'_>, ParseError> {
+/// Parses a comparison between two expressions, like `1d20 + 7 >= 16`,
+/// checking both sides against `limits` like [`parse_with_limits`].
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::limits::Limits;
+/// use dice_parser::parser::parse_query_with_limits;
+///
+/// let query = parse_query_with_limits("1d20+7>=16", &Limits::default()).unwrap();
+/// assert_eq!(query.to_string(), "1d20 + 7 >= 16");
+/// assert_eq!(query.exact().unwrap().probability, 0.6);
+/// ```
+pub fn parse_query_with_limits<'s>(
+    input: &'s str,
+    limits: &Limits,
+) -> Result<Query<'s>, ParseError> {
+    limits.check_input(input)?;
+
+    let mut pairs = DiceParser::parse(Rule::query, input).map_err(Box::new)?;
+    let lhs = try_parse_to_ast_with(pairs.next().unwrap().into_inner(), limits)?;
+    let compare = parse_compare(&pairs.next().unwrap());
+    let rhs = try_parse_to_ast_with(pairs.next().unwrap().into_inner(), limits)?;
+
+    limits.check(&lhs)?;
+    limits.check(&rhs)?;
+    Ok(Query { lhs, compare, rhs })
+}
+
+/// Generates an [`Expr`] from [`Pairs`], returning a [`ParseError`] instead of panicking.
+///
+/// Numbers are checked against [`Limits::max_int`] of the [`Limits::default`].
+/// To pick the limits, see [`try_parse_to_ast_with`].
+pub fn try_parse_to_ast(pairs: Pairs<'_, Rule>) -> Result<Expr<'_>, ParseError> {
+    try_parse_to_ast_with(pairs, &Limits::default())
+}
+
+/// Like [`try_parse_to_ast`], but checks numbers against [`Limits::max_int`] of `limits`.
+///
+/// The count and sides of a die term must be at least `1`, and every other
+/// number must be at least `0`.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::limits::Limits;
+/// use dice_parser::parser::{DiceParser, ParseError, Parser as _, Rule, try_parse_to_ast_with};
+///
+/// let limits = Limits { max_int: 100, ..Limits::default() };
+/// let parse = |input| {
+///     let mut pairs = DiceParser::parse(Rule::equation, input).unwrap();
+///     try_parse_to_ast_with(pairs.next().unwrap().into_inner(), &limits)
+/// };
+///
+/// assert!(parse("1d100 + 100").is_ok());
+/// assert!(matches!(parse("1d101"), Err(ParseError::OutOfRange { max: 100, .. })));
+/// assert!(matches!(parse("0d6"), Err(ParseError::ZeroCount { .. })));
+/// assert!(matches!(parse("2d0"), Err(ParseError::ZeroSides { .. })));
+/// ```
+pub fn try_parse_to_ast_with<'s>(
+    pairs: Pairs<'s, Rule>,
+    limits: &Limits,
+) -> Result<Expr<'s>, ParseError> {
     PRATT_PARSER
         .map_primary(|primary| match primary.as_rule() {
             Rule::dice => {
+                let span = primary.as_span();
                 let mut iter = primary.into_inner();
-                let count_tok = iter.next().unwrap();
-                let sides_tok = iter.next().unwrap();
-
-                if count_tok.as_str().len() > 4 {
-                    return Err(ParseError::IntTooLong {
-                        span: count_tok.as_span().into(),
-                    });
-                }
-                if sides_tok.as_str().len() > 4 {
-                    return Err(ParseError::IntTooLong {
-                        span: sides_tok.as_span().into(),
-                    });
-                }
 
-                let count = count_tok
-                    .as_str()
-                    .parse::<i32>()
-                    .map_err(|why| ParseError::ParseIntError(why))?;
-                if count <= 0 {
-                    return Err(ParseError::OutOfRange(count));
+                let count = parse_int(iter.next().unwrap(), limits.max_int)?;
+                if count == 0 {
+                    return Err(ParseError::ZeroCount { span: span.into() });
                 }
-
-                let sides = sides_tok
-                    .as_str()
-                    .parse::<i32>()
-                    .map_err(|why| ParseError::ParseIntError(why))?;
-                if sides <= 0 {
-                    return Err(ParseError::OutOfRange(sides));
+                let sides = parse_int(iter.next().unwrap(), limits.max_int)?;
+                if sides == 0 {
+                    return Err(ParseError::ZeroSides { span: span.into() });
                 }
 
-                Ok(Expr::Dice(count, sides))
+                Ok(dice_expr(
+                    count,
+                    sides,
+                    parse_crits(iter, sides, limits.max_int)?,
+                ))
             }
-            Rule::natural => {
-                if primary.as_str().len() > 4 {
-                    Err(ParseError::IntTooLong {
-                        span: primary.as_span().into(),
-                    })
-                } else {
-                    primary
-                        .as_str()
-                        .parse::<i32>()
-                        .map_err(|e| ParseError::ParseIntError(e))
-                        .map(|x| Expr::Int(x))
-                }
+            Rule::natural => parse_int(primary, limits.max_int).map(Expr::Int),
+            Rule::expr => try_parse_to_ast_with(primary.into_inner(), limits),
+            Rule::function => {
+                let mut iter = primary.into_inner();
+                let name = iter.next().unwrap();
+                let lhs = try_parse_to_ast_with(iter.next().unwrap().into_inner(), limits)?;
+                Ok(Expr::list(lhs, parse_function(name, iter, limits.max_int)?))
             }
-            Rule::expr => try_parse_to_ast(primary.into_inner()),
             _ => unreachable!(),
         })
         .map_infix(|lhs, op, rhs| match op.as_rule() {
@@ -232,6 +440,7 @@ pub fn try_parse_to_ast(pairs: Pairs<Rule>) -> Result<Expr<'_>, ParseError> {
                     Ok(Expr::label(lhs?, msg))
                 }
             }
+            Rule::list_op => Ok(Expr::list(lhs?, parse_list_op(op))),
             _ => unreachable!(),
         })
         .parse(pairs)
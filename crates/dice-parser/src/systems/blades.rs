@@ -0,0 +1,118 @@
+//! Blades in the Dark, and other Forged in the Dark games.
+//!
+//! Roll a d6 for each point of rating, and read the highest:
+//! a 6 is a full success, a 4 or 5 a partial success, and anything lower a
+//! failure. Two or more 6s are a critical success. With no dice at all, roll
+//! two and read the *lowest*, which can't be a critical.
+
+use std::fmt::Display;
+
+use super::{GameSystem, Pool, Reading, parse_pool, roll};
+use crate::{
+    eval::{ArithmeticError, faces::FaceSource},
+    limits::Limits,
+    parser::{ParseError, Rule},
+};
+
+/// Blades in the Dark, rolled like `blades 3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Blades;
+
+impl GameSystem for Blades {
+    fn name(&self) -> &'static str {
+        "Blades in the Dark"
+    }
+
+    fn keyword(&self) -> &'static str {
+        "blades"
+    }
+
+    fn bands(&self) -> &'static [&'static str] {
+        &[
+            "failure",
+            "partial success",
+            "full success",
+            "critical success",
+        ]
+    }
+
+    fn parse<'s>(&self, args: &'s str, limits: &Limits) -> Result<Box<dyn Pool + 's>, ParseError> {
+        let (dice, _) = parse_pool(Rule::pool, args, limits)?;
+        Ok(Box::new(BladesPool { dice }))
+    }
+}
+
+/// A Blades in the Dark roll of `dice` d6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BladesPool {
+    pub dice: i64,
+}
+
+impl Pool for BladesPool {
+    fn roll(&self, faces: &mut dyn FaceSource) -> Result<Reading, ArithmeticError> {
+        let zero = self.dice == 0;
+        let rolled = roll(faces, if zero { 2 } else { self.dice as usize }, 6)?;
+
+        let value = if zero {
+            rolled.iter().min()
+        } else {
+            rolled.iter().max()
+        };
+        let value = *value.unwrap();
+        let sixes = rolled.iter().filter(|&&f| f == 6).count();
+
+        let band = match value {
+            6 if sixes >= 2 && !zero => "critical success",
+            6 => "full success",
+            4 | 5 => "partial success",
+            _ => "failure",
+        };
+        Ok(Reading {
+            band,
+            value,
+            faces: vec![rolled],
+            detail: None,
+        })
+    }
+}
+
+impl Display for BladesPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "blades {}", self.dice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::faces::Fixed;
+
+    #[test]
+    fn test_bands() {
+        let read = |dice, faces: &[i64]| {
+            let reading = BladesPool { dice }
+                .roll(&mut Fixed::new(faces.iter().copied()))
+                .unwrap();
+            (reading.band, reading.value)
+        };
+
+        assert_eq!(read(3, &[1, 3, 2]), ("failure", 3));
+        assert_eq!(read(3, &[1, 4, 2]), ("partial success", 4));
+        assert_eq!(read(2, &[6, 5]), ("full success", 6));
+        assert_eq!(read(4, &[6, 1, 6, 2]), ("critical success", 6));
+
+        // zero dice roll two, and keep the lowest
+        assert_eq!(read(0, &[6, 5]), ("partial success", 5));
+        assert_eq!(read(0, &[6, 6]), ("full success", 6));
+    }
+
+    #[test]
+    fn test_parse() {
+        let limits = Limits::default();
+        assert_eq!(Blades.parse("0", &limits).unwrap().to_string(), "blades 0");
+        assert!(matches!(
+            Blades.parse("3 push", &limits),
+            Err(ParseError::Syntax(_))
+        ));
+    }
+}
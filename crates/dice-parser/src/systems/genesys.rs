@@ -0,0 +1,584 @@
+//! Genesys and the Star Wars roleplaying games by Fantasy Flight, whose
+//! narrative dice show symbols instead of numbers.
+//!
+//! A pool is written like `2a1p2d`: two ability dice, a proficiency die and
+//! two difficulty dice. Every face has zero or more [`Symbols`], and the
+//! symbols of a roll cancel out into a [`Net`] result:
+//!
+//! - each failure cancels a success, and each threat cancels an advantage;
+//! - a triumph is also a success, and a despair is also a failure, but the
+//!   triumph or despair itself is never cancelled.
+//!
+//! The check succeeds with at least one net success. For the exact odds of
+//! every net result, see [`NarrativePool::distribution`].
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use super::{GameSystem, Pool, Reading};
+use crate::{
+    eval::{
+        ArithmeticError,
+        distribution::{DistributionError, MAX_OUTCOMES},
+        faces::{FaceSource, on_die},
+    },
+    limits::{LimitError, Limits},
+    parser::{DiceParser, ParseError, Parser as _, Rule, parse_int},
+};
+
+/// Narrative dice, rolled like `genesys 2a1p2d`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Genesys;
+
+impl GameSystem for Genesys {
+    fn name(&self) -> &'static str {
+        "Genesys"
+    }
+
+    fn keyword(&self) -> &'static str {
+        "genesys"
+    }
+
+    fn bands(&self) -> &'static [&'static str] {
+        &["failure", "success"]
+    }
+
+    fn parse<'s>(&self, args: &'s str, limits: &Limits) -> Result<Box<dyn Pool + 's>, ParseError> {
+        Ok(Box::new(NarrativePool::parse(args, limits)?))
+    }
+}
+
+/// The kinds of narrative dice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Die {
+    /// A green d8, for the character's skill.
+    Ability,
+    /// A yellow d12, an upgraded ability die. The only die with a triumph.
+    Proficiency,
+    /// A blue d6, for helpful circumstances.
+    Boost,
+    /// A purple d8, for the difficulty of the check.
+    Difficulty,
+    /// A red d12, an upgraded difficulty die. The only die with a despair.
+    Challenge,
+    /// A black d6, for hindering circumstances.
+    Setback,
+}
+
+impl Die {
+    /// Every kind of die, in the order they are written.
+    pub const ALL: [Die; 6] = [
+        Die::Ability,
+        Die::Proficiency,
+        Die::Boost,
+        Die::Difficulty,
+        Die::Challenge,
+        Die::Setback,
+    ];
+
+    /// The letter of the die in the notation, like the `a` in `2a`.
+    pub const fn letter(self) -> char {
+        match self {
+            Die::Ability => 'a',
+            Die::Proficiency => 'p',
+            Die::Boost => 'b',
+            Die::Difficulty => 'd',
+            Die::Challenge => 'c',
+            Die::Setback => 's',
+        }
+    }
+
+    /// The symbols on every face, where face `n` is at index `n - 1`.
+    pub const fn faces(self) -> &'static [Symbols] {
+        const BLANK: Symbols = Symbols::new(0, 0, 0, 0);
+        const S: Symbols = Symbols::new(1, 0, 0, 0);
+        const SS: Symbols = Symbols::new(2, 0, 0, 0);
+        const A: Symbols = Symbols::new(0, 0, 1, 0);
+        const AA: Symbols = Symbols::new(0, 0, 2, 0);
+        const SA: Symbols = Symbols::new(1, 0, 1, 0);
+        const F: Symbols = Symbols::new(0, 1, 0, 0);
+        const FF: Symbols = Symbols::new(0, 2, 0, 0);
+        const T: Symbols = Symbols::new(0, 0, 0, 1);
+        const TT: Symbols = Symbols::new(0, 0, 0, 2);
+        const FT: Symbols = Symbols::new(0, 1, 0, 1);
+        const TRIUMPH: Symbols = Symbols {
+            triumph: 1,
+            ..BLANK
+        };
+        const DESPAIR: Symbols = Symbols {
+            despair: 1,
+            ..BLANK
+        };
+
+        match self {
+            Die::Ability => &[BLANK, S, S, SS, A, A, SA, AA],
+            Die::Proficiency => &[BLANK, S, S, SS, SS, A, SA, SA, SA, AA, AA, TRIUMPH],
+            Die::Boost => &[BLANK, BLANK, S, SA, AA, A],
+            Die::Difficulty => &[BLANK, F, FF, T, T, T, TT, FT],
+            Die::Challenge => &[BLANK, F, F, FF, FF, T, T, FT, FT, TT, TT, DESPAIR],
+            Die::Setback => &[BLANK, BLANK, F, F, T, T],
+        }
+    }
+
+    /// The number of sides, like `8` for a d8.
+    pub const fn sides(self) -> i64 {
+        self.faces().len() as i64
+    }
+
+    fn from_letter(letter: char) -> Self {
+        let letter = letter.to_ascii_lowercase();
+        Die::ALL
+            .into_iter()
+            .find(|die| die.letter() == letter)
+            .expect("the grammar only allows known letters")
+    }
+}
+
+/// The symbols on a face, or all the faces of a roll, before cancelling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Symbols {
+    pub success: u32,
+    pub failure: u32,
+    pub advantage: u32,
+    pub threat: u32,
+    pub triumph: u32,
+    pub despair: u32,
+}
+
+impl Symbols {
+    const fn new(success: u32, failure: u32, advantage: u32, threat: u32) -> Self {
+        Self {
+            success,
+            failure,
+            advantage,
+            threat,
+            triumph: 0,
+            despair: 0,
+        }
+    }
+
+    /// Cancels out opposing symbols.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::systems::genesys::{Net, Symbols};
+    ///
+    /// let rolled = Symbols { success: 1, failure: 3, advantage: 2, triumph: 1, ..Symbols::default() };
+    /// assert_eq!(rolled.net(), Net { success: -1, advantage: 2, triumph: 1, despair: 0 });
+    /// ```
+    pub fn net(&self) -> Net {
+        Net {
+            success: (self.success + self.triumph) as i64 - (self.failure + self.despair) as i64,
+            advantage: self.advantage as i64 - self.threat as i64,
+            triumph: self.triumph,
+            despair: self.despair,
+        }
+    }
+}
+
+impl std::ops::Add for Symbols {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            success: self.success + rhs.success,
+            failure: self.failure + rhs.failure,
+            advantage: self.advantage + rhs.advantage,
+            threat: self.threat + rhs.threat,
+            triumph: self.triumph + rhs.triumph,
+            despair: self.despair + rhs.despair,
+        }
+    }
+}
+
+/// The result of a roll, after cancelling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Net {
+    /// Net successes, or net failures if negative. This counts triumphs and despairs.
+    pub success: i64,
+    /// Net advantage, or net threat if negative.
+    pub advantage: i64,
+    pub triumph: u32,
+    pub despair: u32,
+}
+
+impl Net {
+    /// Returns `true` if the check succeeds, with at least one net success.
+    pub fn is_success(&self) -> bool {
+        self.success > 0
+    }
+}
+
+impl Display for Net {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |n: u64, one: &'static str, many: &'static str| {
+            format!("{n} {}", if n == 1 { one } else { many })
+        };
+
+        let mut parts = Vec::new();
+        match self.success {
+            0 => {}
+            n @ 1.. => parts.push(plural(n as u64, "success", "successes")),
+            n => parts.push(plural(n.unsigned_abs(), "failure", "failures")),
+        }
+        match self.advantage {
+            0 => {}
+            n @ 1.. => parts.push(format!("{n} advantage")),
+            n => parts.push(format!("{} threat", n.unsigned_abs())),
+        }
+        if self.triumph > 0 {
+            parts.push(plural(self.triumph.into(), "triumph", "triumphs"));
+        }
+        if self.despair > 0 {
+            parts.push(format!("{} despair", self.despair));
+        }
+
+        if parts.is_empty() {
+            write!(f, "no symbols")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// A pool of narrative dice, like `2a1p2d`.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::eval::faces::Fixed;
+/// use dice_parser::limits::Limits;
+/// use dice_parser::systems::genesys::{Die, NarrativePool, Net};
+///
+/// let pool = NarrativePool::parse("2a 1p 2d", &Limits::default()).unwrap();
+/// assert_eq!(pool.to_string(), "2a1p2d");
+/// assert_eq!(pool.count(Die::Ability), 2);
+///
+/// // success + advantage, advantage, triumph, failure, threat
+/// let roll = pool.roll_symbols(&mut Fixed::new([7, 5, 12, 2, 4])).unwrap();
+/// assert_eq!(roll.net, Net { success: 1, advantage: 1, triumph: 1, despair: 0 });
+/// assert_eq!(roll.net.to_string(), "1 success, 1 advantage, 1 triumph");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NarrativePool {
+    /// The number of each die, in the order of [`Die::ALL`].
+    counts: [u32; 6],
+}
+
+/// A roll of a [`NarrativePool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NarrativeRoll {
+    /// Every die and the face it rolled, in the order of [`Die::ALL`].
+    pub faces: Vec<(Die, i64)>,
+    /// Every symbol rolled, before cancelling.
+    pub symbols: Symbols,
+    pub net: Net,
+}
+
+impl NarrativePool {
+    /// A pool with `count` dice of each kind. Repeated kinds add up, to at
+    /// most [`u32::MAX`] dice of a kind.
+    pub fn new(dice: impl IntoIterator<Item = (Die, u32)>) -> Self {
+        let mut pool = Self::default();
+        for (die, count) in dice {
+            let total = &mut pool.counts[die as usize];
+            *total = total.saturating_add(count);
+        }
+        pool
+    }
+
+    /// Parses a [`Rule::narrative`], like `2a1p2d`. A term without a count, like
+    /// `a`, is a single die, and repeated kinds add up.
+    pub fn parse(input: &str, limits: &Limits) -> Result<Self, ParseError> {
+        let pairs = DiceParser::parse(Rule::narrative, input).map_err(Box::new)?;
+
+        let mut dice = Vec::new();
+        let mut total: u64 = 0;
+        for pair in pairs.filter(|pair| pair.as_rule() == Rule::narrative_die) {
+            let mut inner = pair.into_inner().rev();
+            let die = Die::from_letter(inner.next().unwrap().as_str().chars().next().unwrap());
+            let count = match inner.next() {
+                Some(natural) => parse_int(natural, limits.max_int.min(u32::MAX.into()))?,
+                None => 1,
+            };
+
+            total = total.saturating_add(count as u64);
+            if total > limits.max_dice {
+                return Err(LimitError::Dice {
+                    dice: total,
+                    max: limits.max_dice,
+                }
+                .into());
+            }
+            dice.push((die, count as u32));
+        }
+        Ok(Self::new(dice))
+    }
+
+    /// The number of dice of this kind.
+    pub fn count(&self, die: Die) -> u32 {
+        self.counts[die as usize]
+    }
+
+    /// Every die in the pool, in the order of [`Die::ALL`].
+    fn dice(&self) -> impl Iterator<Item = Die> + '_ {
+        Die::ALL
+            .into_iter()
+            .flat_map(|die| std::iter::repeat_n(die, self.count(die) as usize))
+    }
+
+    /// Rolls every die, and cancels the symbols.
+    pub fn roll_symbols(
+        &self,
+        faces: &mut dyn FaceSource,
+    ) -> Result<NarrativeRoll, ArithmeticError> {
+        let mut rolled = Vec::new();
+        let mut symbols = Symbols::default();
+        for die in self.dice() {
+            // a `FaceSource` may not check its faces against the die
+            let face = on_die(faces.face(die.sides())?, die.sides())?;
+            symbols = symbols + die.faces()[face as usize - 1];
+            rolled.push((die, face));
+        }
+
+        Ok(NarrativeRoll {
+            faces: rolled,
+            symbols,
+            net: symbols.net(),
+        })
+    }
+
+    /// The exact odds of every [`Net`] result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dice_parser::limits::Limits;
+    /// use dice_parser::systems::genesys::NarrativePool;
+    ///
+    /// // an ability die against a difficulty die
+    /// let odds = NarrativePool::parse("1a1d", &Limits::default()).unwrap().distribution().unwrap();
+    /// assert_eq!(odds.total(), 64);
+    /// assert_eq!(odds.probability(|net| net.is_success()), 22.0 / 64.0);
+    /// ```
+    pub fn distribution(&self) -> Result<SymbolDistribution, DistributionError> {
+        let mut outcomes = BTreeMap::from([(Net::default(), 1u128)]);
+        let mut total: u128 = 1;
+
+        for die in self.dice() {
+            total = total
+                .checked_mul(die.sides() as u128)
+                .ok_or(DistributionError::TooManyCombinations)?;
+
+            let mut next = BTreeMap::new();
+            for (net, weight) in outcomes {
+                for face in die.faces() {
+                    let face = face.net();
+                    let net = Net {
+                        success: net.success + face.success,
+                        advantage: net.advantage + face.advantage,
+                        triumph: net.triumph + face.triumph,
+                        despair: net.despair + face.despair,
+                    };
+                    *next.entry(net).or_insert(0) += weight;
+                }
+            }
+            if next.len() > MAX_OUTCOMES {
+                return Err(DistributionError::TooManyOutcomes);
+            }
+            outcomes = next;
+        }
+
+        Ok(SymbolDistribution { outcomes, total })
+    }
+}
+
+impl Pool for NarrativePool {
+    fn roll(&self, faces: &mut dyn FaceSource) -> Result<Reading, ArithmeticError> {
+        let roll = self.roll_symbols(faces)?;
+        Ok(Reading {
+            band: if roll.net.is_success() {
+                "success"
+            } else {
+                "failure"
+            },
+            value: roll.net.success,
+            faces: Die::ALL
+                .into_iter()
+                .map(|die| {
+                    roll.faces
+                        .iter()
+                        .filter(|(d, _)| *d == die)
+                        .map(|&(_, face)| face)
+                        .collect()
+                })
+                .filter(|faces: &Vec<i64>| !faces.is_empty())
+                .collect(),
+            detail: Some(roll.net.to_string()),
+        })
+    }
+}
+
+impl Display for NarrativePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.counts == [0; 6] {
+            return write!(f, "0a");
+        }
+        for die in Die::ALL {
+            match self.count(die) {
+                0 => {}
+                n => write!(f, "{n}{}", die.letter())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every [`Net`] result of a [`NarrativePool`], and the number of ways to roll it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolDistribution {
+    outcomes: BTreeMap<Net, u128>,
+    total: u128,
+}
+
+impl SymbolDistribution {
+    /// The sum of all weights, see [`Self::weight`].
+    pub fn total(&self) -> u128 {
+        self.total
+    }
+
+    /// The number of ways `net` can be rolled, out of [`Self::total`].
+    pub fn weight(&self, net: &Net) -> u128 {
+        self.outcomes.get(net).copied().unwrap_or(0)
+    }
+
+    /// The chance of a result which matches `f`, like [`Net::is_success`].
+    pub fn probability(&self, f: impl Fn(&Net) -> bool) -> f64 {
+        let weight: u128 = self
+            .outcomes
+            .iter()
+            .filter(|(net, _)| f(net))
+            .map(|(_, &weight)| weight)
+            .sum();
+        weight as f64 / self.total as f64
+    }
+
+    /// Every possible result and its weight.
+    pub fn outcomes(&self) -> impl Iterator<Item = (Net, u128)> + '_ {
+        self.outcomes.iter().map(|(&net, &weight)| (net, weight))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::faces::{FaceError, Fixed};
+
+    #[test]
+    fn test_faces() {
+        for die in Die::ALL {
+            assert_eq!(Die::from_letter(die.letter().to_ascii_uppercase()), die);
+            assert!(matches!(die.sides(), 6 | 8 | 12));
+        }
+
+        let sum = |die: Die| die.faces().iter().fold(Symbols::default(), |a, &b| a + b);
+        let ability = sum(Die::Ability);
+        let difficulty = sum(Die::Difficulty);
+        assert_eq!((ability.success, ability.advantage), (5, 5));
+        assert_eq!((difficulty.failure, difficulty.threat), (4, 6));
+        assert_eq!(sum(Die::Proficiency).triumph, 1);
+        assert_eq!(sum(Die::Challenge).despair, 1);
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let pool = NarrativePool::new([(Die::Proficiency, 1), (Die::Challenge, 1)]);
+
+        // a triumph and a despair cancel as a success and a failure, but both stay
+        let roll = pool.roll_symbols(&mut Fixed::new([12])).unwrap();
+        assert_eq!(
+            roll.net,
+            Net {
+                success: 0,
+                advantage: 0,
+                triumph: 1,
+                despair: 1
+            }
+        );
+        assert_eq!(roll.net.to_string(), "1 triumph, 1 despair");
+        assert!(!roll.net.is_success());
+
+        let roll = pool.roll_symbols(&mut Fixed::new([1, 10])).unwrap();
+        assert_eq!(roll.net.to_string(), "2 threat");
+
+        let reading = pool.roll(&mut Fixed::new([4, 1])).unwrap();
+        assert_eq!(reading.band, "success");
+        assert_eq!(reading.faces, [[4], [1]]);
+        assert_eq!(reading.detail.as_deref(), Some("2 successes"));
+    }
+
+    #[test]
+    fn test_faces_out_of_range() {
+        /// A source which doesn't check its faces against the die.
+        struct Unchecked(i64);
+        impl FaceSource for Unchecked {
+            fn face(&mut self, _sides: i64) -> Result<i64, FaceError> {
+                Ok(self.0)
+            }
+        }
+
+        let pool = NarrativePool::new([(Die::Ability, 1)]);
+        for face in [0, 9, -1] {
+            assert_eq!(
+                pool.roll_symbols(&mut Unchecked(face)),
+                Err(FaceError::OutOfRange { face, sides: 8 }.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_distribution() {
+        let odds = NarrativePool::parse("2a1p2d1c", &Limits::default())
+            .unwrap()
+            .distribution()
+            .unwrap();
+        assert_eq!(odds.total(), 8 * 8 * 12 * 8 * 8 * 12);
+        assert_eq!(odds.outcomes().map(|(_, w)| w).sum::<u128>(), odds.total());
+        assert!((odds.probability(|_| true) - 1.0).abs() < 1e-12);
+        assert_eq!(
+            odds.probability(|net| net.triumph > 0),
+            1.0 / 12.0,
+            "only the proficiency die has a triumph"
+        );
+
+        assert!(
+            NarrativePool::default()
+                .distribution()
+                .unwrap()
+                .weight(&Net::default())
+                == 1
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let limits = Limits::default();
+        let pool = NarrativePool::parse("A a 2D s", &limits).unwrap();
+        assert_eq!(pool.to_string(), "2a2d1s");
+        assert!(NarrativePool::parse("2x", &limits).is_err());
+        assert!(NarrativePool::parse("", &limits).is_err());
+        assert!(matches!(
+            NarrativePool::parse("9000a9000d", &limits),
+            Err(ParseError::Limit(LimitError::Dice { .. }))
+        ));
+
+        // repeated kinds can't overflow their count
+        let pool = NarrativePool::parse("4294967295a 1a", &Limits::UNLIMITED).unwrap();
+        assert_eq!(pool.count(Die::Ability), u32::MAX);
+    }
+}
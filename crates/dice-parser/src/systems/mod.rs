@@ -0,0 +1,293 @@
+//! Game systems, which read a roll as a named outcome instead of a number.
+//!
+//! Many games roll a fixed pool of dice and read it by a rule of their own,
+//! like "the highest d6 decides" in Blades in the Dark. A [`GameSystem`] parses
+//! the dice after its keyword, like the `3` in `blades 3`, into a [`Pool`].
+//! Rolling that pool gives a [`Reading`], whose band is one of
+//! [`GameSystem::bands`].
+//!
+//! A [`Registry`] matches an input to a system by its keyword. The
+//! [`Registry::default`] has every built-in system:
+//!
+//! | Keyword   | System                           | Example          |
+//! |-----------|----------------------------------|------------------|
+//! | `blades`  | [`blades::Blades`]               | `blades 3`       |
+//! | `pbta`    | [`pbta::PoweredByTheApocalypse`] | `pbta +1`        |
+//! | `yz`      | [`year_zero::YearZero`]          | `yz 5 push`      |
+//! | `sw`      | [`savage_worlds::SavageWorlds`]  | `sw d8 + 1`      |
+//! | `genesys` | [`genesys::Genesys`]             | `genesys 2a1p2d` |
+//!
+//! Other systems are added with [`Registry::with_system`].
+
+pub mod blades;
+pub mod genesys;
+pub mod pbta;
+pub mod savage_worlds;
+pub mod year_zero;
+
+use std::fmt::{Debug, Display};
+
+use crate::{
+    ast::Expr,
+    eval::{ArithmeticError, faces::FaceSource},
+    limits::{LimitError, Limits},
+    parser::{DiceParser, ParseError, Parser as _, Rule, parse_int, try_parse_to_ast_with},
+};
+
+/// A game with its own rule for reading a roll.
+pub trait GameSystem: Debug + Send + Sync {
+    /// The name of the game, like `Blades in the Dark`.
+    fn name(&self) -> &'static str;
+
+    /// The word which starts a roll, like `blades`. It is matched ignoring case.
+    fn keyword(&self) -> &'static str;
+
+    /// Every band a [`Reading`] can fall in, from worst to best.
+    fn bands(&self) -> &'static [&'static str];
+
+    /// Parses the dice after the keyword, like the `3` in `blades 3`.
+    fn parse<'s>(&self, args: &'s str, limits: &Limits) -> Result<Box<dyn Pool + 's>, ParseError>;
+}
+
+/// The dice of a single roll of a [`GameSystem`].
+///
+/// It displays as the input it was parsed from, like `blades 3`.
+pub trait Pool: Debug + Display {
+    /// Rolls the dice, and reads them by the rules of the game.
+    fn roll(&self, faces: &mut dyn FaceSource) -> Result<Reading, ArithmeticError>;
+}
+
+/// A roll, read by the rules of a [`GameSystem`].
+///
+/// This only implements `Serialize`, since the band is always one of
+/// the `&'static str` in [`GameSystem::bands`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Reading {
+    /// The band of the outcome, like `partial success`.
+    pub band: &'static str,
+    /// The number the band was read from, like the highest die or the number of successes.
+    pub value: i64,
+    /// The faces of every group of dice, in the order they were rolled.
+    pub faces: Vec<Vec<i64>>,
+    /// Anything else read from the roll, like the net symbols of narrative dice.
+    pub detail: Option<String>,
+}
+
+/// A [`Pool`], along with the [`GameSystem`] which parsed it.
+pub type SystemPool<'r, 's> = (&'r dyn GameSystem, Box<dyn Pool + 's>);
+
+/// The game systems which can be parsed, by keyword.
+///
+/// # Examples
+///
+/// ```
+/// use dice_parser::eval::faces::Fixed;
+/// use dice_parser::limits::Limits;
+/// use dice_parser::systems::Registry;
+///
+/// let systems = Registry::default();
+/// let (system, pool) = systems.parse("Blades 3", &Limits::default()).unwrap().unwrap();
+/// assert_eq!(system.name(), "Blades in the Dark");
+/// assert_eq!(pool.to_string(), "blades 3");
+///
+/// let reading = pool.roll(&mut Fixed::new([2, 5, 4])).unwrap();
+/// assert_eq!(reading.band, "partial success");
+/// assert_eq!(reading.value, 5);
+///
+/// // anything else is left to the regular parser
+/// assert!(systems.parse("3d6", &Limits::default()).is_none());
+/// ```
+#[derive(Debug)]
+pub struct Registry {
+    systems: Vec<Box<dyn GameSystem>>,
+}
+
+impl Registry {
+    /// A registry without any systems.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+        }
+    }
+
+    /// Adds a system, replacing any system with the same keyword.
+    #[must_use]
+    pub fn with_system(mut self, system: impl GameSystem + 'static) -> Self {
+        self.systems
+            .retain(|s| !s.keyword().eq_ignore_ascii_case(system.keyword()));
+        self.systems.push(Box::new(system));
+        self
+    }
+
+    /// Returns the system with this keyword, ignoring case.
+    pub fn get(&self, keyword: &str) -> Option<&dyn GameSystem> {
+        self.systems
+            .iter()
+            .find(|s| s.keyword().eq_ignore_ascii_case(keyword))
+            .map(|s| &**s)
+    }
+
+    /// Every system, in the order they were added.
+    pub fn systems(&self) -> impl Iterator<Item = &dyn GameSystem> {
+        self.systems.iter().map(|s| &**s)
+    }
+
+    /// Parses an input which starts with the keyword of a system, like `blades 3`.
+    ///
+    /// Returns [`None`] if the first word isn't a keyword, so the input can be
+    /// parsed as a regular expression instead.
+    pub fn parse<'s>(
+        &self,
+        input: &'s str,
+        limits: &Limits,
+    ) -> Option<Result<SystemPool<'_, 's>, ParseError>> {
+        let input = input.trim();
+        let (keyword, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let system = self.get(keyword)?;
+
+        let pool = limits
+            .check_input(input)
+            .map_err(ParseError::from)
+            .and_then(|()| system.parse(args.trim_start(), limits));
+        Some(pool.map(|pool| (system, pool)))
+    }
+}
+
+impl Default for Registry {
+    /// Every built-in system.
+    fn default() -> Self {
+        Self::new()
+            .with_system(blades::Blades)
+            .with_system(pbta::PoweredByTheApocalypse)
+            .with_system(year_zero::YearZero)
+            .with_system(savage_worlds::SavageWorlds)
+            .with_system(genesys::Genesys)
+    }
+}
+
+/// Parses a [`Rule::pool`] or a [`Rule::pushed_pool`], returning the number of
+/// dice and whether they are pushed.
+fn parse_pool(rule: Rule, args: &str, limits: &Limits) -> Result<(i64, bool), ParseError> {
+    let mut pairs = DiceParser::parse(rule, args).map_err(Box::new)?;
+    let count = parse_int(pairs.next().unwrap(), limits.max_int)?;
+    let push = pairs
+        .next()
+        .is_some_and(|pair| pair.as_rule() == Rule::push);
+
+    if count as u64 > limits.max_dice {
+        return Err(LimitError::Dice {
+            dice: count as u64,
+            max: limits.max_dice,
+        }
+        .into());
+    }
+    Ok((count, push))
+}
+
+/// Parses a [`Rule::modifier`].
+fn parse_modifier<'s>(args: &'s str, limits: &Limits) -> Result<Option<Expr<'s>>, ParseError> {
+    let pairs = DiceParser::parse(Rule::modifier, args).map_err(Box::new)?;
+    modifier(pairs, limits)
+}
+
+/// Parses a [`Rule::trait_die`], returning the sides and the modifier.
+fn parse_trait_die<'s>(
+    args: &'s str,
+    limits: &Limits,
+) -> Result<(i64, Option<Expr<'s>>), ParseError> {
+    let mut pairs = DiceParser::parse(Rule::trait_die, args).map_err(Box::new)?;
+
+    let die = pairs.next().unwrap();
+    let span = die.as_span();
+    let sides = parse_int(die.into_inner().next().unwrap(), limits.max_int)?;
+    if sides == 0 {
+        return Err(ParseError::ZeroSides { span: span.into() });
+    }
+    Ok((sides, modifier(pairs, limits)?))
+}
+
+/// Finds the [`Rule::expr`] of a modifier, if there is one.
+fn modifier<'s>(
+    pairs: pest::iterators::Pairs<'s, Rule>,
+    limits: &Limits,
+) -> Result<Option<Expr<'s>>, ParseError> {
+    let Some(pair) = pairs.into_iter().find(|pair| pair.as_rule() == Rule::expr) else {
+        return Ok(None);
+    };
+    let expr = try_parse_to_ast_with(pair.into_inner(), limits)?;
+    limits.check(&expr)?;
+    Ok(Some(expr))
+}
+
+/// Rolls `count` dice with `sides` sides, keeping every face.
+fn roll(faces: &mut dyn FaceSource, count: usize, sides: i64) -> Result<Vec<i64>, ArithmeticError> {
+    let mut rolled = vec![0; count];
+    faces.fill_faces(sides, &mut rolled)?;
+    Ok(rolled)
+}
+
+/// Formats an optional modifier after a roll, like the ` + 1` in `pbta + 1`.
+struct Modifier<'a, 's>(&'a Option<Expr<'s>>);
+
+impl Display for Modifier<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(x) = self.0 else {
+            return Ok(());
+        };
+        let x = x.to_string();
+        match x.strip_prefix('-') {
+            Some(rest) => write!(f, " - {rest}"),
+            None => write!(f, " + {x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let limits = Limits::default();
+        let systems = Registry::default();
+        let keywords: Vec<_> = systems.systems().map(|s| s.keyword()).collect();
+        assert_eq!(keywords, ["blades", "pbta", "yz", "sw", "genesys"]);
+
+        let parse = |input| {
+            systems
+                .parse(input, &limits)
+                .map(|pool| pool.map(|(_, pool)| pool.to_string()))
+        };
+        let normalized = |input| parse(input).and_then(Result::ok);
+        assert_eq!(normalized("  PBTA   ").as_deref(), Some("pbta"));
+        assert_eq!(normalized("pbta -1").as_deref(), Some("pbta - 1"));
+        assert_eq!(normalized("pbta +1d4").as_deref(), Some("pbta + 1d4"));
+        assert_eq!(normalized("sw d8-1+2").as_deref(), Some("sw d8 - 1 + 2"));
+        assert_eq!(normalized("yz 5 PUSH").as_deref(), Some("yz 5 push"));
+        assert!(parse("pbtax 1").is_none());
+        assert!(parse("1d20").is_none());
+
+        assert!(matches!(parse("blades"), Some(Err(ParseError::Syntax(_)))));
+        assert!(matches!(
+            parse("sw d0"),
+            Some(Err(ParseError::ZeroSides { .. }))
+        ));
+        assert!(matches!(
+            parse("yz 20000"),
+            Some(Err(ParseError::Limit(LimitError::Dice { .. })))
+        ));
+    }
+
+    #[test]
+    fn test_with_system_replaces() {
+        let systems = Registry::new()
+            .with_system(blades::Blades)
+            .with_system(pbta::PoweredByTheApocalypse)
+            .with_system(blades::Blades);
+        let keywords: Vec<_> = systems.systems().map(|s| s.keyword()).collect();
+        assert_eq!(keywords, ["pbta", "blades"]);
+        assert!(systems.get("yz").is_none());
+    }
+}
@@ -0,0 +1,99 @@
+//! Powered by the Apocalypse games, like Apocalypse World and Dungeon World.
+//!
+//! Roll 2d6 and add a modifier: 10 or more is a strong hit, 7 to 9 a weak
+//! hit, and 6 or less a miss.
+
+use std::fmt::Display;
+
+use super::{GameSystem, Modifier, Pool, Reading, parse_modifier, roll};
+use crate::{
+    ast::{Expr, precedence::BinOp},
+    eval::{ArithmeticError, DiceRoller, checked_binop, faces::FaceSource},
+    limits::Limits,
+    parser::ParseError,
+};
+
+/// Powered by the Apocalypse, rolled like `pbta`, `pbta +1` or `pbta -1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoweredByTheApocalypse;
+
+impl GameSystem for PoweredByTheApocalypse {
+    fn name(&self) -> &'static str {
+        "Powered by the Apocalypse"
+    }
+
+    fn keyword(&self) -> &'static str {
+        "pbta"
+    }
+
+    fn bands(&self) -> &'static [&'static str] {
+        &["miss", "weak hit", "strong hit"]
+    }
+
+    fn parse<'s>(&self, args: &'s str, limits: &Limits) -> Result<Box<dyn Pool + 's>, ParseError> {
+        let modifier = parse_modifier(args, limits)?;
+        Ok(Box::new(PbtaPool { modifier }))
+    }
+}
+
+/// A Powered by the Apocalypse roll of 2d6, plus an optional modifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PbtaPool<'s> {
+    /// Any expression, like the `1` in `pbta +1` or the `1d4` in `pbta +1d4`.
+    pub modifier: Option<Expr<'s>>,
+}
+
+impl Pool for PbtaPool<'_> {
+    fn roll(&self, faces: &mut dyn FaceSource) -> Result<Reading, ArithmeticError> {
+        let rolled = roll(faces, 2, 6)?;
+        let modifier = match &self.modifier {
+            Some(m) => DiceRoller::new(&mut *faces).try_eval(m)?,
+            None => 0,
+        };
+        let value = checked_binop(BinOp::Add, rolled[0] + rolled[1], modifier)?;
+
+        let band = match value {
+            10.. => "strong hit",
+            7..=9 => "weak hit",
+            _ => "miss",
+        };
+        Ok(Reading {
+            band,
+            value,
+            faces: vec![rolled],
+            detail: None,
+        })
+    }
+}
+
+impl Display for PbtaPool<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pbta{}", Modifier(&self.modifier))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::faces::Fixed;
+
+    #[test]
+    fn test_bands() {
+        let limits = Limits::default();
+        let read = |args, faces: &[i64]| {
+            let reading = PoweredByTheApocalypse
+                .parse(args, &limits)
+                .unwrap()
+                .roll(&mut Fixed::new(faces.iter().copied()))
+                .unwrap();
+            (reading.band, reading.value)
+        };
+
+        assert_eq!(read("", &[3, 3]), ("miss", 6));
+        assert_eq!(read("+1", &[3, 3]), ("weak hit", 7));
+        assert_eq!(read("+ 3", &[4, 3]), ("strong hit", 10));
+        assert_eq!(read("-2", &[6, 5]), ("weak hit", 9));
+        // the modifier rolls after the 2d6
+        assert_eq!(read("+1d4", &[6, 3, 1]), ("strong hit", 10));
+    }
+}
@@ -0,0 +1,135 @@
+//! Savage Worlds, rolling a Wild Card's trait die alongside a wild die.
+//!
+//! Roll the trait die and a d6 wild die, add the modifier to each, and keep
+//! the higher total. Both dice *ace*: a die which rolls its highest face is
+//! rolled again and added. A 4 succeeds, and every 4 more is a raise.
+//! If both dice show a 1, it's a critical failure, whatever the modifier.
+
+use std::fmt::Display;
+
+use super::{GameSystem, Modifier, Pool, Reading, parse_trait_die};
+use crate::{
+    ast::{Expr, precedence::BinOp},
+    eval::{ArithmeticError, DiceRoller, checked_binop, faces::FaceSource},
+    limits::Limits,
+    parser::ParseError,
+};
+
+/// How many times a die aces before it stops, so a d1 can't roll forever.
+pub const MAX_ACES: usize = 100;
+
+/// Savage Worlds, rolled like `sw d8` or `sw d8 + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SavageWorlds;
+
+impl GameSystem for SavageWorlds {
+    fn name(&self) -> &'static str {
+        "Savage Worlds"
+    }
+
+    fn keyword(&self) -> &'static str {
+        "sw"
+    }
+
+    fn bands(&self) -> &'static [&'static str] {
+        &["critical failure", "failure", "success", "raise"]
+    }
+
+    fn parse<'s>(&self, args: &'s str, limits: &Limits) -> Result<Box<dyn Pool + 's>, ParseError> {
+        let (sides, modifier) = parse_trait_die(args, limits)?;
+        Ok(Box::new(SavageWorldsPool { sides, modifier }))
+    }
+}
+
+/// A Savage Worlds roll of a trait die with `sides` sides, and a d6 wild die.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavageWorldsPool<'s> {
+    pub sides: i64,
+    /// Any expression, like the `1` in `sw d8 + 1`. It's rolled once, and
+    /// added to both dice.
+    pub modifier: Option<Expr<'s>>,
+}
+
+impl Pool for SavageWorldsPool<'_> {
+    fn roll(&self, faces: &mut dyn FaceSource) -> Result<Reading, ArithmeticError> {
+        let trait_die = ace(faces, self.sides)?;
+        let wild_die = ace(faces, 6)?;
+        let modifier = match &self.modifier {
+            Some(m) => DiceRoller::new(&mut *faces).try_eval(m)?,
+            None => 0,
+        };
+
+        let total = |rolled: &[i64]| {
+            let sum = rolled
+                .iter()
+                .try_fold(0, |sum, &f| checked_binop(BinOp::Add, sum, f))?;
+            checked_binop(BinOp::Add, sum, modifier)
+        };
+        let value = total(&trait_die)?.max(total(&wild_die)?);
+
+        let band = if trait_die[0] == 1 && wild_die[0] == 1 {
+            "critical failure"
+        } else {
+            match value {
+                8.. => "raise",
+                4..=7 => "success",
+                _ => "failure",
+            }
+        };
+        Ok(Reading {
+            band,
+            value,
+            faces: vec![trait_die, wild_die],
+            detail: None,
+        })
+    }
+}
+
+/// Rolls a die, and rolls it again each time it shows its highest face.
+fn ace(faces: &mut dyn FaceSource, sides: i64) -> Result<Vec<i64>, ArithmeticError> {
+    let mut rolled = vec![faces.face(sides)?];
+    while rolled.last() == Some(&sides) && rolled.len() <= MAX_ACES {
+        rolled.push(faces.face(sides)?);
+    }
+    Ok(rolled)
+}
+
+impl Display for SavageWorldsPool<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sw d{}{}", self.sides, Modifier(&self.modifier))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::faces::{Fixed, Max};
+
+    #[test]
+    fn test_bands() {
+        let read = |sides, modifier, faces: &[i64]| {
+            let pool = SavageWorldsPool {
+                sides,
+                modifier: Some(Expr::int(modifier)),
+            };
+            let reading = pool.roll(&mut Fixed::new(faces.iter().copied())).unwrap();
+            (reading.band, reading.value)
+        };
+
+        assert_eq!(read(8, 0, &[3, 2]), ("failure", 3));
+        assert_eq!(read(8, 1, &[3, 2]), ("success", 4));
+        // the wild die aces: 6 + 6 + 3
+        assert_eq!(read(4, 0, &[2, 6, 6, 3]), ("raise", 15));
+        assert_eq!(read(8, 5, &[1, 1]), ("critical failure", 6));
+    }
+
+    #[test]
+    fn test_aces_stop() {
+        let pool = SavageWorldsPool {
+            sides: 1,
+            modifier: None,
+        };
+        let reading = pool.roll(&mut Max).unwrap();
+        assert_eq!(reading.faces[0].len(), MAX_ACES + 1);
+    }
+}
@@ -0,0 +1,104 @@
+//! Year Zero Engine games, like Mutant: Year Zero and Alien.
+//!
+//! Roll a pool of d6 and count the 6s: every 6 is a success, and any success
+//! at all succeeds. A roll can be *pushed* once, rerolling every die which
+//! shows neither a 6 nor a 1. The 1s stay, since they are the cost of pushing.
+
+use std::fmt::Display;
+
+use super::{GameSystem, Pool, Reading, parse_pool, roll};
+use crate::{
+    eval::{ArithmeticError, faces::FaceSource},
+    limits::Limits,
+    parser::{ParseError, Rule},
+};
+
+/// The Year Zero Engine, rolled like `yz 5`, or `yz 5 push` to push it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct YearZero;
+
+impl GameSystem for YearZero {
+    fn name(&self) -> &'static str {
+        "Year Zero Engine"
+    }
+
+    fn keyword(&self) -> &'static str {
+        "yz"
+    }
+
+    fn bands(&self) -> &'static [&'static str] {
+        &["failure", "success"]
+    }
+
+    fn parse<'s>(&self, args: &'s str, limits: &Limits) -> Result<Box<dyn Pool + 's>, ParseError> {
+        let (dice, push) = parse_pool(Rule::pushed_pool, args, limits)?;
+        Ok(Box::new(YearZeroPool { dice, push }))
+    }
+}
+
+/// A Year Zero roll of `dice` d6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YearZeroPool {
+    pub dice: i64,
+    /// Whether the roll is pushed right away. The [`Reading::faces`] then
+    /// hold the first roll, followed by the pushed roll.
+    pub push: bool,
+}
+
+impl Pool for YearZeroPool {
+    fn roll(&self, faces: &mut dyn FaceSource) -> Result<Reading, ArithmeticError> {
+        let mut rolled = vec![roll(faces, self.dice as usize, 6)?];
+
+        if self.push {
+            let mut pushed = rolled[0].clone();
+            for face in pushed.iter_mut().filter(|f| !matches!(f, 1 | 6)) {
+                *face = faces.face(6)?;
+            }
+            rolled.push(pushed);
+        }
+
+        let successes = rolled.last().unwrap().iter().filter(|&&f| f == 6).count() as i64;
+        Ok(Reading {
+            band: if successes > 0 { "success" } else { "failure" },
+            value: successes,
+            faces: rolled,
+            detail: None,
+        })
+    }
+}
+
+impl Display for YearZeroPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "yz {}", self.dice)?;
+        if self.push {
+            write!(f, " push")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::faces::Fixed;
+
+    #[test]
+    fn test_push() {
+        let pool = YearZeroPool {
+            dice: 4,
+            push: false,
+        };
+        let reading = pool.roll(&mut Fixed::new([6, 1, 3, 6])).unwrap();
+        assert_eq!((reading.band, reading.value), ("success", 2));
+        assert_eq!(reading.faces, [[6, 1, 3, 6]]);
+
+        // only the 3 and the 4 are rerolled
+        let pool = YearZeroPool { push: true, ..pool };
+        let reading = pool.roll(&mut Fixed::new([1, 3, 6, 4, 6, 2])).unwrap();
+        assert_eq!((reading.band, reading.value), ("success", 2));
+        assert_eq!(reading.faces, [[1, 3, 6, 4], [1, 6, 6, 2]]);
+
+        let reading = pool.roll(&mut Fixed::new([2])).unwrap();
+        assert_eq!((reading.band, reading.value), ("failure", 0));
+    }
+}
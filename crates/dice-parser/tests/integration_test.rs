@@ -1,5 +1,9 @@
 use common::ParseEvalTest;
-use dice_parser::ast::Expr;
+use dice_parser::ast::{
+    Expr,
+    crit::{Compare, Crits, Threshold},
+    list::ListOp,
+};
 
 mod common;
 
@@ -33,3 +37,171 @@ fn test_parse_eval() {
         c.doit();
     }
 }
+
+#[test]
+fn test_parse_crits() {
+    let threshold = |compare, value| Some(Threshold { compare, value });
+
+    let cases = vec![ParseEvalTest {
+        to_parse: "1d1cs + 1D1CS>=19cf",
+
+        tree_exp: {
+            use Expr as e;
+            let max = Crits {
+                success: threshold(Compare::Eq, 1),
+                failure: None,
+            };
+            let both = Crits {
+                success: threshold(Compare::Ge, 19),
+                failure: threshold(Compare::Eq, 1),
+            };
+
+            Some(e::add(e::CritDice(1, 1, max), e::CritDice(1, 1, both)))
+        },
+        eval_exp: Some(2),
+
+        as_str: "1d1cs1 + 1d1cs>=19cf1",
+        rng: None,
+    }];
+
+    for c in cases.into_iter() {
+        c.doit();
+    }
+}
+
+#[test]
+fn test_parse_lists() {
+    let cases = vec![
+        ParseEvalTest {
+            to_parse: "4d1s + 6d1 UNIQUE[one] * 2",
+
+            tree_exp: {
+                use Expr as e;
+                Some(e::add(
+                    e::list(e::dice(4, 1), ListOp::Sort),
+                    e::mul(
+                        e::label(e::list(e::dice(6, 1), ListOp::Unique), "one"),
+                        e::int(2),
+                    ),
+                ))
+            },
+            eval_exp: Some(6),
+
+            as_str: "4d1s + 6d1 unique[one] * 2",
+            rng: None,
+        },
+        ParseEvalTest {
+            to_parse: "-(6d1 group)matches - (1 + 2)uniques",
+
+            tree_exp: {
+                use Expr as e;
+                Some(e::sub(
+                    e::not(e::list(
+                        e::list(e::dice(6, 1), ListOp::Group),
+                        ListOp::Matches,
+                    )),
+                    e::list(
+                        e::list(e::add(e::int(1), e::int(2)), ListOp::Unique),
+                        ListOp::Sort,
+                    ),
+                ))
+            },
+            eval_exp: Some(-4),
+
+            as_str: "-6d1 group matches - (1 + 2) unique s",
+            rng: None,
+        },
+        ParseEvalTest {
+            to_parse: "4d6 sort",
+            tree_exp: None,
+            eval_exp: None,
+            as_str: "",
+            rng: None,
+        },
+    ];
+
+    for c in cases.into_iter() {
+        c.doit();
+    }
+}
+
+#[test]
+fn test_parse_functions() {
+    let cases = vec![
+        ParseEvalTest {
+            to_parse: "sum(3d1) * 2 + MAX(4d1[a]) - min(2)",
+
+            tree_exp: {
+                use Expr as e;
+                Some(e::sub(
+                    e::add(
+                        e::mul(e::list(e::dice(3, 1), ListOp::Sum), e::int(2)),
+                        e::list(e::label(e::dice(4, 1), "a"), ListOp::Max),
+                    ),
+                    e::list(e::int(2), ListOp::Min),
+                ))
+            },
+            eval_exp: Some(5),
+
+            as_str: "sum(3d1) * 2 + max(4d1[a]) - min(2)",
+            rng: None,
+        },
+        ParseEvalTest {
+            to_parse: "count(8d1>=1) + count(2d1 unique) + count(8d1 = 2)s",
+
+            tree_exp: {
+                use Expr as e;
+                let count = |compare, value| ListOp::Count(Some(Threshold { compare, value }));
+                Some(e::add(
+                    e::add(
+                        e::list(e::dice(8, 1), count(Compare::Ge, 1)),
+                        e::list(e::list(e::dice(2, 1), ListOp::Unique), ListOp::Count(None)),
+                    ),
+                    e::list(e::list(e::dice(8, 1), count(Compare::Eq, 2)), ListOp::Sort),
+                ))
+            },
+            eval_exp: Some(9),
+
+            as_str: "count(8d1 >= 1) + count(2d1 unique) + count(8d1 = 2)s",
+            rng: None,
+        },
+        ParseEvalTest {
+            to_parse: "max(4d6 >= 3)",
+            tree_exp: None,
+            eval_exp: None,
+            as_str: "",
+            rng: None,
+        },
+    ];
+
+    for c in cases.into_iter() {
+        c.doit();
+    }
+}
+
+#[test]
+fn test_parse_ranges() {
+    use dice_parser::parser::{DiceParser, Parser as _, Rule, try_parse_to_ast};
+
+    let parse = |input| {
+        let mut pairs = DiceParser::parse(Rule::equation, input).unwrap();
+        try_parse_to_ast(pairs.next().unwrap().into_inner()).map_err(|e| e.to_string())
+    };
+
+    assert_eq!(
+        parse("10000 + 1d10000"),
+        Ok(Expr::add(Expr::int(10000), Expr::dice(1, 10000)))
+    );
+    assert_eq!(
+        parse("1 + 99999999999"),
+        Err("number `99999999999` at 4..15 is out of range, expected 0..=1000000000".to_owned())
+    );
+    assert_eq!(
+        parse("0d20"),
+        Err("dice `0d20` at 0..4 must roll at least one die".to_owned())
+    );
+    assert_eq!(
+        parse("2 * 3d0"),
+        Err("dice `3d0` at 4..7 must have at least one side".to_owned())
+    );
+}
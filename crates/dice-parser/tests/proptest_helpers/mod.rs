@@ -1,6 +1,11 @@
 use dice_parser::{
-    ast::{Expr, precedence::Op},
-    eval::{ArithmeticError, DiceRoller, DivideByZeroError},
+    ast::{
+        Expr,
+        crit::{Compare, Threshold},
+        list::ListOp,
+        precedence::Op,
+    },
+    eval::{ArithmeticError, DiceRoller},
 };
 use proptest::{
     prelude::{Strategy, any},
@@ -13,13 +18,16 @@ pub fn naive_try_eval<R: TryCryptoRng + Rng>(
     expr: &Expr,
 ) -> Result<i64, ArithmeticError> {
     match expr {
-        Expr::Int(x) => Ok(*x as i64),
-        Expr::Dice(c, s) => Ok(roller.roll(*c, *s)),
+        Expr::Int(x) => Ok(*x),
+        Expr::Dice(c, s) | Expr::CritDice(c, s, _) => roller.roll(*c, *s),
         Expr::Not(expr) => naive_try_eval::<R>(roller, expr).map(|x| -x),
         Expr::Label(expr, _) => naive_try_eval::<R>(roller, expr),
+        Expr::List(..) => Ok(naive_faces(roller, expr)?.iter().sum()),
         Expr::Add(lhs, rhs) | Expr::Sub(lhs, rhs) | Expr::Mul(lhs, rhs) | Expr::Div(lhs, rhs) => {
-            let left = naive_try_eval::<R>(roller, lhs)?;
+            // the right operand is evaluated first, like `DiceRoller` does, so
+            // an error on each side, like a degenerate roll, is the same one
             let right = naive_try_eval::<R>(roller, rhs)?;
+            let left = naive_try_eval::<R>(roller, lhs)?;
 
             {
                 match expr {
@@ -31,7 +39,7 @@ pub fn naive_try_eval<R: TryCryptoRng + Rng>(
                                 rhs: Some(right),
                             })
                         },
-                        |x| Ok(x),
+                        Ok,
                     ),
                     Expr::Sub(..) => left.checked_sub(right).map_or_else(
                         || {
@@ -41,7 +49,7 @@ pub fn naive_try_eval<R: TryCryptoRng + Rng>(
                                 rhs: Some(right),
                             })
                         },
-                        |x| Ok(x),
+                        Ok,
                     ),
                     Expr::Mul(..) => left.checked_mul(right).map_or_else(
                         || {
@@ -51,7 +59,7 @@ pub fn naive_try_eval<R: TryCryptoRng + Rng>(
                                 rhs: Some(right),
                             })
                         },
-                        |x| Ok(x),
+                        Ok,
                     ),
                     Expr::Div(..) => {
                         if right == 0 {
@@ -67,15 +75,69 @@ pub fn naive_try_eval<R: TryCryptoRng + Rng>(
     }
 }
 
+/// The faces a list operation reads from `expr`, where any other value is one face.
+///
+/// [`ListOp::Matches`] returns its count as the only face.
+fn naive_faces<R: TryCryptoRng + Rng>(
+    roller: &mut DiceRoller<R>,
+    expr: &Expr,
+) -> Result<Vec<i64>, ArithmeticError> {
+    match expr {
+        Expr::Dice(c, s) | Expr::CritDice(c, s, _) => roller.roll_faces(*c, *s),
+        Expr::Label(expr, _) => naive_faces::<R>(roller, expr),
+        Expr::List(expr, op) => {
+            let mut faces = naive_faces::<R>(roller, expr)?;
+            faces.sort();
+            let count = |face: &i64| faces.iter().filter(|&f| f == face).count();
+
+            Ok(match op {
+                ListOp::Sort => faces,
+                ListOp::Unique => {
+                    let mut unique = faces.clone();
+                    unique.dedup();
+                    unique
+                }
+                ListOp::Matches => {
+                    let mut matched: Vec<i64> =
+                        faces.iter().copied().filter(|f| count(f) > 1).collect();
+                    matched.dedup();
+                    vec![matched.len() as i64]
+                }
+                ListOp::Group => {
+                    let best = faces
+                        .iter()
+                        .copied()
+                        .max_by_key(|f| (count(f), *f))
+                        .unwrap();
+                    vec![best; count(&best)]
+                }
+                ListOp::Sum => vec![faces.iter().sum()],
+                ListOp::Max => vec![*faces.iter().max().unwrap()],
+                ListOp::Min => vec![*faces.iter().min().unwrap()],
+                ListOp::Count(None) => vec![faces.len() as i64],
+                ListOp::Count(Some(t)) => {
+                    vec![
+                        faces
+                            .iter()
+                            .filter(|&&f| t.compare.holds(f, t.value))
+                            .count() as i64,
+                    ]
+                }
+            })
+        }
+        expr => Ok(vec![naive_try_eval::<R>(roller, expr)?]),
+    }
+}
+
 // Thanks to: <https://github.com/inanna-malick/recursion/blob/main/recursion-tests/src/expr/naive.rs#L70>
-pub fn arb_expr<'a>() -> impl Strategy<Value = Expr<'static>> {
+pub fn arb_expr() -> impl Strategy<Value = Expr<'static>> {
     let leaf = prop_oneof![
-        any::<i8>().prop_map(|x| Expr::int(x as i32)),
+        any::<i8>().prop_map(|x| Expr::int(x as i64)),
         (any::<u8>(), any::<u8>()).prop_filter_map("zero or negative dice roll", |(a, b)| {
-            if a <= 0 || b <= 0 {
+            if a == 0 || b == 0 {
                 None
             } else {
-                Some(Expr::dice(a as i32, b as i32))
+                Some(Expr::dice(a as i64, b as i64))
             }
         })
     ];
@@ -85,21 +147,39 @@ pub fn arb_expr<'a>() -> impl Strategy<Value = Expr<'static>> {
             (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::sub(a, b)),
             (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::mul(a, b)),
             (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::div(a, b)),
-            (inner.clone()).prop_map(|a| Expr::not(a)),
-            (inner).prop_map(|a| Expr::label(a, any::<String>())),
+            (inner.clone()).prop_map(Expr::not),
+            (inner.clone()).prop_map(|a| Expr::label(a, any::<String>())),
+            (inner, arb_list_op()).prop_map(|(a, op)| Expr::list(a, op)),
         ]
     })
 }
 
-pub fn arb_add_expr<'a>() -> impl Strategy<Value = Expr<'static>> {
+pub fn arb_list_op() -> impl Strategy<Value = ListOp> {
+    proptest::sample::select(vec![
+        ListOp::Sort,
+        ListOp::Unique,
+        ListOp::Matches,
+        ListOp::Group,
+        ListOp::Sum,
+        ListOp::Max,
+        ListOp::Min,
+        ListOp::Count(None),
+        ListOp::Count(Some(Threshold {
+            compare: Compare::Gt,
+            value: 3,
+        })),
+    ])
+}
+
+pub fn arb_add_expr() -> impl Strategy<Value = Expr<'static>> {
     use proptest::prelude::*;
     let leaf = prop_oneof![
-        any::<i8>().prop_map(|x| Expr::int(x as i32)),
+        any::<i8>().prop_map(|x| Expr::int(x as i64)),
         (any::<u8>(), any::<u8>()).prop_filter_map("zero or negative dice roll", |(a, b)| {
-            if a <= 0 || b <= 0 {
+            if a == 0 || b == 0 {
                 None
             } else {
-                Some(Expr::dice(a as i32, b as i32))
+                Some(Expr::dice(a as i64, b as i64))
             }
         })
     ];
@@ -108,21 +188,21 @@ pub fn arb_add_expr<'a>() -> impl Strategy<Value = Expr<'static>> {
     })
 }
 
-pub fn arb_no_div_expr<'a>() -> impl Strategy<Value = Expr<'static>> {
+pub fn arb_no_div_expr() -> impl Strategy<Value = Expr<'static>> {
     use proptest::prelude::*;
     let leaf = prop_oneof![
-        any::<i8>().prop_map(|x| Expr::int(x as i32)),
+        any::<i8>().prop_map(|x| Expr::int(x as i64)),
         (any::<u8>(), any::<u8>()).prop_filter_map("zero or negative dice roll", |(a, b)| {
-            if a <= 0 || b <= 0 {
+            if a == 0 || b == 0 {
                 None
             } else {
-                Some(Expr::dice(a as i32, b as i32))
+                Some(Expr::dice(a as i64, b as i64))
             }
         })
     ];
     leaf.prop_recursive(8, 256, 10, move |inner| {
         prop_oneof![
-            (inner.clone()).prop_map(|a| Expr::not(a)),
+            (inner.clone()).prop_map(Expr::not),
             (inner.clone()).prop_map(|a| Expr::label(a, any::<String>())),
             (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::add(a, b)),
             (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::sub(a, b)),
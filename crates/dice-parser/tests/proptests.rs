@@ -1,4 +1,3 @@
-mod common;
 mod proptest_helpers;
 
 use pest::Parser as _;
@@ -6,7 +5,7 @@ use proptest::prelude::*;
 
 use dice_mocks::*;
 use dice_parser::{
-    ast::Expr,
+    ast::{Expr, canonical::equivalent},
     eval::DiceRoller,
     parser::{Rule, parse_expr},
 };
@@ -55,7 +54,7 @@ proptest! {
         assert_eq!(eval_naive, eval_gat, "naive impl differs from gat impl!");
 
         // check also that there are no parenthesis in an expression with just addition
-        assert_eq!(expr.to_string().find(|c| c == '(' || c == ')'), None, "redundant parenthesis in eval_gat!");
+        assert_eq!(expr.to_string().find(['(', ')']), None, "redundant parenthesis in eval_gat!");
     }
 }
 
@@ -71,7 +70,7 @@ proptest! {
         let eval_naive = naive_try_eval(&mut roller_naive, expr.as_ref());
 
         assert_eq!(eval_naive, eval_gat);
-        assert!(expr.to_string().len() > 0);
+        assert!(!expr.to_string().is_empty());
     }
 }
 
@@ -87,6 +86,16 @@ proptest! {
     }
 }
 
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10000))]
+    #[test]
+    fn canonical_is_idempotent(expr in arb_expr()) {
+        let canonical = expr.canonical();
+        assert_eq!(canonical.canonical(), canonical, "canonical form of `{}` is not stable", expr);
+        assert!(equivalent(&expr, &canonical), "`{}` not equivalent to `{}`", expr, canonical);
+    }
+}
+
 #[test]
 fn proptest_regressions() {
     let cases = [
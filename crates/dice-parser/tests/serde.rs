@@ -0,0 +1,42 @@
+#![cfg(feature = "serde")]
+
+use dice_parser::{
+    ast::{Expr, OwnedExpr},
+    eval::{ArithmeticError, distribution::Distribution},
+    parser::{DiceParser, Parser as _, Rule, try_parse_to_ast},
+};
+
+#[test]
+fn test_expr_roundtrip() {
+    let json = {
+        let input = String::from("2d6[slashing] + 1d8[fire] / -2");
+        let mut pairs = DiceParser::parse(Rule::equation, &input).unwrap();
+        let tree = try_parse_to_ast(pairs.next().unwrap().into_inner()).unwrap();
+        serde_json::to_string(&tree).unwrap()
+    };
+
+    let tree: OwnedExpr = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        tree,
+        Expr::add(
+            Expr::label(Expr::dice(2, 6), "slashing"),
+            Expr::div(
+                Expr::label(Expr::dice(1, 8), "fire"),
+                Expr::not(Expr::int(2))
+            ),
+        )
+    );
+}
+
+#[test]
+fn test_results_roundtrip() {
+    let err = ArithmeticError::DivideByZero(3);
+    let json = serde_json::to_string(&err).unwrap();
+    assert_eq!(serde_json::from_str::<ArithmeticError>(&json).unwrap(), err);
+
+    let dist = Distribution::of(&Expr::dice(2, 6)).unwrap();
+    let json = serde_json::to_string(&dist).unwrap();
+    assert_eq!(serde_json::from_str::<Distribution>(&json).unwrap(), dist);
+
+    assert!(serde_json::from_str::<Distribution>(r#"{"outcomes":{"1":1},"total":2}"#).is_err());
+}
@@ -14,7 +14,7 @@ use winnow::{
 
 fn pratt_parser(i: &mut Stream<'_>) -> winnow::Result<i64, EmptyError> {
     use pratt::precedence;
-    use pratt::precedence::{Assoc, Power};
+    use pratt::precedence::{Assoc, BinaryFold, Power, UnaryFold};
 
     fn parser<'i>(start_power: Power) -> impl Parser<Stream<'i>, i64, EmptyError> {
         move |i: &mut Stream<'i>| {
@@ -26,18 +26,18 @@ fn pratt_parser(i: &mut Stream<'_>) -> winnow::Result<i64, EmptyError> {
                             _ => digit1.parse_to::<i64>()
                     },
                     dispatch! {any;
-                        b'+' => empty.value((9, (|_: &mut _, a| Ok(a)) as _)),
-                        b'-' => empty.value((9, (|_: &mut _, a: i64| Ok(-a)) as _)),
+                        b'+' => empty.value((9, (|_: &mut _, a| Ok(a)) as UnaryFold<_, _, _>)),
+                        b'-' => empty.value((9, (|_: &mut _, a: i64| Ok(-a)) as UnaryFold<_, _, _>)),
                         _ => fail,
                     },
-                    fail,
+                    fail::<_, (Power, UnaryFold<_, _, _>), _>,
                     dispatch! {any;
-                        b'+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as _)),
-                        b'-' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a - b)) as _)),
-                        b'*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as _)),
-                        b'/' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a / b)) as _)),
-                        b'%' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a % b)) as _)),
-                        b'^' => empty.value((Assoc::Left(9), (|_: &mut _, a, b| Ok(a ^ b)) as _)),
+                        b'+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>)),
+                        b'-' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a - b)) as BinaryFold<_, _, _>)),
+                        b'*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as BinaryFold<_, _, _>)),
+                        b'/' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a / b)) as BinaryFold<_, _, _>)),
+                        b'%' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a % b)) as BinaryFold<_, _, _>)),
+                        b'^' => empty.value((Assoc::Left(9), (|_: &mut _, a, b| Ok(a ^ b)) as BinaryFold<_, _, _>)),
                         _ => fail
                     },
                 )
@@ -49,7 +49,7 @@ fn pratt_parser(i: &mut Stream<'_>) -> winnow::Result<i64, EmptyError> {
     parser(0).parse_next(i)
 }
 fn shunting_yard_parser(i: &mut Stream<'_>) -> winnow::Result<i64, EmptyError> {
-    use pratt::precedence::{Assoc, Power};
+    use pratt::precedence::{Assoc, BinaryFold, Power, UnaryFold};
     use pratt::shunting_yard;
 
     fn parser<'i>(start_power: Power) -> impl Parser<Stream<'i>, i64, EmptyError> {
@@ -62,18 +62,18 @@ fn shunting_yard_parser(i: &mut Stream<'_>) -> winnow::Result<i64, EmptyError> {
                             _ => digit1.parse_to::<i64>()
                     },
                     dispatch! {any;
-                        b'+' => empty.value((9, (|_: &mut _, a| Ok(a)) as _)),
-                        b'-' => empty.value((9, (|_: &mut _, a: i64| Ok(-a)) as _)),
+                        b'+' => empty.value((9, (|_: &mut _, a| Ok(a)) as UnaryFold<_, _, _>)),
+                        b'-' => empty.value((9, (|_: &mut _, a: i64| Ok(-a)) as UnaryFold<_, _, _>)),
                         _ => fail,
                     },
-                    fail,
+                    fail::<_, (Power, UnaryFold<_, _, _>), _>,
                     dispatch! {any;
-                        b'+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as _)),
-                        b'-' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a - b)) as _)),
-                        b'*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as _)),
-                        b'/' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a / b)) as _)),
-                        b'%' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a % b)) as _)),
-                        b'^' => empty.value((Assoc::Left(9), (|_: &mut _, a, b| Ok(a ^ b)) as _)),
+                        b'+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>)),
+                        b'-' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a - b)) as BinaryFold<_, _, _>)),
+                        b'*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as BinaryFold<_, _, _>)),
+                        b'/' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a / b)) as BinaryFold<_, _, _>)),
+                        b'%' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a % b)) as BinaryFold<_, _, _>)),
+                        b'^' => empty.value((Assoc::Left(9), (|_: &mut _, a, b| Ok(a ^ b)) as BinaryFold<_, _, _>)),
                         _ => fail
                     },
                 )
@@ -87,7 +87,7 @@ fn shunting_yard_parser(i: &mut Stream<'_>) -> winnow::Result<i64, EmptyError> {
 
 fn parse_expression(c: &mut Criterion) {
     // remove the last `\n`
-    let input = &CORPUS.as_bytes()[0..CORPUS.as_bytes().len() - 1];
+    let input = &CORPUS.as_bytes()[0..CORPUS.len() - 1];
     let mut group = c.benchmark_group("pratt");
 
     pratt_parser.parse(input).expect("pratt should parse");
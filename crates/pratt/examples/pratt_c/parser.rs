@@ -1,5 +1,8 @@
-use winnow::combinator::{cut_err, empty, fail, not, opt, peek, separated_pair, trace};
-use winnow::error::{ContextError, ErrMode};
+use pratt::precedence::{
+    self, Assoc, BinaryFold, CloseDelimiter, DelimitedFold, TernaryFold, UnaryFold,
+};
+use winnow::combinator::{cut_err, empty, fail, not, peek, trace};
+use winnow::error::{ContextError, ErrMode, ParserError};
 use winnow::prelude::*;
 use winnow::token::{any, take, take_while};
 use winnow::{
@@ -62,151 +65,238 @@ pub(crate) enum Expr {
     BitXor(Box<Expr>, Box<Expr>),
     BitAnd(Box<Expr>, Box<Expr>),
     BitwiseNot(Box<Expr>),
+
+    // A missing operand, only made by the `pratt_c_recovery` example.
+    #[allow(dead_code)]
+    Error,
 }
 
 // Parser definition
 
+type Error = ErrMode<ContextError>;
+
 pub(crate) fn pratt_parser(i: &mut &str) -> ModalResult<Expr> {
-    use pratt::precedence::{self, Assoc};
-    // precedence is based on https://en.cppreference.com/w/c/language/operator_precedence
-    // but specified in reverse order, because the `cppreference` table
-    // uses `descending` precedence, but we need ascending one
-    fn parser<'i>(start_power: i64) -> impl Parser<&'i str, Expr, ErrMode<ContextError>> {
-        move |i: &mut &str| {
-            precedence::precedence(
+    parser(0).parse_next(i)
+}
+
+// precedence is based on https://en.cppreference.com/w/c/language/operator_precedence
+// but specified in reverse order, because the `cppreference` table
+// uses `descending` precedence, but we need ascending one
+fn parser<'i>(start_power: i64) -> impl Parser<&'i str, Expr, Error> {
+    move |i: &mut &str| {
+        precedence::mixfix(
             start_power,
-            trace(
-                "operand",
-                delimited(
-                    multispace0,
-                    dispatch! {peek(any);
-                        '(' => delimited('(',  parser(0).map(|e| Expr::Paren(Box::new(e))), cut_err(')')),
-                        _ => alt((
-                            identifier.map(|s| Expr::Name(s.into())),
-                            digit1.parse_to::<i64>().map(Expr::Value)
-                        )),
-                    },
-                    multispace0,
-                ),
-            ),
-            trace(
-                "prefix",
-                delimited(
-                    multispace0,
-                    dispatch! {any;
-                        '+' => alt((
-                            // ++
-                            '+'.value((18, (|_: &mut _, a| Ok(Expr::PreIncr(Box::new(a)))) as _)),
-                            empty.value((18, (|_: &mut _, a| Ok(a)) as _))
-                        )),
-                        '-' =>  alt((
-                            // --
-                            '-'.value((18, (|_: &mut _, a| Ok(Expr::PreDecr(Box::new(a)))) as _)),
-                            empty.value((18, (|_: &mut _, a| Ok(Expr::Neg(Box::new(a)))) as _))
-                        )),
-                        '&' => empty.value((18, (|_: &mut _, a| Ok(Expr::Addr(Box::new(a)))) as _)),
-                        '*' => empty.value((18, (|_: &mut _, a| Ok(Expr::Deref(Box::new(a)))) as _)),
-                        '!' => empty.value((18, (|_: &mut _, a| Ok(Expr::Not(Box::new(a)))) as _)),
-                        '~' => empty.value((18, (|_: &mut _, a| Ok(Expr::BitwiseNot(Box::new(a)))) as _)),
-                        _ => fail
-                    },
-                    multispace0,
-                ),
-            ),
-            trace(
-                "postfix",
-                delimited(
-                    multispace0,
-                    alt((
-                        dispatch! {any;
-                            '!' => not('=').value((19, (|_: &mut _, a| Ok(Expr::Fac(Box::new(a)))) as _)),
-                            '?' => empty.value((3, (|i: &mut &str, cond| {
-                                let (left, right) = cut_err(separated_pair(parser(0), delimited(multispace0, ':', multispace0), parser(3))).parse_next(i)?;
-                                Ok(Expr::Ternary(Box::new(cond), Box::new(left), Box::new(right)))
-                            }) as _)),
-                            '[' => empty.value((20, (|i: &mut &str, a| {
-                                let index = delimited(multispace0, parser(0), (multispace0, cut_err(']'), multispace0)).parse_next(i)?;
-                                Ok(Expr::Index(Box::new(a), Box::new(index)))
-                            }) as _)),
-                            '(' => empty.value((20, (|i: &mut &str, a| {
-                                let args = delimited(multispace0, opt(parser(0)), (multispace0, cut_err(')'), multispace0)).parse_next(i)?;
-                                Ok(Expr::FunctionCall(Box::new(a), args.map(Box::new)))
-                            }) as _)),
-                            _ => fail,
-                        },
-                        dispatch! {take(2usize);
-                            "++" => empty.value((20, (|_: &mut _, a| Ok(Expr::PostIncr(Box::new(a)))) as _)),
-                            "--" => empty.value((20, (|_: &mut _, a| Ok(Expr::PostDecr(Box::new(a)))) as _)),
-                            _ => fail,
-                        },
-                    )),
-                    multispace0,
-                ),
-            ),
-            trace(
-                "infix",
-                alt((
-                    dispatch! {any;
-                        '*' => alt((
-                            // **
-                            "*".value((Assoc::Right(28), (|_: &mut _, a, b| Ok(Expr::Pow(Box::new(a), Box::new(b)))) as _)),
-                            empty.value((Assoc::Left(16), (|_: &mut _, a, b| Ok(Expr::Mul(Box::new(a), Box::new(b)))) as _)),
-                        )),
-                        '/' => empty.value((Assoc::Left(16), (|_: &mut _, a, b| Ok(Expr::Div(Box::new(a), Box::new(b)))) as _)),
-                        '%' => empty.value((Assoc::Left(16), (|_: &mut _, a, b| Ok(Expr::Rem(Box::new(a), Box::new(b)))) as _)),
-
-                        '+' => empty.value((Assoc::Left(14), (|_: &mut _, a, b| Ok(Expr::Add(Box::new(a), Box::new(b)))) as _)),
-                        '-' => alt((
-                            dispatch!{take(2usize);
-                                "ne" => empty.value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::NotEq(Box::new(a), Box::new(b)))) as _)),
-                                "eq" => empty.value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::Eq(Box::new(a), Box::new(b)))) as _)),
-                                "gt" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Greater(Box::new(a), Box::new(b)))) as _)),
-                                "ge" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::GreaterEqual(Box::new(a), Box::new(b)))) as _)),
-                                "lt" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Less(Box::new(a), Box::new(b)))) as _)),
-                                "le" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::LessEqual(Box::new(a), Box::new(b)))) as _)),
-                                _ => fail
-                            },
-                            '>'.value((Assoc::Left(20), (|_: &mut _, a, b| Ok(Expr::ArrowOp(Box::new(a), Box::new(b)))) as _)),
-                            empty.value((Assoc::Left(14), (|_: &mut _, a, b| Ok(Expr::Sub(Box::new(a), Box::new(b)))) as _))
-                        )),
-                        '.' => empty.value((Assoc::Left(20), (|_: &mut _, a, b| Ok(Expr::Dot(Box::new(a), Box::new(b)))) as _)),
-                        '&' => alt((
-                            // &&
-                            "&".value((Assoc::Left(6), (|_: &mut _, a, b| Ok(Expr::And(Box::new(a), Box::new(b)))) as _)  ),
-
-                            empty.value((Assoc::Left(12), (|_: &mut _, a, b| Ok(Expr::BitAnd(Box::new(a), Box::new(b)))) as _)),
-                        )),
-                        '^' => empty.value((Assoc::Left(8), (|_: &mut _, a, b| Ok(Expr::BitXor(Box::new(a), Box::new(b)))) as _)),
-                        '=' => alt((
-                            // ==
-                            "=".value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::Eq(Box::new(a), Box::new(b)))) as _)),
-                            empty.value((Assoc::Right(2), (|_: &mut _, a, b| Ok(Expr::Assign(Box::new(a), Box::new(b)))) as _))
-                        )),
-
-                        '>' => alt((
-                            // >=
-                            "=".value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::GreaterEqual(Box::new(a), Box::new(b)))) as _)),
-                            empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Greater(Box::new(a), Box::new(b)))) as _))
-                        )),
-                        '<' => alt((
-                            // <=
-                            "=".value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::LessEqual(Box::new(a), Box::new(b)))) as _)),
-                            empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Less(Box::new(a), Box::new(b)))) as _))
-                        )),
-                        ',' => empty.value((Assoc::Left(0), (|_: &mut _, a, b| Ok(Expr::Comma(Box::new(a), Box::new(b)))) as _)),
-                        _ => fail
-                    },
-                    dispatch! {take(2usize);
-                        "!=" => empty.value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::NotEq(Box::new(a), Box::new(b)))) as _)),
-                        "||" => empty.value((Assoc::Left(4), (|_: &mut _, a, b| Ok(Expr::Or(Box::new(a), Box::new(b)))) as _)),
+            operand(delimited('(', parser(0), cut_err(')'))),
+            prefix,
+            postfix,
+            infix,
+            ternary,
+            delimited_postfix,
+        )
+        .parse_next(i)
+    }
+}
+
+/// A name, a value, or an expression in parentheses parsed with `parens`.
+pub(crate) fn operand<'i>(
+    parens: impl Parser<&'i str, Expr, Error>,
+) -> impl Parser<&'i str, Expr, Error> {
+    let mut parens = parens.map(|e| Expr::Paren(Box::new(e)));
+    trace(
+        "operand",
+        delimited(
+            multispace0,
+            dispatch! {peek(any);
+                '(' => parens.by_ref(),
+                _ => alt((
+                    identifier.map(|s| Expr::Name(s.into())),
+                    digit1.parse_to::<i64>().map(Expr::Value)
+                )),
+            },
+            multispace0,
+        ),
+    )
+}
+
+pub(crate) fn prefix<'i>(i: &mut &'i str) -> ModalResult<(i64, UnaryFold<&'i str, Expr, Error>)> {
+    trace(
+        "prefix",
+        delimited(
+            multispace0,
+            dispatch! {any;
+                '+' => alt((
+                    // ++
+                    '+'.value((18, (|_: &mut _, a| Ok(Expr::PreIncr(Box::new(a)))) as UnaryFold<_, _, _>)),
+                    empty.value((18, (|_: &mut _, a| Ok(a)) as UnaryFold<_, _, _>))
+                )),
+                '-' =>  alt((
+                    // --
+                    '-'.value((18, (|_: &mut _, a| Ok(Expr::PreDecr(Box::new(a)))) as UnaryFold<_, _, _>)),
+                    empty.value((18, (|_: &mut _, a| Ok(Expr::Neg(Box::new(a)))) as UnaryFold<_, _, _>))
+                )),
+                '&' => empty.value((18, (|_: &mut _, a| Ok(Expr::Addr(Box::new(a)))) as UnaryFold<_, _, _>)),
+                '*' => empty.value((18, (|_: &mut _, a| Ok(Expr::Deref(Box::new(a)))) as UnaryFold<_, _, _>)),
+                '!' => empty.value((18, (|_: &mut _, a| Ok(Expr::Not(Box::new(a)))) as UnaryFold<_, _, _>)),
+                '~' => empty.value((18, (|_: &mut _, a| Ok(Expr::BitwiseNot(Box::new(a)))) as UnaryFold<_, _, _>)),
+                _ => fail
+            },
+            multispace0,
+        ),
+    )
+    .parse_next(i)
+}
+
+pub(crate) fn postfix<'i>(i: &mut &'i str) -> ModalResult<(i64, UnaryFold<&'i str, Expr, Error>)> {
+    trace(
+        "postfix",
+        delimited(
+            multispace0,
+            alt((
+                dispatch! {any;
+                    '!' => not('=').value((19, (|_: &mut _, a| Ok(Expr::Fac(Box::new(a)))) as UnaryFold<_, _, _>)),
+                    _ => fail,
+                },
+                dispatch! {take(2usize);
+                    "++" => empty.value((20, (|_: &mut _, a| Ok(Expr::PostIncr(Box::new(a)))) as UnaryFold<_, _, _>)),
+                    "--" => empty.value((20, (|_: &mut _, a| Ok(Expr::PostDecr(Box::new(a)))) as UnaryFold<_, _, _>)),
+                    _ => fail,
+                },
+            )),
+            multispace0,
+        ),
+    )
+    .parse_next(i)
+}
+
+pub(crate) fn infix<'i>(i: &mut &'i str) -> ModalResult<(Assoc, BinaryFold<&'i str, Expr, Error>)> {
+    trace(
+        "infix",
+        alt((
+            dispatch! {any;
+                '*' => alt((
+                    // **
+                    "*".value((Assoc::Right(28), (|_: &mut _, a, b| Ok(Expr::Pow(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                    empty.value((Assoc::Left(16), (|_: &mut _, a, b| Ok(Expr::Mul(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                )),
+                '/' => empty.value((Assoc::Left(16), (|_: &mut _, a, b| Ok(Expr::Div(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                '%' => empty.value((Assoc::Left(16), (|_: &mut _, a, b| Ok(Expr::Rem(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+
+                '+' => empty.value((Assoc::Left(14), (|_: &mut _, a, b| Ok(Expr::Add(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                '-' => alt((
+                    dispatch!{take(2usize);
+                        "ne" => empty.value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::NotEq(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                        "eq" => empty.value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::Eq(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                        "gt" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Greater(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                        "ge" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::GreaterEqual(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                        "lt" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Less(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                        "le" => empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::LessEqual(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
                         _ => fail
                     },
+                    '>'.value((Assoc::Left(20), (|_: &mut _, a, b| Ok(Expr::ArrowOp(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                    empty.value((Assoc::Left(14), (|_: &mut _, a, b| Ok(Expr::Sub(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>))
                 )),
-            ),
-        ).parse_next(i)
-        }
-    }
-    parser(0).parse_next(i)
+                '.' => empty.value((Assoc::Left(20), (|_: &mut _, a, b| Ok(Expr::Dot(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                '&' => alt((
+                    // &&
+                    "&".value((Assoc::Left(6), (|_: &mut _, a, b| Ok(Expr::And(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)  ),
+
+                    empty.value((Assoc::Left(12), (|_: &mut _, a, b| Ok(Expr::BitAnd(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                )),
+                '^' => empty.value((Assoc::Left(8), (|_: &mut _, a, b| Ok(Expr::BitXor(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                '=' => alt((
+                    // ==
+                    "=".value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::Eq(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                    empty.value((Assoc::Right(2), (|_: &mut _, a, b| Ok(Expr::Assign(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>))
+                )),
+
+                '>' => alt((
+                    // >=
+                    "=".value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::GreaterEqual(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                    empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Greater(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>))
+                )),
+                '<' => alt((
+                    // <=
+                    "=".value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::LessEqual(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                    empty.value((Assoc::Neither(12), (|_: &mut _, a, b| Ok(Expr::Less(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>))
+                )),
+                ',' => empty.value((Assoc::Left(0), (|_: &mut _, a, b| Ok(Expr::Comma(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                _ => fail
+            },
+            dispatch! {take(2usize);
+                "!=" => empty.value((Assoc::Neither(10), (|_: &mut _, a, b| Ok(Expr::NotEq(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                "||" => empty.value((Assoc::Left(4), (|_: &mut _, a, b| Ok(Expr::Or(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>)),
+                _ => fail
+            },
+        )),
+    )
+    .parse_next(i)
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn ternary<'i>(
+    i: &mut &'i str,
+) -> ModalResult<(
+    Assoc,
+    CloseDelimiter<&'i str, Error>,
+    TernaryFold<&'i str, Expr, Error>,
+)> {
+    trace(
+        "ternary",
+        delimited(
+            multispace0,
+            '?'.value((
+                Assoc::Right(4),
+                (|i: &mut &str| {
+                    delimited(multispace0, ':', multispace0)
+                        .void()
+                        .parse_next(i)
+                }) as CloseDelimiter<_, _>,
+                (|_: &mut _, cond, left, right| {
+                    Ok(Expr::Ternary(
+                        Box::new(cond),
+                        Box::new(left),
+                        Box::new(right),
+                    ))
+                }) as TernaryFold<_, _, _>,
+            )),
+            multispace0,
+        ),
+    )
+    .parse_next(i)
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn delimited_postfix<'i>(
+    i: &mut &'i str,
+) -> ModalResult<(
+    i64,
+    CloseDelimiter<&'i str, Error>,
+    DelimitedFold<&'i str, Expr, Error>,
+)> {
+    trace(
+        "delimited",
+        delimited(
+            multispace0,
+            dispatch! {any;
+                '[' => empty.value((
+                    20,
+                    (|i: &mut &str| (']', multispace0).void().parse_next(i)) as CloseDelimiter<_, _>,
+                    (|i: &mut &str, a, index| match index {
+                        Some(index) => Ok(Expr::Index(Box::new(a), Box::new(index))),
+                        None => Err(ParserError::from_input(i)),
+                    }) as DelimitedFold<_, _, _>,
+                )),
+                '(' => empty.value((
+                    20,
+                    (|i: &mut &str| (')', multispace0).void().parse_next(i)) as CloseDelimiter<_, _>,
+                    (|_: &mut _, a, args: Option<_>| Ok(Expr::FunctionCall(Box::new(a), args.map(Box::new)))) as DelimitedFold<_, _, _>,
+                )),
+                _ => fail,
+            },
+            multispace0,
+        ),
+    )
+    .parse_next(i)
 }
 
 fn identifier<'i>(i: &mut &'i str) -> winnow::ModalResult<&'i str> {
@@ -246,6 +336,7 @@ impl Expr {
         match self {
             Self::Name(name) => writeln!(f, "NAME {name}"),
             Self::Value(value) => writeln!(f, "VAL {value}"),
+            Self::Error => writeln!(f, "ERROR"),
             Self::Addr(a) => unary_fmt!(a, "ADDR"),
             Self::Deref(a) => unary_fmt!(a, "DEREF"),
             Self::Neg(a) => unary_fmt!(a, "NEG"),
@@ -298,6 +389,7 @@ impl Expr {
         match self {
             Self::Name(name) => return write!(f, "{name}"),
             Self::Value(value) => return write!(f, "{value}"),
+            Self::Error => return write!(f, "<error>"),
             Self::Paren(a) => return a.fmt_delimited(f),
             _ => (),
         }
@@ -0,0 +1,156 @@
+//! The `pratt_c` grammar with error recovery, which prints a tree of what it
+//! could parse and every error in the input, like an editor would show.
+
+use std::io::BufRead;
+
+use pratt::recovery::Recovery;
+use winnow::ascii::multispace0;
+use winnow::combinator::delimited;
+use winnow::error::{ContextError, ErrMode};
+use winnow::prelude::*;
+
+use parser::Expr;
+
+mod parser;
+
+type Error = ErrMode<ContextError>;
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut handle = stdin.lock();
+    let mut buffer = String::new();
+
+    loop {
+        buffer.clear();
+        match handle.read_line(&mut buffer) {
+            Ok(0) => break, // eof
+            Ok(_) => {
+                let (tree, errors) = parse(&buffer);
+                println!("{tree}");
+                for (offset, message) in errors {
+                    eprintln!("error at {offset}: {message}");
+                }
+            }
+            Err(why) => eprintln!("failed to read line: `{why}`"),
+        }
+    }
+}
+
+/// Parses the whole input into a tree, where [`Expr::Error`] is a missing
+/// operand, and the errors with their offset.
+fn parse(input: &str) -> (Expr, Vec<(usize, String)>) {
+    // the input is usually fine, so there's no need to recover
+    if let Ok(tree) = parser::pratt_parser.parse(input) {
+        return (tree, Vec::new());
+    }
+
+    // an error node takes the whitespace after it, like an operand does
+    let recovery = Recovery::new(&input, multispace0.map(|_| Expr::Error));
+    let mut i = input;
+    let result = expr(&recovery).parse_next(&mut i);
+    let mut errors: Vec<_> = recovery
+        .into_errors()
+        .into_iter()
+        .map(|(offset, e)| (offset, message(e)))
+        .collect();
+    let offset = input.len() - i.len();
+    match result {
+        Ok(tree) => {
+            if !i.is_empty() {
+                errors.push((offset, "unexpected input".into()));
+            }
+            (tree, errors)
+        }
+        // like a chain of non-associative operators, which isn't recovered from
+        Err(e) => {
+            errors.push((offset, message(e)));
+            (Expr::Error, errors)
+        }
+    }
+}
+
+/// The `pratt_c` grammar, where a missing `)` is recovered from too.
+fn expr<'i, 'r>(
+    recovery: &'r Recovery<'_, &'i str, Expr, Error>,
+) -> impl Parser<&'i str, Expr, Error> + 'r {
+    move |i: &mut &'i str| {
+        recovery
+            .mixfix(
+                0,
+                parser::operand(delimited('(', expr(recovery), recovery.close(')'))),
+                parser::prefix,
+                parser::postfix,
+                parser::infix,
+                parser::ternary,
+                parser::delimited_postfix,
+            )
+            .parse_next(i)
+    }
+}
+
+/// The lines of an error's context, on one line.
+fn message(e: Error) -> String {
+    match e.into_inner() {
+        Ok(e) => e.to_string().replace('\n', ", "),
+        Err(_) => "incomplete input".into(),
+    }
+}
+
+#[cfg(test)]
+mod recovery_test {
+    use super::*;
+
+    fn parse_errors(i: &str, expect: &str, errors: &[(usize, &str)]) {
+        let (tree, actual) = parse(i);
+        let tree = tree.to_string();
+        assert_eq!(tree.lines().last().unwrap(), expect);
+        let errors: Vec<_> = errors.iter().map(|&(o, e)| (o, e.to_string())).collect();
+        assert_eq!(actual, errors);
+    }
+
+    #[test]
+    fn valid() {
+        parse_errors("x = ((y*4)-2)", "(= x (- (* y 4) 2))", &[]);
+    }
+
+    #[test]
+    fn recovered() {
+        // `*` is a dereference here, so only the end is missing
+        parse_errors(
+            "1 + * 2 + (3 -",
+            "(+ (+ 1 (*2)) (- 3 <error>))",
+            &[
+                (14, "invalid infix operator, expected operand"),
+                (14, "expected closing delimiter"),
+            ],
+        );
+        parse_errors(
+            "a + / b",
+            "(+ a (/ <error> b))",
+            &[(3, "invalid infix operator, expected operand")],
+        );
+        parse_errors(
+            "f(x, ) ? : y",
+            "(? (call f (, x <error>)) <error> y)",
+            &[
+                (4, "invalid infix operator, expected operand"),
+                (9, "invalid ternary operator, expected operand"),
+            ],
+        );
+        parse_errors(
+            "a[1 + 2",
+            "([] a (+ 1 2))",
+            &[(7, "invalid delimited postfix operator")],
+        );
+    }
+
+    #[test]
+    fn unrecovered() {
+        parse_errors("a + b) + c", "(+ a b)", &[(5, "unexpected input")]);
+        parse_errors(
+            "1 == 2 == 3",
+            "<error>",
+            &[(7, "invalid chained non-associative operator")],
+        );
+    }
+}
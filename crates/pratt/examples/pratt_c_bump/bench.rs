@@ -3,7 +3,7 @@ mod parser;
 use std::cell::RefCell;
 
 use criterion::BatchSize;
-use winnow::{Stateful, prelude::*};
+use winnow::prelude::*;
 
 fn pratt(c: &mut criterion::Criterion) {
     let input = "a = 2*-2 * (a ? 1 + 2 * 4 - --a.bar + 2 : 2) / ( &**foo.a->p! -+1) + 3^1 / 4 == 1 * (2 - 7 + 567 *12 /2) + 3*(1+2*( 45 /2))";
@@ -13,20 +13,14 @@ fn pratt(c: &mut criterion::Criterion) {
         let bump = RefCell::new(bumpalo::Bump::new());
 
         {
-            let i = Stateful {
-                input,
-                state: &*bump.borrow(),
-            };
-            parser::pratt_parser.parse(i).expect("pratt should parse");
+            parser::pratt_parser(&bump.borrow())
+                .parse(input)
+                .expect("pratt should parse");
         }
         bump.borrow_mut().reset();
         {
-            let i = Stateful {
-                input,
-                state: &*bump.borrow(),
-            };
-            parser::shunting_yard_parser
-                .parse(i)
+            parser::shunting_yard_parser(&bump.borrow())
+                .parse(input)
                 .expect("shunting yard should parse");
         }
         bump.borrow_mut().reset();
@@ -39,11 +33,9 @@ fn pratt(c: &mut criterion::Criterion) {
                         &bump
                     },
                     |b| {
-                        let i = Stateful {
-                            input,
-                            state: &*b.borrow(),
-                        };
-                        std::hint::black_box(parser::pratt_parser.parse(i).unwrap());
+                        std::hint::black_box(
+                            parser::pratt_parser(&b.borrow()).parse(input).unwrap(),
+                        );
                     },
                     BatchSize::SmallInput,
                 );
@@ -57,11 +49,11 @@ fn pratt(c: &mut criterion::Criterion) {
                         &bump
                     },
                     |b| {
-                        let i = Stateful {
-                            input,
-                            state: &*b.borrow(),
-                        };
-                        std::hint::black_box(parser::shunting_yard_parser.parse(i).unwrap());
+                        std::hint::black_box(
+                            parser::shunting_yard_parser(&b.borrow())
+                                .parse(input)
+                                .unwrap(),
+                        );
                     },
                     BatchSize::SmallInput,
                 );
@@ -71,10 +63,9 @@ fn pratt(c: &mut criterion::Criterion) {
 
     group.bench_function("pratt_with_new_bump_each_time", |b| {
         b.iter_batched(
-            || bumpalo::Bump::new(),
+            bumpalo::Bump::new,
             |b| {
-                let i = Stateful { input, state: &b };
-                std::hint::black_box(parser::pratt_parser.parse(i).unwrap());
+                std::hint::black_box(parser::pratt_parser(&b).parse(input).unwrap());
             },
             BatchSize::SmallInput,
         );
@@ -82,10 +73,9 @@ fn pratt(c: &mut criterion::Criterion) {
 
     group.bench_function("shunting_yard_with_new_bump_each_time", |b| {
         b.iter_batched(
-            || bumpalo::Bump::new(),
+            bumpalo::Bump::new,
             |b| {
-                let i = Stateful { input, state: &b };
-                std::hint::black_box(parser::shunting_yard_parser.parse(i).unwrap());
+                std::hint::black_box(parser::shunting_yard_parser(&b).parse(input).unwrap());
             },
             BatchSize::SmallInput,
         );
@@ -1,6 +1,6 @@
 mod parser;
 
-use winnow::{Stateful, prelude::*};
+use winnow::prelude::*;
 
 fn main() {
     // Join all CLI args into a single string, or just read one line from stdin
@@ -16,11 +16,7 @@ fn main() {
         args
     };
 
-    let input = Stateful {
-        input: input.as_str(),
-        state: &b,
-    };
-    match parser::pratt_parser.parse(input) {
+    match parser::pratt_parser(&b).parse(input.as_str()) {
         Ok(result) => {
             println!("{result}");
         }
@@ -1,10 +1,12 @@
+use bumpalo::Bump;
 use bumpalo::boxed::Box;
 use bumpalo::collections::String as BString;
-use winnow::combinator::{cut_err, empty, fail, not, opt, peek, separated_pair, trace};
-use winnow::error::{ContextError, ErrMode};
-use winnow::stream::AsChar as _;
+use pratt::precedence::{self, Assoc, CloseDelimiter};
+use pratt::shunting_yard;
+use winnow::combinator::{cut_err, empty, fail, not, peek, trace};
+use winnow::error::{ContextError, ErrMode, ParserError};
+use winnow::prelude::*;
 use winnow::token::{any, take, take_while};
-use winnow::{Stateful, prelude::*};
 use winnow::{
     ascii::{digit1, multispace0},
     combinator::alt,
@@ -67,315 +69,388 @@ pub(crate) enum Expr<'a> {
     BitwiseNot(Box<'a, Expr<'a>>),
 }
 
-type Input<'i, 'a> = Stateful<&'i str, &'a bumpalo::Bump>;
-
 // Parser definition
 
-pub(crate) fn pratt_parser<'i, 'a>(i: &mut Input<'i, 'a>) -> ModalResult<Expr<'a>> {
-    use pratt::precedence::{self, Assoc};
+type Error = ErrMode<ContextError>;
+
+// The folds capture the arena, so every node of the tree is allocated in `bump`.
+
+pub(crate) fn pratt_parser<'i, 'a>(bump: &'a Bump) -> impl Parser<&'i str, Expr<'a>, Error> {
     // precedence is based on https://en.cppreference.com/w/c/language/operator_precedence
     // but specified in reverse order, because the `cppreference` table
     // uses `descending` precedence, but we need ascending one
-    fn parser<'i, 'a>(
-        start_power: i64,
-    ) -> impl Parser<Input<'i, 'a>, Expr<'a>, ErrMode<ContextError>> {
-        move |i: &mut Input<'i, 'a>| {
-            precedence::precedence(
-                    start_power,
-                    trace(
-                        "operand",
-                        delimited(
-                            multispace0,
-                            dispatch! {peek(any);
-                                '(' => |i: &mut Input<'i, 'a>| {
-                                        delimited('(', parser(0).map(|e| Expr::Paren(Box::new_in(e, &*i.state))), cut_err(')')).parse_next(i)
-                                },
-                                _ => alt((
-                                    |i: &mut Input<'i, 'a>| {
-                                        identifier.map(|s| Expr::Name(BString::from_str_in(s, &*i.state))).parse_next(i)
-                                    },
-                                    digit1.parse_to::<i64>().map(Expr::Value),
-                                )),
-                            },
-                            multispace0,
-                        ),
-                    ),
-                    trace(
-                        "prefix",
-                        delimited(
-                            multispace0,
-                            dispatch! {any;
-                                '+' => alt((
-                                    // ++
-                                    '+'.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PreIncr(Box::new_in(a, i.state)))) as _)),
-                                    empty.value((18, (|_: &mut _, a| Ok(a)) as _))
-                                )),
-                                '-' =>  alt((
-                                    // --
-                                    '-'.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PreDecr(Box::new_in(a, i.state)))) as _)),
-                                    empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Neg(Box::new_in(a, i.state)))) as _))
-                                )),
-                                '&' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Addr(Box::new_in(a, i.state)))) as _)),
-                                '*' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Deref(Box::new_in(a, i.state)))) as _)),
-                                '!' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Not(Box::new_in(a, i.state)))) as _)),
-                                '~' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::BitwiseNot(Box::new_in(a, i.state)))) as _)),
-                                _ => fail
-                            },
-                            multispace0,
-                        ),
-                    ),
-                    trace(
-                        "postfix",
-                        delimited(
-                            multispace0,
-                            alt((
-                                dispatch! {any;
-                                    '!' => not('=').value((19, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Fac(Box::new_in(a, &i.state)))) as _)),
-                                    '?' => empty.value((3, (|i: &mut Input<'i, 'a>, cond| {
-                                        let (left, right) = cut_err(separated_pair(parser(0), delimited(multispace0, ':', multispace0), parser(3))).parse_next(i)?;
-                                        Ok(Expr::Ternary(Box::new_in(cond, &i.state), Box::new_in(left, &i.state), Box::new_in(right, &i.state)))
-                                    }) as _)),
-                                    '[' => empty.value((20, (|i: &mut Input<'i, 'a>, a| {
-                                        let index = delimited(multispace0, parser(0), (multispace0, cut_err(']'), multispace0)).parse_next(i)?;
-                                        Ok(Expr::Index(Box::new_in(a, &i.state), Box::new_in(index, &i.state)))
-                                    }) as _)),
-                                    '(' => empty.value((20, (|i: &mut Input<'i, 'a>, a| {
-                                        let args = delimited(multispace0, opt(parser(0)), (multispace0, cut_err(')'), multispace0)).parse_next(i)?;
-                                        Ok(Expr::FunctionCall(Box::new_in(a, &i.state), args.map(|a| Box::new_in(a, &i.state))))
-                                    }) as _)),
-                                    _ => fail,
-                                },
-                                dispatch! {take(2usize);
-                                    "++" => empty.value((20, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PostIncr(Box::new_in(a, &i.state)))) as _)),
-                                    "--" => empty.value((20, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PostDecr(Box::new_in(a, &i.state)))) as _)),
-                                    _ => fail,
-                                },
+    fn parser<'i, 'a>(bump: &'a Bump, start_power: i64) -> impl Parser<&'i str, Expr<'a>, Error> {
+        move |i: &mut &'i str| {
+            precedence::mixfix(
+                start_power,
+                operand(bump, delimited('(', parser(bump, 0), cut_err(')'))),
+                trace(
+                    "prefix",
+                    delimited(
+                        multispace0,
+                        dispatch! {any;
+                            '+' => alt((
+                                // ++
+                                '+'.value((18, unary(bump, Expr::PreIncr))),
+                                // unary plus unboxes its operand again, to have the same fold type
+                                empty.value((18, unary(bump, Box::into_inner)))
+                            )),
+                            '-' =>  alt((
+                                // --
+                                '-'.value((18, unary(bump, Expr::PreDecr))),
+                                empty.value((18, unary(bump, Expr::Neg)))
                             )),
-                            multispace0,
-                        ),
+                            '&' => empty.value((18, unary(bump, Expr::Addr))),
+                            '*' => empty.value((18, unary(bump, Expr::Deref))),
+                            '!' => empty.value((18, unary(bump, Expr::Not))),
+                            '~' => empty.value((18, unary(bump, Expr::BitwiseNot))),
+                            _ => fail
+                        },
+                        multispace0,
                     ),
-                    trace(
-                        "infix",
+                ),
+                trace(
+                    "postfix",
+                    delimited(
+                        multispace0,
                         alt((
-                            // fail,
                             dispatch! {any;
-                                '*' => alt((
-                                    // **
-                                    "*".value((Assoc::Right(28), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Pow(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Left(16), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Mul(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                )),
-                                '/' => empty.value((Assoc::Left(16), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Div(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '%' => empty.value((Assoc::Left(16), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Rem(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-
-                                '+' => empty.value((Assoc::Left(14), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Add(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '-' => alt((
-                                    dispatch!{take(2usize);
-                                        "ne" => empty.value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::NotEq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "eq" => empty.value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Eq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "gt" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Greater(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "ge" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::GreaterEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "lt" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Less(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "le" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::LessEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        _ => fail
-                                    },
-                                    '>'.value((Assoc::Left(20), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::ArrowOp(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Left(14), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Sub(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-                                '.' => empty.value((Assoc::Left(20), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Dot(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '&' => alt((
-                                    // &&
-                                    "&".value((Assoc::Left(6), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::And(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)  ),
-
-                                    empty.value((Assoc::Left(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::BitAnd(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                )),
-                                '^' => empty.value((Assoc::Left(8), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::BitXor(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '=' => alt((
-                                    // ==
-                                    "=".value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Eq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Right(2), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Assign(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-
-                                '>' => alt((
-                                    // >=
-                                    "=".value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::GreaterEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Greater(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-                                '<' => alt((
-                                    // <=
-                                    "=".value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::LessEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Less(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-                                ',' => empty.value((Assoc::Left(0), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Comma(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                _ => fail
+                                '!' => not('=').value((19, unary(bump, Expr::Fac))),
+                                _ => fail,
                             },
                             dispatch! {take(2usize);
-                                "!=" => empty.value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::NotEq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                "||" => empty.value((Assoc::Left(4), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Or(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                _ => fail
+                                "++" => empty.value((20, unary(bump, Expr::PostIncr))),
+                                "--" => empty.value((20, unary(bump, Expr::PostDecr))),
+                                _ => fail,
                             },
                         )),
+                        multispace0,
                     ),
-                )
-                .parse_next(i)
+                ),
+                trace(
+                    "infix",
+                    alt((
+                        dispatch! {any;
+                            '*' => alt((
+                                // **
+                                "*".value((Assoc::Right(28), binary(bump, Expr::Pow))),
+                                empty.value((Assoc::Left(16), binary(bump, Expr::Mul))),
+                            )),
+                            '/' => empty.value((Assoc::Left(16), binary(bump, Expr::Div))),
+                            '%' => empty.value((Assoc::Left(16), binary(bump, Expr::Rem))),
+
+                            '+' => empty.value((Assoc::Left(14), binary(bump, Expr::Add))),
+                            '-' => alt((
+                                dispatch!{take(2usize);
+                                    "ne" => empty.value((Assoc::Neither(10), binary(bump, Expr::NotEq))),
+                                    "eq" => empty.value((Assoc::Neither(10), binary(bump, Expr::Eq))),
+                                    "gt" => empty.value((Assoc::Neither(12), binary(bump, Expr::Greater))),
+                                    "ge" => empty.value((Assoc::Neither(12), binary(bump, Expr::GreaterEqual))),
+                                    "lt" => empty.value((Assoc::Neither(12), binary(bump, Expr::Less))),
+                                    "le" => empty.value((Assoc::Neither(12), binary(bump, Expr::LessEqual))),
+                                    _ => fail
+                                },
+                                '>'.value((Assoc::Left(20), binary(bump, Expr::ArrowOp))),
+                                empty.value((Assoc::Left(14), binary(bump, Expr::Sub)))
+                            )),
+                            '.' => empty.value((Assoc::Left(20), binary(bump, Expr::Dot))),
+                            '&' => alt((
+                                // &&
+                                "&".value((Assoc::Left(6), binary(bump, Expr::And))),
+
+                                empty.value((Assoc::Left(12), binary(bump, Expr::BitAnd))),
+                            )),
+                            '^' => empty.value((Assoc::Left(8), binary(bump, Expr::BitXor))),
+                            '=' => alt((
+                                // ==
+                                "=".value((Assoc::Neither(10), binary(bump, Expr::Eq))),
+                                empty.value((Assoc::Right(2), binary(bump, Expr::Assign)))
+                            )),
+
+                            '>' => alt((
+                                // >=
+                                "=".value((Assoc::Neither(12), binary(bump, Expr::GreaterEqual))),
+                                empty.value((Assoc::Neither(12), binary(bump, Expr::Greater)))
+                            )),
+                            '<' => alt((
+                                // <=
+                                "=".value((Assoc::Neither(12), binary(bump, Expr::LessEqual))),
+                                empty.value((Assoc::Neither(12), binary(bump, Expr::Less)))
+                            )),
+                            ',' => empty.value((Assoc::Left(0), binary(bump, Expr::Comma))),
+                            _ => fail
+                        },
+                        dispatch! {take(2usize);
+                            "!=" => empty.value((Assoc::Neither(10), binary(bump, Expr::NotEq))),
+                            "||" => empty.value((Assoc::Left(4), binary(bump, Expr::Or))),
+                            _ => fail
+                        },
+                    )),
+                ),
+                trace(
+                    "ternary",
+                    delimited(
+                        multispace0,
+                        '?'.value((
+                            Assoc::Right(4),
+                            (|i: &mut &str| {
+                                delimited(multispace0, ':', multispace0)
+                                    .void()
+                                    .parse_next(i)
+                            }) as CloseDelimiter<_, _>,
+                            move |_: &mut _, cond, left, right| {
+                                Ok(Expr::Ternary(
+                                    Box::new_in(cond, bump),
+                                    Box::new_in(left, bump),
+                                    Box::new_in(right, bump),
+                                ))
+                            },
+                        )),
+                        multispace0,
+                    ),
+                ),
+                trace(
+                    "delimited",
+                    delimited(
+                        multispace0,
+                        dispatch! {any;
+                            '[' => empty.value((
+                                20,
+                                (|i: &mut &str| (']', multispace0).void().parse_next(i)) as CloseDelimiter<_, _>,
+                                delimited_fold(bump, |a, index| index.map(|index| Expr::Index(a, index))),
+                            )),
+                            '(' => empty.value((
+                                20,
+                                (|i: &mut &str| (')', multispace0).void().parse_next(i)) as CloseDelimiter<_, _>,
+                                delimited_fold(bump, |a, args| Some(Expr::FunctionCall(a, args))),
+                            )),
+                            _ => fail,
+                        },
+                        multispace0,
+                    ),
+                ),
+            )
+            .parse_next(i)
         }
     }
-    parser(0).parse_next(i)
+    parser(bump, 0)
 }
 
 #[allow(dead_code)] // dead code is FINE, this gets used in the benchmark.
-pub(crate) fn shunting_yard_parser<'i, 'a>(i: &mut Input<'i, 'a>) -> ModalResult<Expr<'a>> {
-    use pratt::precedence::Assoc;
-    use pratt::shunting_yard;
-    // precedence is based on https://en.cppreference.com/w/c/language/operator_precedence
-    // but specified in reverse order, because the `cppreference` table
-    // uses `descending` precedence, but we need ascending one
-    fn parser<'i, 'a>(
-        start_power: i64,
-    ) -> impl Parser<Input<'i, 'a>, Expr<'a>, ErrMode<ContextError>> {
-        move |i: &mut Input<'i, 'a>| {
-            shunting_yard::precedence(
-                    start_power,
-                    trace(
-                        "operand",
-                        delimited(
-                            multispace0,
-                            dispatch! {peek(any);
-                                '(' => |i: &mut Input<'i, 'a>| {
-                                        delimited('(', parser(0).map(|e| Expr::Paren(Box::new_in(e, &*i.state))), cut_err(')')).parse_next(i)
-                                },
-                                _ => alt((
-                                    |i: &mut Input<'i, 'a>| {
-                                        identifier.map(|s| Expr::Name(BString::from_str_in(s, &*i.state))).parse_next(i)
-                                    },
-                                    digit1.parse_to::<i64>().map(Expr::Value),
-                                )),
-                            },
-                            multispace0,
-                        ),
-                    ),
-                    trace(
-                        "prefix",
-                        delimited(
-                            multispace0,
-                            dispatch! {any;
-                                '+' => alt((
-                                    // ++
-                                    '+'.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PreIncr(Box::new_in(a, i.state)))) as _)),
-                                    empty.value((18, (|_: &mut _, a| Ok(a)) as _))
-                                )),
-                                '-' =>  alt((
-                                    // --
-                                    '-'.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PreDecr(Box::new_in(a, i.state)))) as _)),
-                                    empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Neg(Box::new_in(a, i.state)))) as _))
-                                )),
-                                '&' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Addr(Box::new_in(a, i.state)))) as _)),
-                                '*' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Deref(Box::new_in(a, i.state)))) as _)),
-                                '!' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Not(Box::new_in(a, i.state)))) as _)),
-                                '~' => empty.value((18, (|i: &mut Input<'i, 'a>, a| Ok(Expr::BitwiseNot(Box::new_in(a, i.state)))) as _)),
-                                _ => fail
-                            },
-                            multispace0,
-                        ),
-                    ),
-                    trace(
-                        "postfix",
-                        delimited(
-                            multispace0,
-                            alt((
-                                dispatch! {any;
-                                    '!' => not('=').value((19, (|i: &mut Input<'i, 'a>, a| Ok(Expr::Fac(Box::new_in(a, &i.state)))) as _)),
-                                    '?' => empty.value((3, (|i: &mut Input<'i, 'a>, cond| {
-                                        let (left, right) = cut_err(separated_pair(parser(0), delimited(multispace0, ':', multispace0), parser(3))).parse_next(i)?;
-                                        Ok(Expr::Ternary(Box::new_in(cond, &i.state), Box::new_in(left, &i.state), Box::new_in(right, &i.state)))
-                                    }) as _)),
-                                    '[' => empty.value((20, (|i: &mut Input<'i, 'a>, a| {
-                                        let index = delimited(multispace0, parser(0), (multispace0, cut_err(']'), multispace0)).parse_next(i)?;
-                                        Ok(Expr::Index(Box::new_in(a, &i.state), Box::new_in(index, &i.state)))
-                                    }) as _)),
-                                    '(' => empty.value((20, (|i: &mut Input<'i, 'a>, a| {
-                                        let args = delimited(multispace0, opt(parser(0)), (multispace0, cut_err(')'), multispace0)).parse_next(i)?;
-                                        Ok(Expr::FunctionCall(Box::new_in(a, &i.state), args.map(|a| Box::new_in(a, &i.state))))
-                                    }) as _)),
-                                    _ => fail,
-                                },
-                                dispatch! {take(2usize);
-                                    "++" => empty.value((20, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PostIncr(Box::new_in(a, &i.state)))) as _)),
-                                    "--" => empty.value((20, (|i: &mut Input<'i, 'a>, a| Ok(Expr::PostDecr(Box::new_in(a, &i.state)))) as _)),
-                                    _ => fail,
-                                },
+pub(crate) fn shunting_yard_parser<'i, 'a>(
+    bump: &'a Bump,
+) -> impl Parser<&'i str, Expr<'a>, Error> {
+    fn parser<'i, 'a>(bump: &'a Bump, start_power: i64) -> impl Parser<&'i str, Expr<'a>, Error> {
+        move |i: &mut &'i str| {
+            shunting_yard::mixfix(
+                start_power,
+                operand(bump, delimited('(', parser(bump, 0), cut_err(')'))),
+                trace(
+                    "prefix",
+                    delimited(
+                        multispace0,
+                        dispatch! {any;
+                            '+' => alt((
+                                // ++
+                                '+'.value((18, unary(bump, Expr::PreIncr))),
+                                // unary plus unboxes its operand again, to have the same fold type
+                                empty.value((18, unary(bump, Box::into_inner)))
                             )),
-                            multispace0,
-                        ),
+                            '-' =>  alt((
+                                // --
+                                '-'.value((18, unary(bump, Expr::PreDecr))),
+                                empty.value((18, unary(bump, Expr::Neg)))
+                            )),
+                            '&' => empty.value((18, unary(bump, Expr::Addr))),
+                            '*' => empty.value((18, unary(bump, Expr::Deref))),
+                            '!' => empty.value((18, unary(bump, Expr::Not))),
+                            '~' => empty.value((18, unary(bump, Expr::BitwiseNot))),
+                            _ => fail
+                        },
+                        multispace0,
                     ),
-                    trace(
-                        "infix",
+                ),
+                trace(
+                    "postfix",
+                    delimited(
+                        multispace0,
                         alt((
-                            // fail,
                             dispatch! {any;
-                                '*' => alt((
-                                    // **
-                                    "*".value((Assoc::Right(28), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Pow(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Left(16), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Mul(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                )),
-                                '/' => empty.value((Assoc::Left(16), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Div(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '%' => empty.value((Assoc::Left(16), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Rem(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-
-                                '+' => empty.value((Assoc::Left(14), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Add(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '-' => alt((
-                                    dispatch!{take(2usize);
-                                        "ne" => empty.value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::NotEq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "eq" => empty.value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Eq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "gt" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Greater(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "ge" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::GreaterEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "lt" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Less(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        "le" => empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::LessEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                        _ => fail
-                                    },
-                                    '>'.value((Assoc::Left(20), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::ArrowOp(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Left(14), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Sub(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-                                '.' => empty.value((Assoc::Left(20), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Dot(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '&' => alt((
-                                    // &&
-                                    "&".value((Assoc::Left(6), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::And(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)  ),
-
-                                    empty.value((Assoc::Left(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::BitAnd(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                )),
-                                '^' => empty.value((Assoc::Left(8), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::BitXor(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                '=' => alt((
-                                    // ==
-                                    "=".value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Eq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Right(2), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Assign(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-
-                                '>' => alt((
-                                    // >=
-                                    "=".value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::GreaterEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Greater(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-                                '<' => alt((
-                                    // <=
-                                    "=".value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::LessEqual(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                    empty.value((Assoc::Neither(12), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Less(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _))
-                                )),
-                                ',' => empty.value((Assoc::Left(0), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Comma(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                _ => fail
+                                '!' => not('=').value((19, unary(bump, Expr::Fac))),
+                                _ => fail,
                             },
                             dispatch! {take(2usize);
-                                "!=" => empty.value((Assoc::Neither(10), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::NotEq(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                "||" => empty.value((Assoc::Left(4), (|i: &mut Input<'i, 'a>, a, b| Ok(Expr::Or(Box::new_in(a, &i.state), Box::new_in(b, &i.state)))) as _)),
-                                _ => fail
+                                "++" => empty.value((20, unary(bump, Expr::PostIncr))),
+                                "--" => empty.value((20, unary(bump, Expr::PostDecr))),
+                                _ => fail,
                             },
                         )),
+                        multispace0,
+                    ),
+                ),
+                trace(
+                    "infix",
+                    alt((
+                        dispatch! {any;
+                            '*' => alt((
+                                // **
+                                "*".value((Assoc::Right(28), binary(bump, Expr::Pow))),
+                                empty.value((Assoc::Left(16), binary(bump, Expr::Mul))),
+                            )),
+                            '/' => empty.value((Assoc::Left(16), binary(bump, Expr::Div))),
+                            '%' => empty.value((Assoc::Left(16), binary(bump, Expr::Rem))),
+
+                            '+' => empty.value((Assoc::Left(14), binary(bump, Expr::Add))),
+                            '-' => alt((
+                                dispatch!{take(2usize);
+                                    "ne" => empty.value((Assoc::Neither(10), binary(bump, Expr::NotEq))),
+                                    "eq" => empty.value((Assoc::Neither(10), binary(bump, Expr::Eq))),
+                                    "gt" => empty.value((Assoc::Neither(12), binary(bump, Expr::Greater))),
+                                    "ge" => empty.value((Assoc::Neither(12), binary(bump, Expr::GreaterEqual))),
+                                    "lt" => empty.value((Assoc::Neither(12), binary(bump, Expr::Less))),
+                                    "le" => empty.value((Assoc::Neither(12), binary(bump, Expr::LessEqual))),
+                                    _ => fail
+                                },
+                                '>'.value((Assoc::Left(20), binary(bump, Expr::ArrowOp))),
+                                empty.value((Assoc::Left(14), binary(bump, Expr::Sub)))
+                            )),
+                            '.' => empty.value((Assoc::Left(20), binary(bump, Expr::Dot))),
+                            '&' => alt((
+                                // &&
+                                "&".value((Assoc::Left(6), binary(bump, Expr::And))),
+
+                                empty.value((Assoc::Left(12), binary(bump, Expr::BitAnd))),
+                            )),
+                            '^' => empty.value((Assoc::Left(8), binary(bump, Expr::BitXor))),
+                            '=' => alt((
+                                // ==
+                                "=".value((Assoc::Neither(10), binary(bump, Expr::Eq))),
+                                empty.value((Assoc::Right(2), binary(bump, Expr::Assign)))
+                            )),
+
+                            '>' => alt((
+                                // >=
+                                "=".value((Assoc::Neither(12), binary(bump, Expr::GreaterEqual))),
+                                empty.value((Assoc::Neither(12), binary(bump, Expr::Greater)))
+                            )),
+                            '<' => alt((
+                                // <=
+                                "=".value((Assoc::Neither(12), binary(bump, Expr::LessEqual))),
+                                empty.value((Assoc::Neither(12), binary(bump, Expr::Less)))
+                            )),
+                            ',' => empty.value((Assoc::Left(0), binary(bump, Expr::Comma))),
+                            _ => fail
+                        },
+                        dispatch! {take(2usize);
+                            "!=" => empty.value((Assoc::Neither(10), binary(bump, Expr::NotEq))),
+                            "||" => empty.value((Assoc::Left(4), binary(bump, Expr::Or))),
+                            _ => fail
+                        },
+                    )),
+                ),
+                trace(
+                    "ternary",
+                    delimited(
+                        multispace0,
+                        '?'.value((
+                            Assoc::Right(4),
+                            (|i: &mut &str| {
+                                delimited(multispace0, ':', multispace0)
+                                    .void()
+                                    .parse_next(i)
+                            }) as CloseDelimiter<_, _>,
+                            move |_: &mut _, cond, left, right| {
+                                Ok(Expr::Ternary(
+                                    Box::new_in(cond, bump),
+                                    Box::new_in(left, bump),
+                                    Box::new_in(right, bump),
+                                ))
+                            },
+                        )),
+                        multispace0,
+                    ),
+                ),
+                trace(
+                    "delimited",
+                    delimited(
+                        multispace0,
+                        dispatch! {any;
+                            '[' => empty.value((
+                                20,
+                                (|i: &mut &str| (']', multispace0).void().parse_next(i)) as CloseDelimiter<_, _>,
+                                delimited_fold(bump, |a, index| index.map(|index| Expr::Index(a, index))),
+                            )),
+                            '(' => empty.value((
+                                20,
+                                (|i: &mut &str| (')', multispace0).void().parse_next(i)) as CloseDelimiter<_, _>,
+                                delimited_fold(bump, |a, args| Some(Expr::FunctionCall(a, args))),
+                            )),
+                            _ => fail,
+                        },
+                        multispace0,
                     ),
-                )
-                .parse_next(i)
+                ),
+            )
+            .parse_next(i)
         }
     }
-    parser(0).parse_next(i)
+    parser(bump, 0)
+}
+
+/// A prefix or postfix fold, which allocates the operand of `node` in `bump`.
+///
+/// Every call returns the same closure type, so the operators of a
+/// `dispatch!` agree on a type without boxing the folds.
+fn unary<'i, 'a>(
+    bump: &'a Bump,
+    node: fn(Box<'a, Expr<'a>>) -> Expr<'a>,
+) -> impl FnOnce(&mut &'i str, Expr<'a>) -> Result<Expr<'a>, Error> + Clone {
+    move |_, a| Ok(node(Box::new_in(a, bump)))
+}
+
+/// An infix fold, see [`unary`].
+fn binary<'i, 'a>(
+    bump: &'a Bump,
+    node: fn(Box<'a, Expr<'a>>, Box<'a, Expr<'a>>) -> Expr<'a>,
+) -> impl FnOnce(&mut &'i str, Expr<'a>, Expr<'a>) -> Result<Expr<'a>, Error> + Clone {
+    move |_, a, b| Ok(node(Box::new_in(a, bump), Box::new_in(b, bump)))
+}
+
+/// A delimited postfix fold, see [`unary`]. It fails when `node` returns
+/// [`None`], like for empty brackets.
+fn delimited_fold<'i, 'a>(
+    bump: &'a Bump,
+    node: fn(Box<'a, Expr<'a>>, Option<Box<'a, Expr<'a>>>) -> Option<Expr<'a>>,
+) -> impl FnOnce(&mut &'i str, Expr<'a>, Option<Expr<'a>>) -> Result<Expr<'a>, Error> + Clone {
+    move |i, a, inner| {
+        node(Box::new_in(a, bump), inner.map(|e| Box::new_in(e, bump)))
+            .ok_or_else(|| ParserError::from_input(i))
+    }
 }
 
-fn identifier<'i, 'a>(i: &mut Input<'i, 'a>) -> ModalResult<&'i str> {
+/// A name, a value, or an expression in parentheses parsed with `parens`.
+fn operand<'i, 'a>(
+    bump: &'a Bump,
+    parens: impl Parser<&'i str, Expr<'a>, Error>,
+) -> impl Parser<&'i str, Expr<'a>, Error> {
+    let mut parens = parens.map(|e| Expr::Paren(Box::new_in(e, bump)));
+    trace(
+        "operand",
+        delimited(
+            multispace0,
+            dispatch! {peek(any);
+                '(' => parens.by_ref(),
+                _ => alt((
+                    identifier.map(|s| Expr::Name(BString::from_str_in(s, bump))),
+                    digit1.parse_to::<i64>().map(Expr::Value),
+                )),
+            },
+            multispace0,
+        ),
+    )
+}
+
+fn identifier<'i>(i: &mut &'i str) -> ModalResult<&'i str> {
     trace(
         "identifier",
         (
@@ -551,6 +626,7 @@ mod test {
     #[allow(clippy::useless_attribute)]
     #[allow(unused_imports)] // its dead for benches
     use super::*;
+    use winnow::error::ParseError;
 
     #[allow(dead_code)]
     // to invoke fmt_delimited()
@@ -562,17 +638,20 @@ mod test {
         }
     }
 
+    #[allow(dead_code)]
+    fn show(result: Result<Expr<'_>, ParseError<&str, ContextError>>) -> Result<String, String> {
+        let s = result.map(|r| format!("{}", PrefixNotation(r)));
+        s.map_err(|e| format!("{:?} at {}", e.inner(), e.offset()))
+    }
+
+    /// Parses with both algorithms, which have to agree.
     #[allow(dead_code)]
     fn parse(i: &str) -> Result<String, String> {
-        let b = bumpalo::Bump::new();
-        let i = Stateful {
-            input: i,
-            state: &b,
-        };
-        let s = pratt_parser
-            .parse(i)
-            .map(|r| format!("{}", PrefixNotation(r)));
-        s.map_err(|e| format!("{:?}", e))
+        let b = Bump::new();
+        let pratt = show(pratt_parser(&b).parse(i));
+        let shunting_yard = show(shunting_yard_parser(&b).parse(i));
+        assert_eq!(pratt, shunting_yard, "{i}");
+        pratt
     }
 
     #[allow(dead_code)]
@@ -0,0 +1,39 @@
+mod parser;
+
+fn main() {
+    // Join all CLI args into a single string, or just read one line from stdin
+    let args = std::env::args().skip(1).collect::<Vec<String>>().join(" ");
+
+    let input = if args.is_empty() {
+        let stdin = std::io::stdin();
+        let mut buffer = String::new();
+        stdin.read_line(&mut buffer).expect("Failed to read line");
+        buffer
+    } else {
+        args
+    };
+
+    match parser::parse(&input) {
+        Ok((tree, trailing)) => {
+            // the tree keeps every token and its trivia, so it prints the input back
+            let reconstructed = format!("{tree}{trailing}");
+            assert_eq!(reconstructed, input);
+            print!("{reconstructed}");
+            if !reconstructed.ends_with('\n') {
+                println!();
+            }
+
+            for token in tree.tokens() {
+                println!("{:?} {:?} after {:?}", token.span, token.text, token.trivia);
+            }
+            match tree.fold(&mut parser::PrefixNotation) {
+                Ok(folded) => println!("{folded}"),
+                Err(err) => eprintln!("failed to fold: {err}"),
+            }
+        }
+        Err(err) => {
+            eprintln!("FAILED");
+            eprintln!("{err}");
+        }
+    }
+}
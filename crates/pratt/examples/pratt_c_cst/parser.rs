@@ -0,0 +1,251 @@
+use pratt::cst::{Fold, Node, SyntaxTable, Token, token};
+use pratt::precedence::Assoc;
+use winnow::LocatingSlice;
+use winnow::ascii::{digit1, multispace1};
+use winnow::combinator::{alt, cut_err, not, repeat, trace};
+use winnow::error::{ContextError, ErrMode, ParseError};
+use winnow::prelude::*;
+use winnow::stream::AsChar;
+use winnow::token::{one_of, take_until, take_while};
+
+pub(crate) type Input<'i> = LocatingSlice<&'i str>;
+
+type Error = ErrMode<ContextError>;
+
+/// The tree of an expression, where each operator is named like in the prefix
+/// notation of the `pratt_c` example.
+pub(crate) type Tree<'i> = Node<&'i str, &'static str>;
+
+/// Parses the whole input into a tree and the trivia after its last token,
+/// which print back to the input.
+pub(crate) fn parse(input: &str) -> Result<(Tree<'_>, &str), ParseError<Input<'_>, ContextError>> {
+    (expr(0), trivia.take()).parse(LocatingSlice::new(input))
+}
+
+/// The `pratt_c` grammar, where the folds are the [`PrefixNotation`] pass.
+fn expr<'i>(start_power: i64) -> SyntaxTable<'i, Input<'i>, &'static str, Error> {
+    SyntaxTable::new(trace(
+        "operand",
+        alt((
+            (
+                t('('),
+                move |i: &mut Input<'i>| expr(0).parse_next(i),
+                cut_err(t(')')),
+            )
+                .map(Node::group),
+            t(alt((identifier, digit1))).map(Node::Operand),
+        )),
+    ))
+    .start_power(start_power)
+    // precedence is based on https://en.cppreference.com/w/c/language/operator_precedence
+    .prefix(t("++"), 18, "pre++")
+    .prefix(t("--"), 18, "pre--")
+    .prefix(t('+'), 18, "+")
+    .prefix(t('-'), 18, "-")
+    .prefix(t('&'), 18, "&")
+    .prefix(t('*'), 18, "*")
+    .prefix(t('!'), 18, "!")
+    .prefix(t('~'), 18, "~")
+    .postfix(t(('!', not('='))), 19, "!")
+    .postfix(t("++"), 20, "post++")
+    .postfix(t("--"), 20, "post--")
+    .infix(t("**"), Assoc::Right(28), "**")
+    .infix(t('*'), Assoc::Left(16), "*")
+    .infix(t('/'), Assoc::Left(16), "/")
+    .infix(t('%'), Assoc::Left(16), "%")
+    .infix(t('+'), Assoc::Left(14), "+")
+    .infix(t("-ne"), Assoc::Neither(10), "!=")
+    .infix(t("-eq"), Assoc::Neither(10), "==")
+    .infix(t("-gt"), Assoc::Neither(12), ">")
+    .infix(t("-ge"), Assoc::Neither(12), ">=")
+    .infix(t("-lt"), Assoc::Neither(12), "<")
+    .infix(t("-le"), Assoc::Neither(12), "<=")
+    .infix(t("->"), Assoc::Left(20), "->")
+    .infix(t('-'), Assoc::Left(14), "-")
+    .infix(t('.'), Assoc::Left(20), ".")
+    .infix(t("&&"), Assoc::Left(6), "&&")
+    .infix(t('&'), Assoc::Left(12), "&")
+    .infix(t('^'), Assoc::Left(8), "^")
+    .infix(t("=="), Assoc::Neither(10), "==")
+    .infix(t('='), Assoc::Right(2), "=")
+    .infix(t(">="), Assoc::Neither(12), ">=")
+    .infix(t('>'), Assoc::Neither(12), ">")
+    .infix(t("<="), Assoc::Neither(12), "<=")
+    .infix(t('<'), Assoc::Neither(12), "<")
+    .infix(t(','), Assoc::Left(0), ",")
+    .infix(t("!="), Assoc::Neither(10), "!=")
+    .infix(t("||"), Assoc::Left(4), "||")
+    .ternary(t('?'), t(':'), Assoc::Right(4), "?")
+    .delimited_postfix(t('['), t(']'), 20, "[]")
+    .delimited_postfix(t('('), t(')'), 20, "call")
+}
+
+/// A token after its trivia.
+fn t<'i, T>(
+    parser: impl Parser<Input<'i>, T, Error>,
+) -> impl Parser<Input<'i>, Token<&'i str>, Error> {
+    token(trivia, parser)
+}
+
+/// Whitespace and `/* comments */`.
+fn trivia(i: &mut Input<'_>) -> ModalResult<()> {
+    trace(
+        "trivia",
+        repeat(
+            0..,
+            alt((
+                multispace1.void(),
+                ("/*", take_until(0.., "*/"), "*/").void(),
+            )),
+        ),
+    )
+    .parse_next(i)
+}
+
+fn identifier<'i>(i: &mut Input<'i>) -> ModalResult<&'i str> {
+    trace(
+        "identifier",
+        (
+            one_of(|c: char| c.is_alpha() || c == '_'),
+            take_while(0.., |c: char| c.is_alphanum() || c == '_'),
+        ),
+    )
+    .take()
+    .parse_next(i)
+}
+
+/// Folds a tree into the prefix notation of the `pratt_c` example, like `(+ 1 2)`.
+pub(crate) struct PrefixNotation;
+
+impl<'i> Fold<&'i str, &'static str> for PrefixNotation {
+    type Output = String;
+    type Error = String;
+
+    fn operand(&mut self, token: &Token<&'i str>) -> Result<String, String> {
+        Ok(token.text.into())
+    }
+
+    fn prefix(
+        &mut self,
+        kind: &&'static str,
+        _: &Token<&'i str>,
+        operand: String,
+    ) -> Result<String, String> {
+        Ok(match *kind {
+            "+" => operand,
+            kind => format!("({kind}{operand})"),
+        })
+    }
+
+    fn postfix(
+        &mut self,
+        kind: &&'static str,
+        _: &Token<&'i str>,
+        operand: String,
+    ) -> Result<String, String> {
+        Ok(format!("({kind}{operand})"))
+    }
+
+    fn infix(
+        &mut self,
+        kind: &&'static str,
+        _: &Token<&'i str>,
+        lhs: String,
+        rhs: String,
+    ) -> Result<String, String> {
+        Ok(format!("({kind} {lhs} {rhs})"))
+    }
+
+    fn ternary(
+        &mut self,
+        kind: &&'static str,
+        _: &Token<&'i str>,
+        lhs: String,
+        middle: String,
+        rhs: String,
+    ) -> Result<String, String> {
+        Ok(format!("({kind} {lhs} {middle} {rhs})"))
+    }
+
+    fn delimited(
+        &mut self,
+        kind: &&'static str,
+        op: &Token<&'i str>,
+        operand: String,
+        inner: Option<String>,
+    ) -> Result<String, String> {
+        match (*kind, inner) {
+            (kind, Some(inner)) => Ok(format!("({kind} {operand} {inner})")),
+            ("call", None) => Ok(format!("(call {operand})")),
+            (_, None) => Err(format!("empty index at {:?}", op.span)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_ok(i: &str, expect: &str) {
+        let (tree, trailing) = parse(i).unwrap();
+        assert_eq!(format!("{tree}{trailing}"), i);
+        assert_eq!(tree.fold(&mut PrefixNotation).unwrap(), expect);
+    }
+
+    #[test]
+    fn lossless() {
+        parse_ok("  1 ", "1");
+        parse_ok("x = ((y*4)-2)", "(= x (- (* y 4) 2))");
+        parse_ok("a ++ --", "(post--(post++a))");
+        parse_ok("++x[1]", "(pre++([] x 1))");
+        parse_ok("+-+1", "(-1)");
+        parse_ok("2 ** 3 ** 2", "(** 2 (** 3 2))");
+        parse_ok("&foo()[0]", "(&([] (call foo) 0))");
+        parse_ok(
+            "2 > 1 ? 1 -ne 3 ? 4 : 5 : 1",
+            "(? (> 2 1) (? (!= 1 3) 4 5) 1)",
+        );
+        parse_ok("f(a, b, c), d", "(, (call f (, (, a b) c)) d)");
+        parse_ok("(((0)))", "0");
+        parse_ok(
+            "/* sum */ a /* and */ + b\t*\n c /* done */\n",
+            "(+ a (* b c))",
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let (tree, _) = parse(" f ( a ) /* call */ + 1").unwrap();
+        assert_eq!(tree.span(), 1..23);
+        let tokens: Vec<_> = tree
+            .tokens()
+            .into_iter()
+            .map(|t| (t.text, t.span.clone()))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("f", 1..2),
+                ("(", 3..4),
+                ("a", 5..6),
+                (")", 7..8),
+                ("+", 20..21),
+                ("1", 22..23),
+            ]
+        );
+        assert_eq!(tree.tokens()[4].trivia, " /* call */ ");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("1 == 2 == 3").is_err());
+        assert!(parse("a +").is_err());
+        assert!(parse("( a + b").is_err());
+        assert!(parse("a + /* unclosed").is_err());
+        let (tree, _) = parse("x[ ]").unwrap();
+        assert_eq!(
+            tree.fold(&mut PrefixNotation),
+            Err("empty index at 1..2".into())
+        );
+    }
+}
@@ -0,0 +1,788 @@
+//! A lossless concrete syntax tree, for tools like editors and formatters
+//! which need every token of an expression and where it is.
+//!
+//! A [`SyntaxTable`] is like an [`OperatorTable`], but each operator has a
+//! kind instead of a fold. It parses a [`Node`], which keeps the tokens of the
+//! operators and operands with their spans and the trivia before them, like
+//! whitespace. Printing a node gives back the exact input, and [`Node::fold`]
+//! runs the folds afterwards, as a separate pass.
+//!
+//! ```
+//! use pratt::cst::{Node, SyntaxTable, token};
+//! use pratt::precedence::Assoc;
+//! use winnow::ascii::{digit1, multispace0};
+//! use winnow::{LocatingSlice, Parser, error::ContextError};
+//!
+//! type Input<'i> = LocatingSlice<&'i str>;
+//!
+//! fn expr<'i>() -> SyntaxTable<'i, Input<'i>, char, ContextError> {
+//!     SyntaxTable::new(token(multispace0, digit1).map(Node::Operand))
+//!         .prefix(token(multispace0, '-'), 9, '-')
+//!         .infix(token(multispace0, '+'), Assoc::Left(3), '+')
+//! }
+//!
+//! let input = " 1 +  -2";
+//! let node = expr().parse(LocatingSlice::new(input)).unwrap();
+//! assert_eq!(node.to_string(), input);
+//! assert_eq!(node.span(), 1..8);
+//! ```
+//!
+//! Tokens are parsed with [`token`], so a span starts after the trivia. The
+//! operand parser is the one place where parentheses are parsed, as a
+//! [`Node::Group`], like the operand of an [`OperatorTable`]. Trivia after the
+//! last token isn't part of any node, so it's left in the input.
+//!
+//! [`OperatorTable`]: crate::table::OperatorTable
+
+use core::cell::{Cell, RefCell};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use winnow::{
+    Parser, Result,
+    combinator::{alt, trace},
+    error::{AddContext, ParserError, StrContext},
+    stream::{Location, Stream, StreamIsPartial},
+};
+
+use crate::precedence::{Assoc, Parsers, Power};
+use crate::table::{Algorithm, Pratt};
+
+/// A token of the input, with the trivia before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<S> {
+    /// What was skipped before the token, like whitespace or comments.
+    pub trivia: S,
+    /// The token itself.
+    pub text: S,
+    /// Where the token is in the input, without the trivia.
+    pub span: Range<usize>,
+}
+
+/// Parses a [`Token`] with `parser`, after the `trivia` before it.
+pub fn token<I, T, U, E>(
+    trivia: impl Parser<I, U, E>,
+    parser: impl Parser<I, T, E>,
+) -> impl Parser<I, Token<I::Slice>, E>
+where
+    I: Stream + Location,
+    E: ParserError<I>,
+{
+    trace(
+        "token",
+        (trivia.take(), parser.take().with_span()).map(|(trivia, (text, span))| Token {
+            trivia,
+            text,
+            span,
+        }),
+    )
+}
+
+/// An expression, with every token it was parsed from.
+///
+/// `K` is the kind of an operator, as given to the [`SyntaxTable`], which
+/// tells operators made of the same tokens apart, like a prefix and an infix `-`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node<S, K> {
+    /// An operand, like `a` or `1`.
+    Operand(Token<S>),
+    /// An expression between grouping delimiters, like `(a)`.
+    Group {
+        open: Token<S>,
+        inner: Box<Node<S, K>>,
+        close: Token<S>,
+    },
+    /// A prefix operator, like `-a`.
+    Prefix {
+        kind: K,
+        op: Token<S>,
+        operand: Box<Node<S, K>>,
+    },
+    /// A postfix operator, like `a!`.
+    Postfix {
+        kind: K,
+        operand: Box<Node<S, K>>,
+        op: Token<S>,
+    },
+    /// An infix operator, like `a + b`.
+    Infix {
+        kind: K,
+        lhs: Box<Node<S, K>>,
+        op: Token<S>,
+        rhs: Box<Node<S, K>>,
+    },
+    /// A ternary operator, like `a ? b : c`.
+    Ternary {
+        kind: K,
+        lhs: Box<Node<S, K>>,
+        open: Token<S>,
+        middle: Box<Node<S, K>>,
+        close: Token<S>,
+        rhs: Box<Node<S, K>>,
+    },
+    /// A delimited postfix operator, like `f(a)` or `f()`.
+    Delimited {
+        kind: K,
+        operand: Box<Node<S, K>>,
+        open: Token<S>,
+        inner: Option<Box<Node<S, K>>>,
+        close: Token<S>,
+    },
+}
+
+impl<S, K> Node<S, K> {
+    /// A [`Node::Group`], for parsing parentheses as
+    /// `(open, expr, close).map(Node::group)`.
+    pub fn group((open, inner, close): (Token<S>, Self, Token<S>)) -> Self {
+        Node::Group {
+            open,
+            inner: Box::new(inner),
+            close,
+        }
+    }
+
+    /// Where the expression is in the input, from its first token to its last.
+    pub fn span(&self) -> Range<usize> {
+        self.first().span.start..self.last().span.end
+    }
+
+    /// The tokens of the expression, in the order of the input.
+    pub fn tokens(&self) -> Vec<&Token<S>> {
+        let mut tokens = Vec::new();
+        let _ = self.try_for_each_token(&mut |token| {
+            tokens.push(token);
+            Ok::<_, ()>(())
+        });
+        tokens
+    }
+
+    /// Folds the expression from its operands up, like the folds of an
+    /// [`OperatorTable`](crate::table::OperatorTable) would have while parsing.
+    pub fn fold<F: Fold<S, K>>(&self, folder: &mut F) -> Result<F::Output, F::Error> {
+        match self {
+            Node::Operand(token) => folder.operand(token),
+            Node::Group { inner, .. } => {
+                let inner = inner.fold(folder)?;
+                folder.group(inner)
+            }
+            Node::Prefix { kind, op, operand } => {
+                let operand = operand.fold(folder)?;
+                folder.prefix(kind, op, operand)
+            }
+            Node::Postfix { kind, operand, op } => {
+                let operand = operand.fold(folder)?;
+                folder.postfix(kind, op, operand)
+            }
+            Node::Infix { kind, lhs, op, rhs } => {
+                let lhs = lhs.fold(folder)?;
+                let rhs = rhs.fold(folder)?;
+                folder.infix(kind, op, lhs, rhs)
+            }
+            Node::Ternary {
+                kind,
+                lhs,
+                open,
+                middle,
+                rhs,
+                ..
+            } => {
+                let lhs = lhs.fold(folder)?;
+                let middle = middle.fold(folder)?;
+                let rhs = rhs.fold(folder)?;
+                folder.ternary(kind, open, lhs, middle, rhs)
+            }
+            Node::Delimited {
+                kind,
+                operand,
+                open,
+                inner,
+                ..
+            } => {
+                let operand = operand.fold(folder)?;
+                let inner = match inner {
+                    Some(inner) => Some(inner.fold(folder)?),
+                    None => None,
+                };
+                folder.delimited(kind, open, operand, inner)
+            }
+        }
+    }
+
+    fn first(&self) -> &Token<S> {
+        match self {
+            Node::Operand(token) => token,
+            Node::Group { open, .. } => open,
+            Node::Prefix { op, .. } => op,
+            Node::Postfix { operand, .. } | Node::Delimited { operand, .. } => operand.first(),
+            Node::Infix { lhs, .. } | Node::Ternary { lhs, .. } => lhs.first(),
+        }
+    }
+
+    fn last(&self) -> &Token<S> {
+        match self {
+            Node::Operand(token) => token,
+            Node::Group { close, .. } | Node::Delimited { close, .. } => close,
+            Node::Postfix { op, .. } => op,
+            Node::Prefix { operand, .. } => operand.last(),
+            Node::Infix { rhs, .. } | Node::Ternary { rhs, .. } => rhs.last(),
+        }
+    }
+
+    fn try_for_each_token<'n, R>(
+        &'n self,
+        f: &mut impl FnMut(&'n Token<S>) -> Result<(), R>,
+    ) -> Result<(), R> {
+        match self {
+            Node::Operand(token) => f(token),
+            Node::Group { open, inner, close } => {
+                f(open)?;
+                inner.try_for_each_token(f)?;
+                f(close)
+            }
+            Node::Prefix { op, operand, .. } => {
+                f(op)?;
+                operand.try_for_each_token(f)
+            }
+            Node::Postfix { operand, op, .. } => {
+                operand.try_for_each_token(f)?;
+                f(op)
+            }
+            Node::Infix { lhs, op, rhs, .. } => {
+                lhs.try_for_each_token(f)?;
+                f(op)?;
+                rhs.try_for_each_token(f)
+            }
+            Node::Ternary {
+                lhs,
+                open,
+                middle,
+                close,
+                rhs,
+                ..
+            } => {
+                lhs.try_for_each_token(f)?;
+                f(open)?;
+                middle.try_for_each_token(f)?;
+                f(close)?;
+                rhs.try_for_each_token(f)
+            }
+            Node::Delimited {
+                operand,
+                open,
+                inner,
+                close,
+                ..
+            } => {
+                operand.try_for_each_token(f)?;
+                f(open)?;
+                if let Some(inner) = inner {
+                    inner.try_for_each_token(f)?;
+                }
+                f(close)
+            }
+        }
+    }
+}
+
+/// Writes the tokens and their trivia, which is the input the node was parsed from.
+impl<S: fmt::Display, K> fmt::Display for Node<S, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.try_for_each_token(&mut |token| write!(f, "{}{}", token.trivia, token.text))
+    }
+}
+
+/// The folds of a [`Node`], which get the kind and the token of each operator.
+/// For ternary and delimited postfix operators, that's the opening delimiter.
+pub trait Fold<S, K> {
+    type Output;
+    type Error;
+
+    fn operand(&mut self, token: &Token<S>) -> Result<Self::Output, Self::Error>;
+
+    /// Folds a group, which is its inner expression by default.
+    fn group(&mut self, inner: Self::Output) -> Result<Self::Output, Self::Error> {
+        Ok(inner)
+    }
+
+    fn prefix(
+        &mut self,
+        kind: &K,
+        op: &Token<S>,
+        operand: Self::Output,
+    ) -> Result<Self::Output, Self::Error>;
+
+    fn postfix(
+        &mut self,
+        kind: &K,
+        op: &Token<S>,
+        operand: Self::Output,
+    ) -> Result<Self::Output, Self::Error>;
+
+    fn infix(
+        &mut self,
+        kind: &K,
+        op: &Token<S>,
+        lhs: Self::Output,
+        rhs: Self::Output,
+    ) -> Result<Self::Output, Self::Error>;
+
+    fn ternary(
+        &mut self,
+        kind: &K,
+        op: &Token<S>,
+        lhs: Self::Output,
+        middle: Self::Output,
+        rhs: Self::Output,
+    ) -> Result<Self::Output, Self::Error>;
+
+    fn delimited(
+        &mut self,
+        kind: &K,
+        op: &Token<S>,
+        operand: Self::Output,
+        inner: Option<Self::Output>,
+    ) -> Result<Self::Output, Self::Error>;
+}
+
+/// The closing delimiter of a ternary or delimited postfix operator, see
+/// [`OperatorTable`](crate::table::OperatorTable).
+type Close<'a, I, E> = RefCell<Box<dyn Parser<I, Token<<I as Stream>::Slice>, E> + 'a>>;
+
+/// An operator parser, which gives the index of its kind and its token.
+type OperatorParser<'a, I, E> = Box<dyn Parser<I, (usize, Token<<I as Stream>::Slice>), E> + 'a>;
+
+/// The operators of one kind, where each parser gives the index of its kind.
+struct Operators<'a, I: Stream, T, E> {
+    parsers: Vec<OperatorParser<'a, I, E>>,
+    kinds: Vec<T>,
+}
+
+impl<'a, I, T, E> Operators<'a, I, T, E>
+where
+    I: Stream + 'a,
+    E: ParserError<I> + 'a,
+{
+    fn new() -> Self {
+        Self {
+            parsers: Vec::new(),
+            kinds: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, parser: impl Parser<I, Token<I::Slice>, E> + 'a, kind: T) {
+        let index = self.kinds.len();
+        self.parsers
+            .push(Box::new(parser.map(move |token| (index, token))));
+        self.kinds.push(kind);
+    }
+}
+
+/// A table of operators around an operand parser, which parses a [`Node`].
+///
+/// See the [module docs](self) for an example.
+pub struct SyntaxTable<'a, I: Stream, K, E, A = Pratt> {
+    start_power: Power,
+    operand: Box<dyn Parser<I, Node<I::Slice, K>, E> + 'a>,
+    prefix: Operators<'a, I, (Power, K), E>,
+    postfix: Operators<'a, I, (Power, K), E>,
+    infix: Operators<'a, I, (Assoc, K), E>,
+    ternary: Operators<'a, I, (Assoc, Close<'a, I, E>, K), E>,
+    delimited: Operators<'a, I, (Power, Close<'a, I, E>, K), E>,
+    algorithm: PhantomData<fn() -> A>,
+}
+
+impl<'a, I, K, E, A> SyntaxTable<'a, I, K, E, A>
+where
+    I: Stream + StreamIsPartial + 'a,
+    K: 'a,
+    E: ParserError<I> + 'a,
+{
+    /// Creates a table without operators, which parses `operand`.
+    pub fn new(operand: impl Parser<I, Node<I::Slice, K>, E> + 'a) -> Self {
+        Self {
+            start_power: 0,
+            operand: Box::new(operand),
+            prefix: Operators::new(),
+            postfix: Operators::new(),
+            infix: Operators::new(),
+            ternary: Operators::new(),
+            delimited: Operators::new(),
+            algorithm: PhantomData,
+        }
+    }
+
+    /// Only parses operators with at least this binding power, `0` by default.
+    pub fn start_power(mut self, power: Power) -> Self {
+        self.start_power = power;
+        self
+    }
+
+    /// Adds a prefix operator, like `-a`. Its operand is parsed with `power`.
+    pub fn prefix(
+        mut self,
+        parser: impl Parser<I, Token<I::Slice>, E> + 'a,
+        power: Power,
+        kind: K,
+    ) -> Self {
+        self.prefix.push(parser, (power, kind));
+        self
+    }
+
+    /// Adds a postfix operator, like `a!`, see
+    /// [`OperatorTable::postfix`](crate::table::OperatorTable::postfix).
+    pub fn postfix(
+        mut self,
+        parser: impl Parser<I, Token<I::Slice>, E> + 'a,
+        power: Power,
+        kind: K,
+    ) -> Self {
+        self.postfix.push(parser, (power, kind));
+        self
+    }
+
+    /// Adds an infix operator, like `a + b`.
+    pub fn infix(
+        mut self,
+        parser: impl Parser<I, Token<I::Slice>, E> + 'a,
+        assoc: Assoc,
+        kind: K,
+    ) -> Self {
+        self.infix.push(parser, (assoc, kind));
+        self
+    }
+
+    /// Adds a ternary operator, like `a ? b : c`, see
+    /// [`OperatorTable::ternary`](crate::table::OperatorTable::ternary).
+    pub fn ternary(
+        mut self,
+        open: impl Parser<I, Token<I::Slice>, E> + 'a,
+        close: impl Parser<I, Token<I::Slice>, E> + 'a,
+        assoc: Assoc,
+        kind: K,
+    ) -> Self {
+        let close = RefCell::new(Box::new(close) as Box<dyn Parser<_, _, _>>);
+        self.ternary.push(open, (assoc, close, kind));
+        self
+    }
+
+    /// Adds a delimited postfix operator, like a call `f(a)` or an index
+    /// `a[i]`, see
+    /// [`OperatorTable::delimited_postfix`](crate::table::OperatorTable::delimited_postfix).
+    pub fn delimited_postfix(
+        mut self,
+        open: impl Parser<I, Token<I::Slice>, E> + 'a,
+        close: impl Parser<I, Token<I::Slice>, E> + 'a,
+        power: Power,
+        kind: K,
+    ) -> Self {
+        let close = RefCell::new(Box::new(close) as Box<dyn Parser<_, _, _>>);
+        self.delimited.push(open, (power, close, kind));
+        self
+    }
+}
+
+impl<I, K, E, A> Parser<I, Node<I::Slice, K>, E> for SyntaxTable<'_, I, K, E, A>
+where
+    I: Stream + StreamIsPartial,
+    K: Clone,
+    E: ParserError<I> + AddContext<I, StrContext>,
+    A: Algorithm,
+{
+    fn parse_next(&mut self, i: &mut I) -> Result<Node<I::Slice, K>, E> {
+        let Self {
+            start_power,
+            operand,
+            prefix,
+            postfix,
+            infix,
+            ternary,
+            delimited,
+            ..
+        } = self;
+        let mut grammar = Parsers::new(
+            *start_power,
+            |i: &mut I| operand.parse_next(i),
+            |i: &mut I| {
+                let ((power, kind), op) = first_match(&mut prefix.parsers, &prefix.kinds, i)?;
+                let kind = kind.clone();
+                Ok((*power, move |_: &mut I, operand| {
+                    Ok(Node::Prefix {
+                        kind,
+                        op,
+                        operand: Box::new(operand),
+                    })
+                }))
+            },
+            |i: &mut I| {
+                let ((power, kind), op) = first_match(&mut postfix.parsers, &postfix.kinds, i)?;
+                let kind = kind.clone();
+                Ok((*power, move |_: &mut I, operand| {
+                    Ok(Node::Postfix {
+                        kind,
+                        operand: Box::new(operand),
+                        op,
+                    })
+                }))
+            },
+            |i: &mut I| {
+                let ((assoc, kind), op) = first_match(&mut infix.parsers, &infix.kinds, i)?;
+                let kind = kind.clone();
+                Ok((*assoc, move |_: &mut I, lhs, rhs| {
+                    Ok(Node::Infix {
+                        kind,
+                        lhs: Box::new(lhs),
+                        op,
+                        rhs: Box::new(rhs),
+                    })
+                }))
+            },
+            |i: &mut I| {
+                let ((assoc, close, kind), open) =
+                    first_match(&mut ternary.parsers, &ternary.kinds, i)?;
+                let kind = kind.clone();
+                let (close, closed) = remember(close);
+                Ok((*assoc, close, move |i: &mut I, lhs, middle, rhs| {
+                    Ok(Node::Ternary {
+                        kind,
+                        lhs: Box::new(lhs),
+                        open,
+                        middle: Box::new(middle),
+                        close: closing_token(i, &closed)?,
+                        rhs: Box::new(rhs),
+                    })
+                }))
+            },
+            |i: &mut I| {
+                let ((power, close, kind), open) =
+                    first_match(&mut delimited.parsers, &delimited.kinds, i)?;
+                let kind = kind.clone();
+                let (close, closed) = remember(close);
+                Ok((
+                    *power,
+                    close,
+                    move |i: &mut I, operand, inner: Option<_>| {
+                        Ok(Node::Delimited {
+                            kind,
+                            operand: Box::new(operand),
+                            open,
+                            inner: inner.map(Box::new),
+                            close: closing_token(i, &closed)?,
+                        })
+                    },
+                ))
+            },
+        );
+        trace("syntax_table", |i: &mut I| {
+            A::precedence(i, &mut grammar, *start_power)
+        })
+        .parse_next(i)
+    }
+}
+
+/// The closing token of a ternary or delimited postfix operator, which is
+/// parsed by the algorithm before the fold, but not given to it.
+type Closed<S> = Rc<Cell<Option<Token<S>>>>;
+
+/// Parses with `close`, and keeps the token for the fold.
+fn remember<'c, I, E>(
+    close: &'c Close<'_, I, E>,
+) -> (impl FnMut(&mut I) -> Result<(), E> + 'c, Closed<I::Slice>)
+where
+    I: Stream,
+{
+    let closed = Rc::new(Cell::new(None));
+    let remembered = Rc::clone(&closed);
+    let close = move |i: &mut I| {
+        let token = close.borrow_mut().parse_next(i)?;
+        remembered.set(Some(token));
+        Ok(())
+    };
+    (close, closed)
+}
+
+/// Takes the token kept by [`remember`].
+fn closing_token<I, E>(i: &I, closed: &Closed<I::Slice>) -> Result<Token<I::Slice>, E>
+where
+    I: Stream,
+    E: ParserError<I>,
+{
+    closed
+        .take()
+        .ok_or_else(|| E::assert(i, "the closing delimiter is parsed before the fold"))
+}
+
+/// Tries each operator in order, failing like `fail` when there are none.
+fn first_match<'k, I, T, E>(
+    parsers: &mut [OperatorParser<'_, I, E>],
+    kinds: &'k [T],
+    i: &mut I,
+) -> Result<(&'k T, Token<I::Slice>), E>
+where
+    I: Stream,
+    E: ParserError<I>,
+{
+    if parsers.is_empty() {
+        return Err(E::from_input(i));
+    }
+    let (index, token) = alt(parsers).parse_next(i)?;
+    Ok((&kinds[index], token))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use winnow::LocatingSlice;
+    use winnow::ascii::{digit1, multispace0};
+    use winnow::combinator::not;
+    use winnow::error::ContextError;
+
+    use super::*;
+    use crate::table::ShuntingYard;
+
+    type Input<'i> = LocatingSlice<&'i str>;
+
+    fn ws<'i>(
+        op: impl Parser<Input<'i>, &'i str, ContextError>,
+    ) -> impl Parser<Input<'i>, Token<&'i str>, ContextError> {
+        token(multispace0, op)
+    }
+
+    fn calc<'i, A: Algorithm + 'static>()
+    -> SyntaxTable<'i, Input<'i>, &'static str, ContextError, A> {
+        SyntaxTable::new(alt((
+            ws(digit1).map(Node::Operand),
+            (ws("("), expr::<A>, ws(")")).map(Node::group),
+        )))
+        .prefix(ws("-"), 9, "neg")
+        .postfix(ws(("!", not('=')).take()), 11, "fac")
+        .infix(ws("=="), Assoc::Neither(2), "eq")
+        .infix(ws("+"), Assoc::Left(5), "add")
+        .infix(ws("-"), Assoc::Left(5), "sub")
+        .infix(ws("*"), Assoc::Left(7), "mul")
+        .ternary(ws("?"), ws(":"), Assoc::Right(1), "if")
+        .delimited_postfix(ws("["), ws("]"), 12, "digit")
+    }
+
+    fn expr<'i, A: Algorithm + 'static>(i: &mut Input<'i>) -> Result<Node<&'i str, &'static str>> {
+        calc::<A>().parse_next(i)
+    }
+
+    /// Evaluates like the `calc` of the table tests.
+    struct Eval;
+
+    impl Fold<&str, &str> for Eval {
+        type Output = i32;
+        type Error = String;
+
+        fn operand(&mut self, token: &Token<&str>) -> Result<i32, String> {
+            token.text.parse().map_err(|e| format!("{e}"))
+        }
+
+        fn prefix(&mut self, _: &&str, _: &Token<&str>, a: i32) -> Result<i32, String> {
+            Ok(-a)
+        }
+
+        fn postfix(&mut self, _: &&str, _: &Token<&str>, a: i32) -> Result<i32, String> {
+            Ok((1..=a).product())
+        }
+
+        fn infix(&mut self, kind: &&str, op: &Token<&str>, a: i32, b: i32) -> Result<i32, String> {
+            match *kind {
+                "eq" => Ok((a == b) as i32),
+                "add" => Ok(a + b),
+                "sub" => Ok(a - b),
+                "mul" => Ok(a * b),
+                _ => Err(format!("unknown operator at {:?}", op.span)),
+            }
+        }
+
+        fn ternary(
+            &mut self,
+            _: &&str,
+            _: &Token<&str>,
+            a: i32,
+            b: i32,
+            c: i32,
+        ) -> Result<i32, String> {
+            Ok(if a != 0 { b } else { c })
+        }
+
+        fn delimited(
+            &mut self,
+            _: &&str,
+            _: &Token<&str>,
+            a: i32,
+            b: Option<i32>,
+        ) -> Result<i32, String> {
+            Ok(a * 10 + b.unwrap_or(0))
+        }
+    }
+
+    /// Parses with both algorithms, which have to agree on the tree.
+    fn both(input: &str) -> Node<&str, &'static str> {
+        let pratt = calc::<Pratt>().parse(LocatingSlice::new(input)).unwrap();
+        let shunting_yard = calc::<ShuntingYard>()
+            .parse(LocatingSlice::new(input))
+            .unwrap();
+        assert_eq!(pratt, shunting_yard, "{input}");
+        pratt
+    }
+
+    #[test]
+    fn test_lossless() {
+        for input in [
+            "1",
+            "  1 +2* 3",
+            "( 1 + 2 ) * 3",
+            "- 3 ! + -3 *4",
+            "1 == 1 ? 2 : 3",
+            " 4 [ 2 ] [ ]",
+            "(((1)))",
+        ] {
+            assert_eq!(both(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_spans() {
+        let node = both(" (1 + 2) * 3");
+        assert_eq!(node.span(), 1..12);
+        let Node::Infix { kind, lhs, op, rhs } = &node else {
+            panic!("{node:?}");
+        };
+        assert_eq!(*kind, "mul");
+        assert_eq!(lhs.span(), 1..8);
+        assert_eq!((op.trivia, op.text, op.span.clone()), (" ", "*", 9..10));
+        assert_eq!(rhs.span(), 11..12);
+
+        let texts: Vec<_> = both("4[2] ? 1 : 0")
+            .tokens()
+            .iter()
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(texts, ["4", "[", "2", "]", "?", "1", ":", "0"]);
+    }
+
+    #[test]
+    fn test_fold() {
+        let eval = |input| both(input).fold(&mut Eval);
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("-3! + -3 * 4"), Ok(-18));
+        assert_eq!(eval("0 ? 1 : 0 ? 2 : 3"), Ok(3));
+        assert_eq!(eval("4[2][]"), Ok(420));
+    }
+
+    #[test]
+    fn test_trailing_trivia() {
+        let mut i = LocatingSlice::new("1 + 2 ");
+        let node = expr::<Pratt>(&mut i).unwrap();
+        assert_eq!(node.to_string(), "1 + 2");
+        assert_eq!(multispace0::<_, ContextError>.parse_next(&mut i), Ok(" "));
+    }
+}
@@ -9,10 +9,20 @@
 //! The main things to look at are:
 //! * [`precedence::precedence`], the Pratt Parser entrypoint
 //! * [`shunting_yard::precedence`], the Shunting Yard entrypoint
+//! * [`precedence::mixfix`] and [`shunting_yard::mixfix`], which also parse
+//!   ternary operators like `a ? b : c` and delimited postfix operators like `f(a)`
+//! * [`table::OperatorTable`], a less verbose way to use either of them
+//! * [`cst::SyntaxTable`], which parses a lossless tree with spans, to fold later
 //!
 //! Slightly less interesting, but also useful:
 //! * [`precedence::Assoc`], the associativity of an operator
 //! * [`precedence::Power`], a type alias for precedence power
+//! * [`precedence::Depth`], a limit on how deeply expressions nest
+//! * [`recovery::Recovery`], which records errors in an expression instead of failing
+//!
+//! Without the default `std` feature the crate is `no_std`, and without the
+//! `alloc` feature it only has the recursive [`precedence`] module, which
+//! doesn't allocate.
 //!
 //! [winnow-pr]: <https://github.com/winnow-rs/winnow/pull/620>
 //! [ghuser-39555]: <https://github.com/39555>
@@ -33,3 +43,12 @@ pub mod precedence;
 #[cfg(feature = "alloc")]
 #[allow(dead_code)]
 pub mod shunting_yard;
+
+#[cfg(feature = "alloc")]
+pub mod table;
+
+#[cfg(feature = "alloc")]
+pub mod cst;
+
+#[cfg(feature = "alloc")]
+pub mod recovery;
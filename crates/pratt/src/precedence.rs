@@ -1,42 +1,217 @@
+use core::cell::Cell;
+use core::marker::PhantomData;
+
 use winnow::{
     Parser, Result,
     combinator::{opt, trace},
-    error::ParserError,
+    error::{AddContext, ParserError, StrContext, StrContextValue},
     stream::{Stream, StreamIsPartial},
 };
 
 pub type Power = i64;
 
+/// A prefix or postfix fold as a function pointer.
+///
+/// Any `FnOnce(&mut I, O) -> Result<O, E>` can be a fold, like a closure
+/// that captures an arena, but the operators of a `dispatch!` have to agree
+/// on a type. Casting each closure with `as UnaryFold<_, _, _>` does that
+/// when none of them capture anything. Closures that capture something can
+/// be returned from one function instead, so they share its return type, like
+/// the folds of the `pratt_c_bump` example, which capture an arena.
+pub type UnaryFold<I, O, E> = fn(&mut I, O) -> Result<O, E>;
+
+/// An infix fold as a function pointer, see [`UnaryFold`].
+pub type BinaryFold<I, O, E> = fn(&mut I, O, O) -> Result<O, E>;
+
+/// A ternary fold as a function pointer, see [`UnaryFold`]. It gets the
+/// operands in order, so `a ? b : c` is folded from `a`, `b` and `c`.
+pub type TernaryFold<I, O, E> = fn(&mut I, O, O, O) -> Result<O, E>;
+
+/// A delimited postfix fold as a function pointer, see [`UnaryFold`]. It gets
+/// the operand and the expression between the delimiters, if there is one,
+/// so `f()` is folded from `f` and `None`.
+pub type DelimitedFold<I, O, E> = fn(&mut I, O, Option<O>) -> Result<O, E>;
+
+/// The closing delimiter of a ternary or delimited postfix operator as a
+/// function pointer, for the same reason as [`UnaryFold`].
+pub type CloseDelimiter<I, E> = fn(&mut I) -> Result<(), E>;
+
+/// The label of a missing operand right after a prefix operator, like `-`.
+pub(crate) const PREFIX: StrContext = StrContext::Label("prefix operator");
+
+/// The label of a missing operand right after an infix operator, like `1 +`.
+pub(crate) const INFIX: StrContext = StrContext::Label("infix operator");
+
+/// The label of a missing operand or delimiter in a ternary operator, like `a ? b`.
+pub(crate) const TERNARY: StrContext = StrContext::Label("ternary operator");
+
+/// The label of a missing operand or delimiter in a delimited postfix operator,
+/// like `a[`.
+pub(crate) const DELIMITED: StrContext = StrContext::Label("delimited postfix operator");
+
+/// The label of an expression nested deeper than a [`Depth`] allows.
+const TOO_DEEP: StrContext = StrContext::Label("nesting depth");
+
+/// The error for a missing operand, optionally right after an operator.
+pub(crate) fn missing_operand<I, E>(i: &I, after: Option<StrContext>) -> E
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    let start = i.checkpoint();
+    let e = E::from_input(i).add_context(
+        i,
+        &start,
+        StrContext::Expected(StrContextValue::Description("operand")),
+    );
+    match after {
+        Some(after) => e.add_context(i, &start, after),
+        None => e,
+    }
+}
+
+/// The error for an operator after a non-associative operator of the same
+/// power, like the second `==` in `a == b == c`. `i` is at the operator.
+pub(crate) fn chained_neither<I, E>(i: &I) -> E
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    let start = i.checkpoint();
+    E::from_input(i).add_context(
+        i,
+        &start,
+        StrContext::Label("chained non-associative operator"),
+    )
+}
+
 /// Parses an expression based on operator precedence.
+///
+/// See [`mixfix`] to also parse ternary and delimited postfix operators.
 #[doc(alias = "pratt")]
 #[doc(alias = "separated")]
 #[doc(alias = "shunting_yard")]
 #[doc(alias = "precedence_climbing")]
 #[inline(always)]
-pub fn precedence<I, ParseOperand, ParseInfix, ParsePrefix, ParsePostfix, Operand, E>(
+pub fn precedence<
+    I,
+    ParseOperand,
+    ParseInfix,
+    ParsePrefix,
+    ParsePostfix,
+    FoldInfix,
+    FoldPrefix,
+    FoldPostfix,
+    Operand,
+    E,
+>(
     start_power: Power,
-    mut operand: ParseOperand,
-    mut prefix: ParsePrefix,
-    mut postfix: ParsePostfix,
-    mut infix: ParseInfix,
+    operand: ParseOperand,
+    prefix: ParsePrefix,
+    postfix: ParsePostfix,
+    infix: ParseInfix,
 ) -> impl Parser<I, Operand, E>
 where
     I: Stream + StreamIsPartial,
     ParseOperand: Parser<I, Operand, E>,
-    ParseInfix: Parser<I, (Assoc, fn(&mut I, Operand, Operand) -> Result<Operand, E>), E>,
-    ParsePrefix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    ParsePostfix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    E: ParserError<I>,
+    ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+    ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+    ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldPostfix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    mixfix(
+        start_power,
+        operand,
+        prefix,
+        postfix,
+        infix,
+        no_ternary,
+        no_delimited,
+    )
+}
+
+/// Parses an expression based on operator precedence, with ternary and
+/// delimited postfix operators.
+///
+/// A ternary operator, like `a ? b : c`, is parsed as an infix operator which
+/// is made of a whole expression between two delimiters. The `ternary` parser
+/// parses the first one, like `?`, and gives the parser of the second one, like `:`.
+/// The expression between them is parsed from `start_power`, like in parentheses.
+///
+/// A delimited postfix operator, like a call `f(a)` or an index `a[i]`,
+/// is parsed as a postfix operator which is made of an optional expression
+/// between two delimiters. The `delimited` parser parses the first one, like `(`,
+/// and gives the parser of the second one, like `)`.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+pub fn mixfix<
+    I,
+    ParseOperand,
+    ParseInfix,
+    ParsePrefix,
+    ParsePostfix,
+    ParseTernary,
+    ParseDelimited,
+    FoldInfix,
+    FoldPrefix,
+    FoldPostfix,
+    FoldTernary,
+    FoldDelimited,
+    CloseTernary,
+    CloseDelimited,
+    Operand,
+    E,
+>(
+    start_power: Power,
+    operand: ParseOperand,
+    prefix: ParsePrefix,
+    postfix: ParsePostfix,
+    infix: ParseInfix,
+    ternary: ParseTernary,
+    delimited: ParseDelimited,
+) -> impl Parser<I, Operand, E>
+where
+    I: Stream + StreamIsPartial,
+    ParseOperand: Parser<I, Operand, E>,
+    ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+    ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+    ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+    ParseTernary: Parser<I, (Assoc, CloseTernary, FoldTernary), E>,
+    ParseDelimited: Parser<I, (Power, CloseDelimited, FoldDelimited), E>,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldPostfix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldTernary: FnOnce(&mut I, Operand, Operand, Operand) -> Result<Operand, E>,
+    FoldDelimited: FnOnce(&mut I, Operand, Option<Operand>) -> Result<Operand, E>,
+    CloseTernary: Parser<I, (), E>,
+    CloseDelimited: Parser<I, (), E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    pratt(Parsers::new(
+        start_power,
+        operand,
+        prefix,
+        postfix,
+        infix,
+        ternary,
+        delimited,
+    ))
+}
+
+/// Parses an expression with the recursive Pratt parser.
+#[inline(always)]
+pub(crate) fn pratt<I, O, E, G>(mut grammar: G) -> impl Parser<I, O, E>
+where
+    I: Stream + StreamIsPartial,
+    G: Grammar<I, O, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
 {
     trace("precedence", move |i: &mut I| {
-        let result = precedence_impl(
-            i,
-            &mut operand,
-            &mut prefix,
-            &mut postfix,
-            &mut infix,
-            start_power,
-        )?;
+        let start_power = grammar.start_power();
+        let result = precedence_impl(i, &mut grammar, start_power, None)?;
         Ok(result)
     })
 }
@@ -48,47 +223,658 @@ pub enum Assoc {
     Neither(Power),
 }
 
+impl Assoc {
+    /// The left and right binding power.
+    pub(crate) fn powers(self) -> (Power, Power) {
+        match self {
+            Assoc::Right(p) => (p, p - 1),
+            Assoc::Left(p) | Assoc::Neither(p) => (p, p + 1),
+        }
+    }
+
+    /// The power, if it's [`Assoc::Neither`].
+    pub(crate) fn neither(self) -> Option<Power> {
+        match self {
+            Assoc::Neither(p) => Some(p),
+            Assoc::Left(_) | Assoc::Right(_) => None,
+        }
+    }
+}
+
+/// A limit on how deeply expressions nest, so that parsing an input like
+/// `((((...))))` or `- - - - ...` fails instead of overflowing the stack.
+///
+/// The recursive parser counts its own recursion with [`Depth::precedence`]
+/// or [`Depth::mixfix`], but the recursion of an operand parser, like
+/// parentheses, happens outside of it. Wrapping that with [`Depth::nest`] makes
+/// it count towards the same limit.
+///
+/// ```
+/// use pratt::precedence::{Assoc, BinaryFold, Depth, Power, UnaryFold};
+/// use winnow::combinator::{alt, delimited, empty, fail};
+/// use winnow::{Parser, ascii::digit1, error::ContextError};
+///
+/// fn expr<'i, 'd>(depth: &'d Depth) -> impl Parser<&'i str, i64, ContextError> + 'd {
+///     move |i: &mut &'i str| {
+///         depth
+///             .precedence(
+///                 0,
+///                 alt((digit1.parse_to(), depth.nest(delimited('(', expr(depth), ')')))),
+///                 '-'.value((9, (|_: &mut _, a: i64| Ok(-a)) as UnaryFold<_, _, _>)),
+///                 fail::<_, (Power, UnaryFold<_, _, _>), _>,
+///                 '+'.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>)),
+///             )
+///             .parse_next(i)
+///     }
+/// }
+///
+/// let depth = Depth::new(16);
+/// assert_eq!(expr(&depth).parse("-(1+(2+3))"), Ok(-6));
+/// assert!(expr(&depth).parse(&"(".repeat(100)).is_err());
+/// assert!(expr(&depth).parse(&"-".repeat(100)).is_err());
+/// ```
+#[derive(Debug)]
+pub struct Depth {
+    max: usize,
+    current: Cell<usize>,
+    /// The `eof_offset` where the limit was hit inside the operand being
+    /// parsed, if it was.
+    too_deep_at: Cell<Option<usize>>,
+}
+
+impl Depth {
+    /// Allows nesting expressions at most `max` levels deep. Each operand of an
+    /// operator is one level deeper than the operator, and so is each
+    /// parser wrapped with [`Depth::nest`].
+    pub const fn new(max: usize) -> Self {
+        Self {
+            max,
+            current: Cell::new(0),
+            too_deep_at: Cell::new(None),
+        }
+    }
+
+    /// How deeply nested the expression being parsed is.
+    pub fn current(&self) -> usize {
+        self.current.get()
+    }
+
+    /// Parses with `parser` one level deeper.
+    pub fn nest<I, O, E>(&self, mut parser: impl Parser<I, O, E>) -> impl Parser<I, O, E>
+    where
+        I: Stream,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        trace("nest", move |i: &mut I| {
+            self.enter(i)?;
+            let result = parser.parse_next(i);
+            self.leave();
+            result
+        })
+    }
+
+    /// Like [`precedence`], which fails when nested deeper than the limit.
+    #[inline(always)]
+    pub fn precedence<
+        I,
+        ParseOperand,
+        ParseInfix,
+        ParsePrefix,
+        ParsePostfix,
+        FoldInfix,
+        FoldPrefix,
+        FoldPostfix,
+        Operand,
+        E,
+    >(
+        &self,
+        start_power: Power,
+        operand: ParseOperand,
+        prefix: ParsePrefix,
+        postfix: ParsePostfix,
+        infix: ParseInfix,
+    ) -> impl Parser<I, Operand, E>
+    where
+        I: Stream + StreamIsPartial,
+        ParseOperand: Parser<I, Operand, E>,
+        ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+        ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+        ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+        FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+        FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+        FoldPostfix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        self.mixfix(
+            start_power,
+            operand,
+            prefix,
+            postfix,
+            infix,
+            no_ternary,
+            no_delimited,
+        )
+    }
+
+    /// Like [`mixfix`], which fails when nested deeper than the limit.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn mixfix<
+        I,
+        ParseOperand,
+        ParseInfix,
+        ParsePrefix,
+        ParsePostfix,
+        ParseTernary,
+        ParseDelimited,
+        FoldInfix,
+        FoldPrefix,
+        FoldPostfix,
+        FoldTernary,
+        FoldDelimited,
+        CloseTernary,
+        CloseDelimited,
+        Operand,
+        E,
+    >(
+        &self,
+        start_power: Power,
+        operand: ParseOperand,
+        prefix: ParsePrefix,
+        postfix: ParsePostfix,
+        infix: ParseInfix,
+        ternary: ParseTernary,
+        delimited: ParseDelimited,
+    ) -> impl Parser<I, Operand, E>
+    where
+        I: Stream + StreamIsPartial,
+        ParseOperand: Parser<I, Operand, E>,
+        ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+        ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+        ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+        ParseTernary: Parser<I, (Assoc, CloseTernary, FoldTernary), E>,
+        ParseDelimited: Parser<I, (Power, CloseDelimited, FoldDelimited), E>,
+        FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+        FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+        FoldPostfix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+        FoldTernary: FnOnce(&mut I, Operand, Operand, Operand) -> Result<Operand, E>,
+        FoldDelimited: FnOnce(&mut I, Operand, Option<Operand>) -> Result<Operand, E>,
+        CloseTernary: Parser<I, (), E>,
+        CloseDelimited: Parser<I, (), E>,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        pratt(
+            Parsers::new(
+                start_power,
+                operand,
+                prefix,
+                postfix,
+                infix,
+                ternary,
+                delimited,
+            )
+            .with_depth(self),
+        )
+    }
+
+    /// Goes one level deeper, unless that's deeper than the limit.
+    fn enter<I, E>(&self, i: &I) -> Result<(), E>
+    where
+        I: Stream,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        let current = self.current.get();
+        if current >= self.max {
+            self.too_deep_at.set(Some(i.eof_offset()));
+            let start = i.checkpoint();
+            return Err(E::from_input(i).add_context(i, &start, TOO_DEEP));
+        }
+        self.current.set(current + 1);
+        Ok(())
+    }
+
+    /// Goes back up the level entered with [`Depth::enter`].
+    fn leave(&self) {
+        self.current.set(self.current.get() - 1);
+    }
+
+    /// Parses an operand with `parser`, or `None` if there isn't one.
+    ///
+    /// The limit being hit inside `parser`, like in nested parentheses, is a
+    /// backtrack error that looks like a missing operand, so it's raised again
+    /// here where it was hit.
+    fn operand<I, O, E>(&self, i: &mut I, parser: &mut impl Parser<I, O, E>) -> Result<Option<O>, E>
+    where
+        I: Stream,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        self.too_deep_at.set(None);
+        let operand = opt(|i: &mut I| parser.parse_next(i)).parse_next(i)?;
+        match self.too_deep_at.get() {
+            Some(at) if operand.is_none() => {
+                i.next_slice(i.eof_offset().saturating_sub(at));
+                let start = i.checkpoint();
+                Err(E::from_input(i).add_context(i, &start, TOO_DEEP))
+            }
+            _ => {
+                self.too_deep_at.set(None);
+                Ok(operand)
+            }
+        }
+    }
+}
+
+/// The operator parsers, as used by both algorithms.
+///
+/// It's a trait so the type of each fold only has to be spelled out once.
+pub(crate) trait Grammar<I: Stream, O, E> {
+    type FoldPrefix: FnOnce(&mut I, O) -> Result<O, E>;
+    type FoldPostfix: FnOnce(&mut I, O) -> Result<O, E>;
+    type FoldInfix: FnOnce(&mut I, O, O) -> Result<O, E>;
+    type FoldTernary: FnOnce(&mut I, O, O, O) -> Result<O, E>;
+    type FoldDelimited: FnOnce(&mut I, O, Option<O>) -> Result<O, E>;
+    type CloseTernary: Parser<I, (), E>;
+    type CloseDelimited: Parser<I, (), E>;
+
+    /// The power of an expression between delimiters.
+    fn start_power(&self) -> Power;
+    /// Goes one level deeper into a nested expression, which fails if that's
+    /// deeper than allowed.
+    fn enter(&mut self, i: &I) -> Result<(), E>;
+    /// Goes back up the level entered with [`Grammar::enter`].
+    fn leave(&mut self);
+    /// Parses an operand, or `None` if there isn't one.
+    fn operand(&mut self, i: &mut I) -> Result<Option<O>, E>;
+    /// Parses an error node where an operand is missing, or fails with the
+    /// error of [`missing_operand`].
+    fn missing_operand(&mut self, i: &mut I, after: Option<StrContext>) -> Result<O, E>;
+    /// Goes on as if a closing delimiter was there, or fails with its `error`.
+    fn missing_delimiter(&mut self, i: &mut I, start: &I::Checkpoint, error: E) -> Result<(), E>;
+    fn prefix(&mut self, i: &mut I) -> Result<(Power, Self::FoldPrefix), E>;
+    fn postfix(&mut self, i: &mut I) -> Result<(Power, Self::FoldPostfix), E>;
+    fn infix(&mut self, i: &mut I) -> Result<(Assoc, Self::FoldInfix), E>;
+    fn ternary(&mut self, i: &mut I) -> Result<(Assoc, Self::CloseTernary, Self::FoldTernary), E>;
+    fn delimited(
+        &mut self,
+        i: &mut I,
+    ) -> Result<(Power, Self::CloseDelimited, Self::FoldDelimited), E>;
+}
+
+/// How a [`Grammar`] recovers from errors, where `()` never does.
+pub(crate) trait Recover<I: Stream, O, E> {
+    /// See [`Grammar::missing_operand`].
+    fn missing_operand(&mut self, i: &mut I, after: Option<StrContext>) -> Result<O, E>;
+    /// See [`Grammar::missing_delimiter`].
+    fn missing_delimiter(&mut self, i: &mut I, start: &I::Checkpoint, error: E) -> Result<(), E>;
+}
+
+impl<I, O, E> Recover<I, O, E> for ()
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    #[inline(always)]
+    fn missing_operand(&mut self, i: &mut I, after: Option<StrContext>) -> Result<O, E> {
+        Err(missing_operand(i, after))
+    }
+
+    #[inline(always)]
+    fn missing_delimiter(&mut self, _: &mut I, _: &I::Checkpoint, error: E) -> Result<(), E> {
+        Err(error)
+    }
+}
+
+/// The operator parsers of [`mixfix`], as a [`Grammar`].
+pub(crate) struct Parsers<
+    'd,
+    Operand,
+    Prefix,
+    Postfix,
+    Infix,
+    Ternary,
+    Delimited,
+    Folds,
+    Recovery = (),
+> {
+    start_power: Power,
+    depth: Option<&'d Depth>,
+    recovery: Recovery,
+    operand: Operand,
+    prefix: Prefix,
+    postfix: Postfix,
+    infix: Infix,
+    ternary: Ternary,
+    delimited: Delimited,
+    folds: PhantomData<fn() -> Folds>,
+}
+
+impl<'d, Operand, Prefix, Postfix, Infix, Ternary, Delimited, Folds>
+    Parsers<'d, Operand, Prefix, Postfix, Infix, Ternary, Delimited, Folds>
+{
+    pub(crate) fn new(
+        start_power: Power,
+        operand: Operand,
+        prefix: Prefix,
+        postfix: Postfix,
+        infix: Infix,
+        ternary: Ternary,
+        delimited: Delimited,
+    ) -> Self {
+        Self {
+            start_power,
+            depth: None,
+            recovery: (),
+            operand,
+            prefix,
+            postfix,
+            infix,
+            ternary,
+            delimited,
+            folds: PhantomData,
+        }
+    }
+}
+
+impl<'d, Operand, Prefix, Postfix, Infix, Ternary, Delimited, Folds, Recovery>
+    Parsers<'d, Operand, Prefix, Postfix, Infix, Ternary, Delimited, Folds, Recovery>
+{
+    /// Limits how deeply expressions nest.
+    pub(crate) fn with_depth(self, depth: &'d Depth) -> Self {
+        Self {
+            depth: Some(depth),
+            ..self
+        }
+    }
+
+    /// Recovers from errors with `recovery`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn with_recovery<R>(
+        self,
+        recovery: R,
+    ) -> Parsers<'d, Operand, Prefix, Postfix, Infix, Ternary, Delimited, Folds, R> {
+        Parsers {
+            start_power: self.start_power,
+            depth: self.depth,
+            recovery,
+            operand: self.operand,
+            prefix: self.prefix,
+            postfix: self.postfix,
+            infix: self.infix,
+            ternary: self.ternary,
+            delimited: self.delimited,
+            folds: PhantomData,
+        }
+    }
+}
+
+impl<
+    I,
+    O,
+    E,
+    ParseOperand,
+    ParsePrefix,
+    ParsePostfix,
+    ParseInfix,
+    ParseTernary,
+    ParseDelimited,
+    FoldPrefix,
+    FoldPostfix,
+    FoldInfix,
+    FoldTernary,
+    FoldDelimited,
+    CloseTernary,
+    CloseDelimited,
+    Recovery,
+> Grammar<I, O, E>
+    for Parsers<
+        '_,
+        ParseOperand,
+        ParsePrefix,
+        ParsePostfix,
+        ParseInfix,
+        ParseTernary,
+        ParseDelimited,
+        (
+            FoldPrefix,
+            FoldPostfix,
+            FoldInfix,
+            FoldTernary,
+            FoldDelimited,
+            CloseTernary,
+            CloseDelimited,
+        ),
+        Recovery,
+    >
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+    ParseOperand: Parser<I, O, E>,
+    ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+    ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+    ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+    ParseTernary: Parser<I, (Assoc, CloseTernary, FoldTernary), E>,
+    ParseDelimited: Parser<I, (Power, CloseDelimited, FoldDelimited), E>,
+    FoldPrefix: FnOnce(&mut I, O) -> Result<O, E>,
+    FoldPostfix: FnOnce(&mut I, O) -> Result<O, E>,
+    FoldInfix: FnOnce(&mut I, O, O) -> Result<O, E>,
+    FoldTernary: FnOnce(&mut I, O, O, O) -> Result<O, E>,
+    FoldDelimited: FnOnce(&mut I, O, Option<O>) -> Result<O, E>,
+    CloseTernary: Parser<I, (), E>,
+    CloseDelimited: Parser<I, (), E>,
+    Recovery: Recover<I, O, E>,
+{
+    type FoldPrefix = FoldPrefix;
+    type FoldPostfix = FoldPostfix;
+    type FoldInfix = FoldInfix;
+    type FoldTernary = FoldTernary;
+    type FoldDelimited = FoldDelimited;
+    type CloseTernary = CloseTernary;
+    type CloseDelimited = CloseDelimited;
+
+    #[inline(always)]
+    fn start_power(&self) -> Power {
+        self.start_power
+    }
+
+    #[inline(always)]
+    fn enter(&mut self, i: &I) -> Result<(), E> {
+        match self.depth {
+            Some(depth) => depth.enter(i),
+            None => Ok(()),
+        }
+    }
+
+    #[inline(always)]
+    fn leave(&mut self) {
+        if let Some(depth) = self.depth {
+            depth.leave();
+        }
+    }
+
+    #[inline(always)]
+    fn operand(&mut self, i: &mut I) -> Result<Option<O>, E> {
+        match self.depth {
+            Some(depth) => depth.operand(i, &mut self.operand),
+            None => opt(|i: &mut I| self.operand.parse_next(i)).parse_next(i),
+        }
+    }
+
+    #[inline(always)]
+    fn missing_operand(&mut self, i: &mut I, after: Option<StrContext>) -> Result<O, E> {
+        self.recovery.missing_operand(i, after)
+    }
+
+    #[inline(always)]
+    fn missing_delimiter(&mut self, i: &mut I, start: &I::Checkpoint, error: E) -> Result<(), E> {
+        self.recovery.missing_delimiter(i, start, error)
+    }
+
+    #[inline(always)]
+    fn prefix(&mut self, i: &mut I) -> Result<(Power, FoldPrefix), E> {
+        self.prefix.parse_next(i)
+    }
+
+    #[inline(always)]
+    fn postfix(&mut self, i: &mut I) -> Result<(Power, FoldPostfix), E> {
+        self.postfix.parse_next(i)
+    }
+
+    #[inline(always)]
+    fn infix(&mut self, i: &mut I) -> Result<(Assoc, FoldInfix), E> {
+        self.infix.parse_next(i)
+    }
+
+    #[inline(always)]
+    fn ternary(&mut self, i: &mut I) -> Result<(Assoc, CloseTernary, FoldTernary), E> {
+        self.ternary.parse_next(i)
+    }
+
+    #[inline(always)]
+    fn delimited(&mut self, i: &mut I) -> Result<(Power, CloseDelimited, FoldDelimited), E> {
+        self.delimited.parse_next(i)
+    }
+}
+
+/// The `ternary` parser of [`precedence`], which has no ternary operators.
+#[inline(always)]
+#[allow(clippy::type_complexity)]
+pub(crate) fn no_ternary<I, O, E>(
+    i: &mut I,
+) -> Result<(Assoc, CloseDelimiter<I, E>, TernaryFold<I, O, E>), E>
+where
+    I: Stream,
+    E: ParserError<I>,
+{
+    Err(E::from_input(i))
+}
+
+/// The `delimited` parser of [`precedence`], which has no delimited postfix operators.
+#[inline(always)]
+#[allow(clippy::type_complexity)]
+pub(crate) fn no_delimited<I, O, E>(
+    i: &mut I,
+) -> Result<(Power, CloseDelimiter<I, E>, DelimitedFold<I, O, E>), E>
+where
+    I: Stream,
+    E: ParserError<I>,
+{
+    Err(E::from_input(i))
+}
+
+/// Parses the delimited part of a postfix operator, after the opening delimiter.
+/// The expression is optional, like in `f()`, so the closing delimiter is tried
+/// first.
+pub(crate) fn delimited_operand<I, O, E, G, Close>(
+    i: &mut I,
+    grammar: &mut G,
+    close: &mut Close,
+    mut inner: impl FnMut(&mut I, &mut G) -> Result<O, E>,
+) -> Result<Option<O>, E>
+where
+    I: Stream,
+    G: Grammar<I, O, E>,
+    Close: Parser<I, (), E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    if opt(close.by_ref()).parse_next(i)?.is_some() {
+        return Ok(None);
+    }
+    let operand = inner(i, grammar)?;
+    close_delimiter(i, grammar, close, DELIMITED)?;
+    Ok(Some(operand))
+}
+
+/// Whether the input ended, so no operator can follow. A partial stream can
+/// still get more input, which the operator parsers ask for with `Incomplete`.
+#[inline(always)]
+pub(crate) fn at_end<I: Stream + StreamIsPartial>(i: &I) -> bool {
+    i.eof_offset() == 0 && !i.is_partial()
+}
+
+/// Parses the closing delimiter of a ternary or delimited postfix operator.
+pub(crate) fn close_delimiter<I, O, E, G, Close>(
+    i: &mut I,
+    grammar: &mut G,
+    close: &mut Close,
+    label: StrContext,
+) -> Result<(), E>
+where
+    I: Stream,
+    G: Grammar<I, O, E>,
+    Close: Parser<I, (), E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    let start = i.checkpoint();
+    match close.parse_next(i) {
+        Ok(()) => Ok(()),
+        // a partial stream can still get the delimiter
+        Err(e) if e.is_incomplete() => Err(e),
+        Err(e) => {
+            let e = e.add_context(i, &start, label);
+            grammar.missing_delimiter(i, &start, e)
+        }
+    }
+}
+
 // recursive function
-fn precedence_impl<I, ParseOperand, ParseInfix, ParsePrefix, ParsePostfix, Operand, E>(
+pub(crate) fn precedence_impl<I, O, E, G>(
     i: &mut I,
-    parse_operand: &mut ParseOperand,
-    prefix: &mut ParsePrefix,
-    postfix: &mut ParsePostfix,
-    infix: &mut ParseInfix,
+    grammar: &mut G,
     min_power: Power,
-) -> Result<Operand, E>
+    after: Option<StrContext>,
+) -> Result<O, E>
 where
     I: Stream + StreamIsPartial,
-    ParseOperand: Parser<I, Operand, E>,
-    ParseInfix: Parser<I, (Assoc, fn(&mut I, Operand, Operand) -> Result<Operand, E>), E>,
-    ParsePrefix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    ParsePostfix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    E: ParserError<I>,
+    G: Grammar<I, O, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    grammar.enter(i)?;
+    let result = parse_expression(i, grammar, min_power, after);
+    grammar.leave();
+    result
+}
+
+/// The body of [`precedence_impl`], one level deeper.
+fn parse_expression<I, O, E, G>(
+    i: &mut I,
+    grammar: &mut G,
+    min_power: Power,
+    after: Option<StrContext>,
+) -> Result<O, E>
+where
+    I: Stream + StreamIsPartial,
+    G: Grammar<I, O, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
 {
-    let operand = opt(parse_operand.by_ref()).parse_next(i)?;
+    let operand = grammar.operand(i)?;
     let mut operand = if let Some(operand) = operand {
         operand
     } else {
         // Prefix unary operators
         let len = i.eof_offset();
-        let (power, fold_prefix) = prefix.parse_next(i)?;
-        // infinite loop check: the parser must always consume
-        if i.eof_offset() == len {
-            return Err(E::assert(i, "`prefix` parsers must always consume"));
+        let prefix = opt(|i: &mut I| grammar.prefix(i)).parse_next(i)?;
+        if let Some((power, fold_prefix)) = prefix {
+            // infinite loop check: the parser must always consume
+            if i.eof_offset() == len {
+                return Err(E::assert(i, "`prefix` parsers must always consume"));
+            }
+            let operand = precedence_impl(i, grammar, power, Some(PREFIX))?;
+            fold_prefix(i, operand)?
+        } else {
+            grammar.missing_operand(i, after)?
         }
-        let operand = precedence_impl(i, parse_operand, prefix, postfix, infix, power)?;
-        fold_prefix(i, operand)?
     };
 
-    // A variable to stop the `'parse` loop when `Assoc::Neither` with the same
+    // A variable to fail the `'parse` loop when `Assoc::Neither` with the same
     // precedence is encountered e.g. `a == b == c`. `Assoc::Neither` has similar
-    // associativity rules as `Assoc::Left`, but we stop parsing when the next operator
-    // is the same as the current one.
+    // associativity rules as `Assoc::Left`, but it's an error when the next operator
+    // has the same power as the current one.
     let mut prev_op_is_neither = None;
-    'parse: while i.eof_offset() > 0 {
+    'parse: while !at_end(i) {
         // Postfix unary operators
         let start = i.checkpoint();
-        if let Some((power, fold_postfix)) = opt(postfix.by_ref()).parse_next(i)? {
+        let postfix = opt(|i: &mut I| grammar.postfix(i)).parse_next(i)?;
+        if let Some((power, fold_postfix)) = postfix {
             // control precedence over the prefix e.g.:
             // `--(i++)` or `(--i)++`
             if power < min_power {
@@ -96,34 +882,71 @@ where
                 break 'parse;
             }
             operand = fold_postfix(i, operand)?;
+            // it applies to the whole operand, like `(a == b)!`, so the next
+            // operator doesn't chain with the previous one
+            prev_op_is_neither = None;
+
+            continue 'parse;
+        }
+
+        // Delimited postfix operators, e.g. `f(a)` or `a[i]`
+        let delimited = opt(|i: &mut I| grammar.delimited(i)).parse_next(i)?;
+        if let Some((power, mut close, fold_delimited)) = delimited {
+            if power < min_power {
+                i.reset(&start);
+                break 'parse;
+            }
+            let start_power = grammar.start_power();
+            let inner = delimited_operand(i, grammar, &mut close, |i: &mut I, grammar: &mut G| {
+                precedence_impl(i, grammar, start_power, Some(DELIMITED))
+            })?;
+            operand = fold_delimited(i, operand, inner)?;
+            prev_op_is_neither = None;
 
             continue 'parse;
         }
 
         // Infix binary operators
-        let start = i.checkpoint();
-        let parse_result = opt(infix.by_ref()).parse_next(i)?;
-        if let Some((assoc, fold_infix)) = parse_result {
-            let mut is_neither = None;
-            let (lpower, rpower) = match assoc {
-                Assoc::Right(p) => (p, p - 1),
-                Assoc::Left(p) => (p, p + 1),
-                Assoc::Neither(p) => {
-                    is_neither = Some(p);
-                    (p, p + 1)
-                }
-            };
-            if lpower < min_power || prev_op_is_neither.is_some_and(|p| lpower == p) {
+        let infix = opt(|i: &mut I| grammar.infix(i)).parse_next(i)?;
+        if let Some((assoc, fold_infix)) = infix {
+            let (lpower, rpower) = assoc.powers();
+            if lpower < min_power {
                 i.reset(&start);
                 break 'parse;
             }
-            prev_op_is_neither = is_neither;
-            let rhs = precedence_impl(i, parse_operand, prefix, postfix, infix, rpower)?;
+            if prev_op_is_neither.is_some_and(|p| lpower == p) {
+                i.reset(&start);
+                return Err(chained_neither(i));
+            }
+            prev_op_is_neither = assoc.neither();
+            let rhs = precedence_impl(i, grammar, rpower, Some(INFIX))?;
             operand = fold_infix(i, operand, rhs)?;
 
             continue 'parse;
         }
 
+        // Ternary operators, e.g. `a ? b : c`
+        let ternary = opt(|i: &mut I| grammar.ternary(i)).parse_next(i)?;
+        if let Some((assoc, mut close, fold_ternary)) = ternary {
+            let (lpower, rpower) = assoc.powers();
+            if lpower < min_power {
+                i.reset(&start);
+                break 'parse;
+            }
+            if prev_op_is_neither.is_some_and(|p| lpower == p) {
+                i.reset(&start);
+                return Err(chained_neither(i));
+            }
+            prev_op_is_neither = assoc.neither();
+            let start_power = grammar.start_power();
+            let middle = precedence_impl(i, grammar, start_power, Some(TERNARY))?;
+            close_delimiter(i, grammar, &mut close, TERNARY)?;
+            let rhs = precedence_impl(i, grammar, rpower, Some(TERNARY))?;
+            operand = fold_ternary(i, operand, middle, rhs)?;
+
+            continue 'parse;
+        }
+
         break 'parse;
     }
 
@@ -161,27 +984,28 @@ mod tests {
                 trace(
                     "prefix",
                     dispatch! {any;
-                        '+' => empty.value((9, (|_: &mut _, a| Ok(a)) as _)),
-                        '-' => empty.value((9, (|_: &mut _, a: i32| Ok(-a)) as _)),
+                        '+' => empty.value((9, (|_: &mut _, a| Ok(a)) as UnaryFold<_, _, _>)),
+                        '-' => empty.value((9, (|_: &mut _, a: i32| Ok(-a)) as UnaryFold<_, _, _>)),
                         _ => fail
                     },
                 ),
                 trace(
                     "postfix",
                     dispatch! {any;
-                        '!' => empty.value((9, (|_: &mut _, a| {Ok(factorial(a))}) as _)),
+                        '!' => empty.value((9, (|_: &mut _, a| {Ok(factorial(a))}) as UnaryFold<_, _, _>)),
                         _ => fail
                     },
                 ),
                 trace(
                     "infix",
                     dispatch! {any;
-                       '+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as _  )),
-                       '-' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a - b)) as _)),
-                       '*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as _)),
-                       '/' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a / b)) as _)),
-                       '%' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a % b)) as _)),
-                       '^' => empty.value((Assoc::Right(9), (|_: &mut _, a, b| Ok(a ^ b)) as _)),
+                       '+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>  )),
+                       '-' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a - b)) as BinaryFold<_, _, _>)),
+                       '*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as BinaryFold<_, _, _>)),
+                       '/' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a / b)) as BinaryFold<_, _, _>)),
+                       '%' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a % b)) as BinaryFold<_, _, _>)),
+                       '^' => empty.value((Assoc::Right(9), (|_: &mut _, a, b| Ok(a ^ b)) as BinaryFold<_, _, _>)),
+                       '=' => empty.value((Assoc::Neither(3), (|_: &mut _, a, b| Ok((a == b) as i32)) as BinaryFold<_, _, _>)),
                        _ => fail
                     },
                 ),
@@ -203,4 +1027,178 @@ mod tests {
         assert_eq!(parser().parse("2 + 4!"), Ok(26));
         assert_eq!(parser().parse("-2 + 2"), Ok(0));
     }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_errors() {
+        use alloc::string::ToString as _;
+
+        let err = |input| {
+            let e = parser().parse(input).unwrap_err();
+            (e.offset(), e.inner().to_string())
+        };
+        let message = |offset, message: &str| (offset, message.to_string());
+
+        // missing operands
+        assert_eq!(err(""), message(0, "expected operand"));
+        assert_eq!(
+            err("2*"),
+            message(2, "invalid infix operator\nexpected operand")
+        );
+        assert_eq!(
+            err("2**3"),
+            message(2, "invalid infix operator\nexpected operand")
+        );
+
+        // dangling prefix operators
+        assert_eq!(
+            err("-"),
+            message(1, "invalid prefix operator\nexpected operand")
+        );
+        assert_eq!(
+            err("2*--"),
+            message(4, "invalid prefix operator\nexpected operand")
+        );
+
+        // chained non-associative operators
+        let chained = "invalid chained non-associative operator";
+        assert_eq!(err("1=1=1"), message(3, chained));
+        assert_eq!(err("1=2-1=1"), message(5, chained));
+        assert_eq!(parser().parse("(1=1)=1"), Ok(1));
+        assert_eq!(parser().parse("1=1+0"), Ok(1));
+    }
+
+    #[test]
+    fn test_mixfix() {
+        let mut parser = mixfix(
+            0,
+            digit1.parse_to::<i32>(),
+            fail::<_, (Power, UnaryFold<_, _, _>), _>,
+            fail::<_, (Power, UnaryFold<_, _, _>), _>,
+            dispatch! {any;
+                '+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>)),
+                '*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as BinaryFold<_, _, _>)),
+                _ => fail
+            },
+            '?'.value((
+                Assoc::Right(3),
+                (|i: &mut &str| ':'.void().parse_next(i)) as CloseDelimiter<_, ContextError>,
+                (|_: &mut _, a, b, c| Ok(if a != 0 { b } else { c })) as TernaryFold<_, _, _>,
+            )),
+            // `f(x)` is `f * x`, and `f()` is `f`
+            '('.value((
+                9,
+                (|i: &mut &str| ')'.void().parse_next(i)) as CloseDelimiter<_, _>,
+                (|_: &mut _, a, b: Option<i32>| Ok(a * b.unwrap_or(1))) as DelimitedFold<_, _, _>,
+            )),
+        );
+        assert_eq!(parser.parse("1?2:3"), Ok(2));
+        assert_eq!(parser.parse("0?2:3+1"), Ok(4));
+        assert_eq!(parser.parse("0?1:0?2:3"), Ok(3));
+        assert_eq!(parser.parse("1?0?7:8:9"), Ok(8));
+        assert_eq!(parser.parse("1+1?2:3"), Ok(2));
+        assert_eq!(parser.parse("2(3)"), Ok(6));
+        assert_eq!(parser.parse("2()"), Ok(2));
+        assert_eq!(parser.parse("2(1+2)(2)"), Ok(12));
+        assert_eq!(parser.parse("1+2(0?1:2)"), Ok(5));
+        assert!(parser.parse("1?2").is_err());
+        assert!(parser.parse("2(3").is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        fn expr<'i>(depth: &Depth) -> impl Parser<&'i str, i32, ContextError> {
+            move |i: &mut &'i str| {
+                depth
+                    .mixfix(
+                        0,
+                        dispatch! {peek(any);
+                            '(' => depth.nest(delimited('(', expr(depth), ')')),
+                            _ => digit1.parse_to::<i32>()
+                        },
+                        '-'.value((9, (|_: &mut _, a: i32| Ok(-a)) as UnaryFold<_, _, _>)),
+                        fail::<_, (Power, UnaryFold<_, _, _>), _>,
+                        dispatch! {any;
+                            '+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>)),
+                            '^' => empty.value((Assoc::Right(7), (|_: &mut _, a, b| Ok(a ^ b)) as BinaryFold<_, _, _>)),
+                            _ => fail
+                        },
+                        '?'.value((
+                            Assoc::Right(3),
+                            (|i: &mut &str| ':'.void().parse_next(i)) as CloseDelimiter<_, _>,
+                            (|_: &mut _, a, b, c| Ok(if a != 0 { b } else { c })) as TernaryFold<_, _, _>,
+                        )),
+                        fail::<_, (Power, CloseDelimiter<_, _>, DelimitedFold<_, _, _>), _>,
+                    )
+                    .parse_next(i)
+            }
+        }
+        fn parse(max: usize, input: &str) -> Result<i32, (usize, ContextError)> {
+            let depth = Depth::new(max);
+            let result = expr(&depth)
+                .parse(input)
+                .map_err(|e| (e.offset(), e.into_inner()));
+            assert_eq!(depth.current(), 0, "{input}");
+            result
+        }
+        let too_deep = |offset| {
+            let start = &"";
+            Err((
+                offset,
+                ContextError::new().add_context(&"", &start.checkpoint(), TOO_DEEP),
+            ))
+        };
+
+        // the expression and the operand of each operator are one level deeper
+        assert_eq!(parse(1, "1"), Ok(1));
+        assert_eq!(parse(1, "1+2"), too_deep(2));
+        assert_eq!(parse(2, "1+2+3"), Ok(6));
+        assert_eq!(parse(1, "-1"), too_deep(1));
+        assert_eq!(parse(2, "-1"), Ok(-1));
+        assert_eq!(parse(2, "1^1^1"), too_deep(4));
+        assert_eq!(parse(3, "1^1^1"), Ok(1));
+        assert_eq!(parse(2, "0?1:2"), Ok(2));
+
+        // and so is each nested parenthesis, where the error is raised again
+        // instead of being taken for a missing operand
+        assert_eq!(parse(4, "((1))"), too_deep(2));
+        assert_eq!(parse(4, "1+((1))"), too_deep(3));
+        assert_eq!(parse(5, "((1))"), Ok(1));
+        assert_eq!(parse(5, "(1)+(2)"), Ok(3));
+        assert_eq!(parse(64, &"(".repeat(100_000)), too_deep(32));
+        assert!(parse(64, &"-".repeat(100_000)).is_err());
+        assert!(parse(64, &"1^".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_capturing_folds() {
+        use core::cell::Cell;
+
+        // every fold captures the counter, and the operator to apply
+        fn fold(
+            folds: &Cell<usize>,
+            op: char,
+        ) -> impl Fn(&mut &str, i32, i32) -> Result<i32> + Clone {
+            move |_, a, b| {
+                folds.set(folds.get() + 1);
+                Ok(if op == '+' { a + b } else { a * b })
+            }
+        }
+
+        let folds = &Cell::new(0);
+        let result = precedence(
+            0,
+            digit1.parse_to::<i32>(),
+            fail::<_, (Power, UnaryFold<_, _, _>), _>,
+            fail::<_, (Power, UnaryFold<_, _, _>), _>,
+            dispatch! {any;
+                '+' => empty.value((Assoc::Left(5), fold(folds, '+'))),
+                '*' => empty.value((Assoc::Left(7), fold(folds, '*'))),
+                _ => fail
+            },
+        )
+        .parse("1+2*3+4");
+        assert_eq!(result, Ok(11));
+        assert_eq!(folds.get(), 3);
+    }
 }
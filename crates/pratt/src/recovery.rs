@@ -0,0 +1,439 @@
+//! Error recovery, for tools like editors which want every error in an input
+//! like `1 + * 2 + (3 -`, and a tree of the rest of it.
+//!
+//! A [`Recovery`] is used like a [`Depth`]. The parsers made with
+//! [`Recovery::precedence`] or [`Recovery::mixfix`] don't fail where an
+//! operand or a closing delimiter is missing. They record the error they would
+//! have failed with, and go on with an error node parsed by the `error` parser,
+//! or as if the delimiter was there. Parentheses are parsed by the operand
+//! parser, so [`Recovery::close`] does the same for their closing delimiter.
+//!
+//! ```
+//! use pratt::precedence::{Assoc, BinaryFold, Power, UnaryFold};
+//! use pratt::recovery::Recovery;
+//! use winnow::combinator::{alt, delimited, empty, fail};
+//! use winnow::{Parser, ascii::digit1, error::ContextError};
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Expr {
+//!     Value(i64),
+//!     Add(Box<Expr>, Box<Expr>),
+//!     Error,
+//! }
+//!
+//! fn expr<'i, 'r>(
+//!     recovery: &'r Recovery<'_, &'i str, Expr, ContextError>,
+//! ) -> impl Parser<&'i str, Expr, ContextError> + 'r {
+//!     move |i: &mut &'i str| {
+//!         recovery
+//!             .precedence(
+//!                 0,
+//!                 alt((
+//!                     digit1.parse_to().map(Expr::Value),
+//!                     delimited('(', expr(recovery), recovery.close(')')),
+//!                 )),
+//!                 fail::<_, (Power, UnaryFold<_, _, _>), _>,
+//!                 fail::<_, (Power, UnaryFold<_, _, _>), _>,
+//!                 '+'.value((
+//!                     Assoc::Left(5),
+//!                     (|_: &mut _, a, b| Ok(Expr::Add(Box::new(a), Box::new(b)))) as BinaryFold<_, _, _>,
+//!                 )),
+//!             )
+//!             .parse_next(i)
+//!     }
+//! }
+//!
+//! let input = "1+(+2";
+//! let recovery = Recovery::new(&input, empty.map(|()| Expr::Error));
+//! let tree = expr(&recovery).parse(input).unwrap();
+//! assert_eq!(
+//!     tree,
+//!     Expr::Add(
+//!         Box::new(Expr::Value(1)),
+//!         Box::new(Expr::Add(Box::new(Expr::Error), Box::new(Expr::Value(2)))),
+//!     )
+//! );
+//! // the offsets of the missing operand and the missing `)`
+//! let offsets: Vec<_> = recovery.into_errors().into_iter().map(|(offset, _)| offset).collect();
+//! assert_eq!(offsets, [3, 5]);
+//! ```
+//!
+//! The errors of an expression which fails and backtracks are dropped again,
+//! since its tree is too. [`Recovery::branch`] does the same for a parser
+//! around it, like a branch of an `alt` which fails after the expression.
+//!
+//! [`Depth`]: crate::precedence::Depth
+
+use core::cell::RefCell;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use winnow::{
+    Parser, Result,
+    combinator::trace,
+    error::{AddContext, ParserError, StrContext, StrContextValue},
+    stream::{Offset, Stream, StreamIsPartial},
+};
+
+use crate::precedence::{
+    Assoc, Parsers, Power, Recover, missing_operand, no_delimited, no_ternary, pratt,
+};
+
+/// The context of a missing closing delimiter, like the `)` of `(1 + 2`.
+const CLOSE: StrContext = StrContext::Expected(StrContextValue::Description("closing delimiter"));
+
+/// Records the errors of an expression instead of failing, see the [module docs](self).
+pub struct Recovery<'p, I: Stream, O, E> {
+    start: I::Checkpoint,
+    error: RefCell<Box<dyn Parser<I, O, E> + 'p>>,
+    errors: RefCell<Vec<(usize, E)>>,
+}
+
+impl<'p, I, O, E> Recovery<'p, I, O, E>
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    /// Recovers from the errors in `input`, where `error` parses the error
+    /// node of a missing operand. It can also skip what's there instead, or
+    /// fail to give up.
+    pub fn new(input: &I, error: impl Parser<I, O, E> + 'p) -> Self {
+        Self {
+            start: input.checkpoint(),
+            error: RefCell::new(Box::new(error)),
+            errors: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The errors recovered from so far, with their offset in the input.
+    pub fn into_errors(self) -> Vec<(usize, E)> {
+        self.errors.into_inner()
+    }
+
+    /// How many errors were recovered from so far.
+    pub fn error_count(&self) -> usize {
+        self.errors.borrow().len()
+    }
+
+    /// Parses a closing delimiter with `parser`, like a `)`, or records its
+    /// error and goes on as if it was there.
+    pub fn close<T>(&self, mut parser: impl Parser<I, T, E>) -> impl Parser<I, Option<T>, E> {
+        trace("close", move |i: &mut I| {
+            let start = i.checkpoint();
+            match parser.parse_next(i) {
+                Ok(o) => Ok(Some(o)),
+                // a partial stream can still get the delimiter
+                Err(e) if e.is_incomplete() => Err(e),
+                Err(e) => {
+                    let e = e.add_context(i, &start, CLOSE);
+                    i.reset(&start);
+                    self.record(&start, e);
+                    Ok(None)
+                }
+            }
+        })
+    }
+
+    /// Parses with `parser`, and drops the errors recorded inside it if it
+    /// fails and backtracks, like a branch of an `alt`.
+    pub fn branch<T>(&self, mut parser: impl Parser<I, T, E>) -> impl Parser<I, T, E> {
+        trace("branch", move |i: &mut I| {
+            let count = self.error_count();
+            let result = parser.parse_next(i);
+            if result.as_ref().is_err_and(|e| e.is_backtrack()) {
+                self.errors.borrow_mut().truncate(count);
+            }
+            result
+        })
+    }
+
+    /// Like [`crate::precedence::precedence`], which recovers from errors.
+    #[inline(always)]
+    pub fn precedence<
+        ParseOperand,
+        ParseInfix,
+        ParsePrefix,
+        ParsePostfix,
+        FoldInfix,
+        FoldPrefix,
+        FoldPostfix,
+    >(
+        &self,
+        start_power: Power,
+        operand: ParseOperand,
+        prefix: ParsePrefix,
+        postfix: ParsePostfix,
+        infix: ParseInfix,
+    ) -> impl Parser<I, O, E>
+    where
+        I: StreamIsPartial,
+        ParseOperand: Parser<I, O, E>,
+        ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+        ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+        ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+        FoldInfix: FnOnce(&mut I, O, O) -> Result<O, E>,
+        FoldPrefix: FnOnce(&mut I, O) -> Result<O, E>,
+        FoldPostfix: FnOnce(&mut I, O) -> Result<O, E>,
+    {
+        self.mixfix(
+            start_power,
+            operand,
+            prefix,
+            postfix,
+            infix,
+            no_ternary,
+            no_delimited,
+        )
+    }
+
+    /// Like [`crate::precedence::mixfix`], which recovers from errors.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn mixfix<
+        ParseOperand,
+        ParseInfix,
+        ParsePrefix,
+        ParsePostfix,
+        ParseTernary,
+        ParseDelimited,
+        FoldInfix,
+        FoldPrefix,
+        FoldPostfix,
+        FoldTernary,
+        FoldDelimited,
+        CloseTernary,
+        CloseDelimited,
+    >(
+        &self,
+        start_power: Power,
+        operand: ParseOperand,
+        prefix: ParsePrefix,
+        postfix: ParsePostfix,
+        infix: ParseInfix,
+        ternary: ParseTernary,
+        delimited: ParseDelimited,
+    ) -> impl Parser<I, O, E>
+    where
+        I: StreamIsPartial,
+        ParseOperand: Parser<I, O, E>,
+        ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+        ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+        ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+        ParseTernary: Parser<I, (Assoc, CloseTernary, FoldTernary), E>,
+        ParseDelimited: Parser<I, (Power, CloseDelimited, FoldDelimited), E>,
+        FoldInfix: FnOnce(&mut I, O, O) -> Result<O, E>,
+        FoldPrefix: FnOnce(&mut I, O) -> Result<O, E>,
+        FoldPostfix: FnOnce(&mut I, O) -> Result<O, E>,
+        FoldTernary: FnOnce(&mut I, O, O, O) -> Result<O, E>,
+        FoldDelimited: FnOnce(&mut I, O, Option<O>) -> Result<O, E>,
+        CloseTernary: Parser<I, (), E>,
+        CloseDelimited: Parser<I, (), E>,
+    {
+        self.branch(pratt(
+            Parsers::new(
+                start_power,
+                operand,
+                prefix,
+                postfix,
+                infix,
+                ternary,
+                delimited,
+            )
+            .with_recovery(self),
+        ))
+    }
+
+    /// Records an error at `at`.
+    fn record(&self, at: &I::Checkpoint, error: E) {
+        let offset = at.offset_from(&self.start);
+        self.errors.borrow_mut().push((offset, error));
+    }
+}
+
+impl<I, O, E> Recover<I, O, E> for &Recovery<'_, I, O, E>
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    fn missing_operand(&mut self, i: &mut I, after: Option<StrContext>) -> Result<O, E> {
+        let error = missing_operand(i, after);
+        let start = i.checkpoint();
+        match self.error.borrow_mut().parse_next(i) {
+            Ok(node) => {
+                self.record(&start, error);
+                Ok(node)
+            }
+            Err(_) => {
+                i.reset(&start);
+                Err(error)
+            }
+        }
+    }
+
+    fn missing_delimiter(&mut self, i: &mut I, start: &I::Checkpoint, error: E) -> Result<(), E> {
+        i.reset(start);
+        self.record(start, error);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use winnow::ascii::digit1;
+    use winnow::combinator::{alt, delimited, dispatch, empty, fail, terminated};
+    use winnow::error::ContextError;
+    use winnow::token::{any, rest};
+
+    use super::*;
+    use crate::precedence::{BinaryFold, CloseDelimiter, DelimitedFold, TernaryFold, UnaryFold};
+
+    type Errors = Vec<(usize, String)>;
+
+    /// Writes out the tree, where `_` is an error node.
+    fn expr<'i, 'r>(
+        recovery: &'r Recovery<'_, &'i str, String, ContextError>,
+    ) -> impl Parser<&'i str, String, ContextError> + 'r {
+        move |i: &mut &'i str| {
+            recovery
+                .mixfix(
+                    0,
+                    alt((
+                        digit1.map(String::from),
+                        delimited('(', expr(recovery), recovery.close(')'))
+                            .map(|e| format!("({e})")),
+                    )),
+                    '-'.value((9, (|_: &mut _, a| Ok(format!("-{a}"))) as UnaryFold<_, _, _>)),
+                    fail::<_, (Power, UnaryFold<_, _, _>), _>,
+                    dispatch! {any;
+                        '+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(format!("{a}+{b}"))) as BinaryFold<_, _, _>)),
+                        '*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(format!("{a}*{b}"))) as BinaryFold<_, _, _>)),
+                        _ => fail
+                    },
+                    '?'.value((
+                        Assoc::Right(3),
+                        (|i: &mut &str| ':'.void().parse_next(i)) as CloseDelimiter<_, _>,
+                        (|_: &mut _, a, b, c| Ok(format!("{a}?{b}:{c}"))) as TernaryFold<_, _, _>,
+                    )),
+                    '['.value((
+                        9,
+                        (|i: &mut &str| ']'.void().parse_next(i)) as CloseDelimiter<_, _>,
+                        (|_: &mut _, a, b: Option<String>| Ok(format!("{a}[{}]", b.unwrap_or_default())))
+                            as DelimitedFold<_, _, _>,
+                    )),
+                )
+                .parse_next(i)
+        }
+    }
+
+    fn parse(input: &str) -> (Result<String, usize>, Errors) {
+        let recovery = Recovery::new(&input, empty.value(String::from("_")));
+        let result = expr(&recovery).parse(input).map_err(|e| e.offset());
+        let errors = recovery
+            .into_errors()
+            .into_iter()
+            .map(|(offset, e)| (offset, e.to_string()))
+            .collect();
+        (result, errors)
+    }
+
+    fn errors(errors: &[(usize, &str)]) -> Errors {
+        errors.iter().map(|&(o, e)| (o, e.to_string())).collect()
+    }
+
+    #[test]
+    fn test_recovery() {
+        assert_eq!(parse("1+2*3"), (Ok("1+2*3".into()), errors(&[])));
+        assert_eq!(
+            parse("1+*2+(3*"),
+            (
+                Ok("1+_*2+(3*_)".into()),
+                errors(&[
+                    (2, "invalid infix operator\nexpected operand"),
+                    (8, "invalid infix operator\nexpected operand"),
+                    (8, "expected closing delimiter"),
+                ])
+            )
+        );
+        assert_eq!(
+            parse("-"),
+            (
+                Ok("-_".into()),
+                errors(&[(1, "invalid prefix operator\nexpected operand")])
+            )
+        );
+        assert_eq!(
+            parse("1?2"),
+            (
+                Ok("1?2:_".into()),
+                errors(&[
+                    (3, "invalid ternary operator"),
+                    (3, "invalid ternary operator\nexpected operand"),
+                ])
+            )
+        );
+        assert_eq!(
+            parse("4[2+"),
+            (
+                Ok("4[2+_]".into()),
+                errors(&[
+                    (4, "invalid infix operator\nexpected operand"),
+                    (4, "invalid delimited postfix operator"),
+                ])
+            )
+        );
+        assert_eq!(
+            parse(""),
+            (Ok("_".into()), errors(&[(0, "expected operand")]))
+        );
+    }
+
+    #[test]
+    fn test_unrecoverable() {
+        // the input after the expression isn't part of it
+        let (result, errors) = parse("1+2)");
+        assert_eq!(result, Err(3));
+        assert_eq!(errors, []);
+
+        // the error node can give up
+        let input = "1+";
+        let recovery = Recovery::new(&input, fail::<_, String, _>);
+        assert!(expr(&recovery).parse(input).is_err());
+        assert_eq!(recovery.error_count(), 0);
+    }
+
+    #[test]
+    fn test_backtrack() {
+        // the error node gives up at the end, after the `_` was recovered from
+        let input = "1+_*(2+";
+        let recovery = Recovery::new(&input, '_'.value(String::from("_")));
+        assert!(expr(&recovery).parse(input).is_err());
+        assert_eq!(recovery.error_count(), 0);
+
+        // a statement needs its `;`, or it's taken as it is
+        let statement = |input: &str| {
+            let recovery = Recovery::new(&input, empty.value(String::from("_")));
+            let result = alt((
+                recovery.branch(terminated(expr(&recovery), ';')),
+                rest.map(String::from),
+            ))
+            .parse(input)
+            .unwrap();
+            let errors: Errors = recovery
+                .into_errors()
+                .into_iter()
+                .map(|(offset, e)| (offset, e.to_string()))
+                .collect();
+            (result, errors)
+        };
+        assert_eq!(
+            statement("1+;"),
+            (
+                "1+_".into(),
+                errors(&[(2, "invalid infix operator\nexpected operand")])
+            )
+        );
+        assert_eq!(statement("1+"), ("1+".into(), errors(&[])));
+    }
+}
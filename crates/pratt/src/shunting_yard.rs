@@ -1,230 +1,355 @@
 use winnow::combinator::opt;
-use winnow::error::ParserError;
+use winnow::error::{AddContext, ParserError, StrContext};
 use winnow::stream::{Stream, StreamIsPartial};
 use winnow::{Parser, Result};
 
 use super::precedence::Assoc;
 use super::precedence::Power;
+use super::precedence::{
+    DELIMITED, Grammar, INFIX, PREFIX, Parsers, TERNARY, at_end, chained_neither, close_delimiter,
+    delimited_operand, missing_operand, no_delimited, no_ternary,
+};
 use winnow::combinator::trace;
 
 use alloc::vec::Vec;
 
-pub fn precedence<I, ParseOperand, ParseInfix, ParsePrefix, ParsePostfix, Operand, E>(
+pub fn precedence<
+    I,
+    ParseOperand,
+    ParseInfix,
+    ParsePrefix,
+    ParsePostfix,
+    FoldInfix,
+    FoldPrefix,
+    FoldPostfix,
+    Operand,
+    E,
+>(
     start_precedence: Power,
-    mut operand: ParseOperand,
-    mut prefix: ParsePrefix,
-    mut postfix: ParsePostfix,
-    mut infix: ParseInfix,
+    operand: ParseOperand,
+    prefix: ParsePrefix,
+    postfix: ParsePostfix,
+    infix: ParseInfix,
 ) -> impl Parser<I, Operand, E>
 where
     I: Stream + StreamIsPartial,
     ParseOperand: Parser<I, Operand, E>,
-    ParseInfix: Parser<I, (Assoc, fn(&mut I, Operand, Operand) -> Result<Operand, E>), E>,
-    ParsePrefix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    ParsePostfix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    E: ParserError<I>,
+    ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+    ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+    ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldPostfix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
 {
+    mixfix(
+        start_precedence,
+        operand,
+        prefix,
+        postfix,
+        infix,
+        no_ternary,
+        no_delimited,
+    )
+}
+
+/// Like [`crate::precedence::mixfix`], with the Shunting Yard algorithm.
+#[allow(clippy::too_many_arguments)]
+pub fn mixfix<
+    I,
+    ParseOperand,
+    ParseInfix,
+    ParsePrefix,
+    ParsePostfix,
+    ParseTernary,
+    ParseDelimited,
+    FoldInfix,
+    FoldPrefix,
+    FoldPostfix,
+    FoldTernary,
+    FoldDelimited,
+    CloseTernary,
+    CloseDelimited,
+    Operand,
+    E,
+>(
+    start_precedence: Power,
+    operand: ParseOperand,
+    prefix: ParsePrefix,
+    postfix: ParsePostfix,
+    infix: ParseInfix,
+    ternary: ParseTernary,
+    delimited: ParseDelimited,
+) -> impl Parser<I, Operand, E>
+where
+    I: Stream + StreamIsPartial,
+    ParseOperand: Parser<I, Operand, E>,
+    ParseInfix: Parser<I, (Assoc, FoldInfix), E>,
+    ParsePrefix: Parser<I, (Power, FoldPrefix), E>,
+    ParsePostfix: Parser<I, (Power, FoldPostfix), E>,
+    ParseTernary: Parser<I, (Assoc, CloseTernary, FoldTernary), E>,
+    ParseDelimited: Parser<I, (Power, CloseDelimited, FoldDelimited), E>,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldPostfix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldTernary: FnOnce(&mut I, Operand, Operand, Operand) -> Result<Operand, E>,
+    FoldDelimited: FnOnce(&mut I, Operand, Option<Operand>) -> Result<Operand, E>,
+    CloseTernary: Parser<I, (), E>,
+    CloseDelimited: Parser<I, (), E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    let mut grammar = Parsers::new(
+        start_precedence,
+        operand,
+        prefix,
+        postfix,
+        infix,
+        ternary,
+        delimited,
+    );
     trace("precedence", move |i: &mut I| {
-        let result = shunting_yard(
-            start_precedence,
-            i,
-            operand.by_ref(),
-            prefix.by_ref(),
-            postfix.by_ref(),
-            infix.by_ref(),
-        )?;
+        let result = shunting_yard(start_precedence, i, &mut grammar, None)?;
         Ok(result)
     })
 }
 
-fn shunting_yard<I, ParseOperand, ParseInfix, ParsePrefix, ParsePostfix, Operand, E>(
+pub(crate) fn shunting_yard<I, O, E, G>(
     start_precedence: Power,
     i: &mut I,
-    mut operand: ParseOperand,
-    mut prefix: ParsePrefix,
-    mut postfix: ParsePostfix,
-    mut infix: ParseInfix,
-) -> Result<Operand, E>
+    grammar: &mut G,
+    mut after: Option<StrContext>,
+) -> Result<O, E>
 where
     I: Stream + StreamIsPartial,
-    ParseOperand: Parser<I, Operand, E>,
-    ParseInfix: Parser<I, (Assoc, fn(&mut I, Operand, Operand) -> Result<Operand, E>), E>,
-    ParsePrefix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    ParsePostfix: Parser<I, (Power, fn(&mut I, Operand) -> Result<Operand, E>), E>,
-    E: ParserError<I>,
+    G: Grammar<I, O, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
 {
     // a stack for computing the result
-    let mut value_stack = Vec::<Operand>::new();
-    let mut operator_stack = Vec::<Operator<I, Operand, E>>::new();
+    let mut value_stack = Vec::<O>::new();
+    let mut operator_stack = Vec::<Operator<G::FoldPrefix, G::FoldInfix, G::FoldTernary>>::new();
 
-    let mut current_is_neither = None;
+    // `after` is what a missing operand comes right after, if anything
     'parse: loop {
         // Prefix unary operators
-        while let Some((lpower, op)) = opt(prefix.by_ref()).parse_next(i)? {
+        while let Some((lpower, op)) = opt(|i: &mut I| grammar.prefix(i)).parse_next(i)? {
             // prefix operators never trigger the evaluation of pending operators
             operator_stack.push(Operator::Unary(lpower, op));
+            after = Some(PREFIX);
         }
 
         // Operand
-        if let Some(operand) = opt(operand.by_ref()).parse_next(i)? {
-            value_stack.push(operand);
-        } else {
-            // error missing operand
-            return Err(E::from_input(i));
-        }
+        let operand = grammar.operand(i)?;
+        let operand = match operand {
+            Some(operand) => operand,
+            None => grammar.missing_operand(i, after)?,
+        };
+        value_stack.push(operand);
 
-        if i.eof_offset() == 0 {
+        if at_end(i) {
             break 'parse;
         }
 
-        // Postfix unary operators
-        while let Some((lpower, op)) = opt(postfix.by_ref()).parse_next(i)? {
-            while operator_stack.last().is_some_and(|op| {
-                let rpower = op.right_power();
-                lpower < rpower
-            }) {
-                evaluate(
-                    i,
-                    &mut value_stack,
-                    operator_stack.pop().expect("already checked"),
-                )?;
+        // Operators binding looser than `start_precedence` end the expression,
+        // like they do for the recursive `precedence`, unless a pending operator
+        // binds looser still on its right, like a low prefix or right associative one
+        let min_power = operator_stack
+            .iter()
+            .map(Operator::right_power)
+            .fold(start_precedence, Power::min);
+        loop {
+            let start = i.checkpoint();
+            // Postfix unary operators
+            let postfix = opt(|i: &mut I| grammar.postfix(i)).parse_next(i)?;
+            if let Some((lpower, op)) = postfix {
+                if lpower < min_power {
+                    i.reset(&start);
+                    break 'parse;
+                }
+                evaluate_to(i, lpower, &mut value_stack, &mut operator_stack)?;
+                // postfix operators are never put in pending state in `operator_stack`
+                let lhs = pop_operand(i, &mut value_stack)?;
+                value_stack.push(op(i, lhs)?);
+                continue;
             }
-            // postfix operators are never put in pending state in `operator_stack`
-            let lhs = value_stack.pop().expect("value");
-            value_stack.push(op(i, lhs)?);
+
+            // Delimited postfix operators, e.g. `f(a)` or `a[i]`
+            let delimited = opt(|i: &mut I| grammar.delimited(i)).parse_next(i)?;
+            if let Some((lpower, mut close, op)) = delimited {
+                if lpower < min_power {
+                    i.reset(&start);
+                    break 'parse;
+                }
+                evaluate_to(i, lpower, &mut value_stack, &mut operator_stack)?;
+                let inner =
+                    delimited_operand(i, grammar, &mut close, |i: &mut I, grammar: &mut G| {
+                        shunting_yard(grammar.start_power(), i, grammar, Some(DELIMITED))
+                    })?;
+                let lhs = pop_operand(i, &mut value_stack)?;
+                value_stack.push(op(i, lhs, inner)?);
+                continue;
+            }
+
+            break;
         }
+
         let start = i.checkpoint();
         // Infix binary operators
-        if let Some((assoc, op)) = opt(infix.by_ref()).parse_next(i)? {
-            let mut next_is_neither = None;
-            let lpower = match assoc {
-                Assoc::Left(p) => p,
-                Assoc::Right(p) => p,
-                Assoc::Neither(p) => {
-                    next_is_neither = Some(p);
-                    p
-                }
-            };
-            if current_is_neither.is_some_and(|n| n == lpower) {
+        let infix = opt(|i: &mut I| grammar.infix(i)).parse_next(i)?;
+        if let Some((assoc, op)) = infix {
+            let (lpower, _) = assoc.powers();
+            if lpower < min_power {
                 i.reset(&start);
                 break 'parse;
             }
+            evaluate_before_infix(i, &start, lpower, &mut value_stack, &mut operator_stack)?;
+            operator_stack.push(Operator::Binary(assoc, op));
+            after = Some(INFIX);
+            continue 'parse;
+        }
 
-            while operator_stack.last().is_some_and(|op| {
-                let rpower = op.right_power();
-                lpower < rpower
-            }) {
-                evaluate(
-                    i,
-                    &mut value_stack,
-                    operator_stack.pop().expect("already checked"),
-                )?;
-            }
-            current_is_neither = next_is_neither;
-            // some hackery around `a ? b : c, end` -> `(, (? a b c) end)`
-            // needs refactoring
-            if start_precedence <= lpower {
-                operator_stack.push(Operator::Binary(assoc, op));
-            } else {
+        // Ternary operators, e.g. `a ? b : c`
+        let ternary = opt(|i: &mut I| grammar.ternary(i)).parse_next(i)?;
+        if let Some((assoc, mut close, op)) = ternary {
+            let (lpower, _) = assoc.powers();
+            if lpower < min_power {
                 i.reset(&start);
                 break 'parse;
             }
-        } else {
-            // no more operators
-            break 'parse;
+            evaluate_before_infix(i, &start, lpower, &mut value_stack, &mut operator_stack)?;
+            // the middle is delimited, so it's parsed on its own
+            let middle = shunting_yard(grammar.start_power(), i, grammar, Some(TERNARY))?;
+            close_delimiter(i, grammar, &mut close, TERNARY)?;
+            value_stack.push(middle);
+            operator_stack.push(Operator::Ternary(assoc, op));
+            after = Some(TERNARY);
+            continue 'parse;
         }
+
+        // no more operators
+        break 'parse;
     }
 
     while let Some(op) = operator_stack.pop() {
         evaluate(i, &mut value_stack, op)?;
     }
-    // TODO: when it could happen?
-    // if eval_stack.len() > 1 {
-    //     // Error: value left on stack
-    // }
-
-    Ok(value_stack.pop().expect("well-formed expression")) // TODO: error handling
+    pop_operand(i, &mut value_stack)
 }
 
-enum Operator<I, Operand, E> {
+enum Operator<FoldPrefix, FoldInfix, FoldTernary> {
     // left binding power for the postfix or the right one for the prefix
-    Unary(Power, fn(&mut I, Operand) -> Result<Operand, E>),
+    Unary(Power, FoldPrefix),
     // left binding power and right binding power for the infix operator
-    Binary(Assoc, fn(&mut I, Operand, Operand) -> Result<Operand, E>),
+    Binary(Assoc, FoldInfix),
+    // like `Binary`, with the middle operand already on the value stack
+    Ternary(Assoc, FoldTernary),
 }
 
-impl<I, O, E> Operator<I, O, E> {
+impl<P, B, T> Operator<P, B, T> {
     fn right_power(&self) -> Power {
         match self {
             Operator::Unary(p, _) => *p,
-            Operator::Binary(Assoc::Left(p), _) => *p + 1,
-            Operator::Binary(Assoc::Right(p), _) => *p - 1,
-            Operator::Binary(Assoc::Neither(p), _) => *p + 1,
+            Operator::Binary(assoc, _) | Operator::Ternary(assoc, _) => assoc.powers().1,
+        }
+    }
+
+    fn neither(&self) -> Option<Power> {
+        match self {
+            Operator::Unary(..) => None,
+            Operator::Binary(assoc, _) | Operator::Ternary(assoc, _) => assoc.neither(),
         }
     }
 }
 
-fn evaluate<I, Operand, E>(
+/// Evaluates the pending operators which bind tighter than `lpower`.
+fn evaluate_to<I, Operand, FoldPrefix, FoldInfix, FoldTernary, E>(
+    i: &mut I,
+    lpower: Power,
+    value_stack: &mut Vec<Operand>,
+    operator_stack: &mut Vec<Operator<FoldPrefix, FoldInfix, FoldTernary>>,
+) -> Result<(), E>
+where
+    I: Stream,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldTernary: FnOnce(&mut I, Operand, Operand, Operand) -> Result<Operand, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    while let Some(op) = operator_stack.pop_if(|op| lpower < op.right_power()) {
+        evaluate(i, value_stack, op)?;
+    }
+    Ok(())
+}
+
+/// Like [`evaluate_to`], for an infix or ternary operator at `start`.
+fn evaluate_before_infix<I, Operand, FoldPrefix, FoldInfix, FoldTernary, E>(
+    i: &mut I,
+    start: &I::Checkpoint,
+    lpower: Power,
+    value_stack: &mut Vec<Operand>,
+    operator_stack: &mut Vec<Operator<FoldPrefix, FoldInfix, FoldTernary>>,
+) -> Result<(), E>
+where
+    I: Stream,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldTernary: FnOnce(&mut I, Operand, Operand, Operand) -> Result<Operand, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    while let Some(op) = operator_stack.pop_if(|op| lpower < op.right_power()) {
+        // `Assoc::Neither` has similar associativity rules as `Assoc::Left`,
+        // but it's an error when the next operator has the same power,
+        // e.g. `a == b == c`
+        if op.neither() == Some(lpower) {
+            i.reset(start);
+            return Err(chained_neither(i));
+        }
+        evaluate(i, value_stack, op)?;
+    }
+    Ok(())
+}
+
+fn evaluate<I, Operand, FoldPrefix, FoldInfix, FoldTernary, E>(
     i: &mut I,
     stack: &mut Vec<Operand>,
-    op: Operator<I, Operand, E>,
-) -> Result<(), E> {
+    op: Operator<FoldPrefix, FoldInfix, FoldTernary>,
+) -> Result<(), E>
+where
+    I: Stream,
+    FoldInfix: FnOnce(&mut I, Operand, Operand) -> Result<Operand, E>,
+    FoldPrefix: FnOnce(&mut I, Operand) -> Result<Operand, E>,
+    FoldTernary: FnOnce(&mut I, Operand, Operand, Operand) -> Result<Operand, E>,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
     match op {
         Operator::Unary(_, op) => {
-            let lhs = stack.pop().expect("value");
+            let lhs = pop_operand(i, stack)?;
             stack.push(op(i, lhs)?);
         }
         Operator::Binary(_, op) => {
-            // TODO: confirm invariants. It should be already checked by the parser algorithm itself
-            let rhs = stack.pop().expect("value");
-            let lhs = stack.pop().expect("value");
+            let rhs = pop_operand(i, stack)?;
+            let lhs = pop_operand(i, stack)?;
             let folded = op(i, lhs, rhs)?;
             stack.push(folded);
         }
+        Operator::Ternary(_, op) => {
+            let rhs = pop_operand(i, stack)?;
+            let middle = pop_operand(i, stack)?;
+            let lhs = pop_operand(i, stack)?;
+            let folded = op(i, lhs, middle, rhs)?;
+            stack.push(folded);
+        }
     };
     Ok(())
 }
 
-fn unwind_operators_stack_to<I, Operand, E>(
-    i: &mut I,
-    start_precedence: Power,
-    current_power: Assoc,
-    value_stack: &mut Vec<Operand>,
-    operator_stack: &mut Vec<Operator<I, Operand, E>>,
-) -> Result<(), E> {
-    let mut current_is_neither = None;
-    while operator_stack.last().is_some_and(|op| {
-        let rpower = op.right_power();
-        let mut next_is_neither = None;
-        let lpower = match current_power {
-            Assoc::Left(p) => p,
-            Assoc::Right(p) => p,
-            Assoc::Neither(p) => {
-                next_is_neither = Some(p);
-                p
-            }
-        };
-        #[cfg(feature = "std")]
-        dbg!(
-            lpower,
-            rpower,
-            start_precedence,
-            current_is_neither,
-            next_is_neither
-        );
-        let r = lpower < rpower
-            && lpower < start_precedence
-            && current_is_neither.is_none_or(|n| n != lpower);
-        current_is_neither = next_is_neither;
-        r
-    }) {
-        evaluate(
-            i,
-            value_stack,
-            operator_stack.pop().expect("already checked"),
-        )?;
-    }
-    Ok(())
+/// Pops an operand, which is always there for the operators on the stack,
+/// since the loop never pushes an operator without parsing its operands.
+fn pop_operand<I, Operand, E>(i: &I, stack: &mut Vec<Operand>) -> Result<Operand, E>
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    stack.pop().ok_or_else(|| missing_operand(i, None))
 }
 
 #[cfg(test)]
@@ -237,7 +362,14 @@ mod tests {
         token::any,
     };
 
+    use alloc::{
+        format,
+        string::{String, ToString as _},
+    };
+    use winnow::error::{ContextError, ParserError};
+
     use super::*;
+    use crate::precedence::{BinaryFold, UnaryFold};
 
     #[cfg(not(feature = "std"))]
     macro_rules! println {
@@ -258,31 +390,35 @@ mod tests {
             trace(
                 "prefix",
                 dispatch! {any;
-                    '+' => trace("+", empty).value((20, (|_: &mut _, a| Ok(a)) as _)),
-                    '-' => trace("-", empty).value((20, (|_: &mut _,a: i32| Ok(-a)) as _)),
+                    '+' => trace("+", empty).value((20, (|_: &mut _, a| Ok(a)) as UnaryFold<_, _, _>)),
+                    '-' => trace("-", empty).value((20, (|_: &mut _,a: i32| Ok(-a)) as UnaryFold<_, _, _>)),
                     _ => fail
                 },
             ),
-            trace("postfix", fail),
+            trace("postfix", fail::<_, (Power, UnaryFold<_, _, _>), _>),
             trace(
                 "infix",
                 dispatch! {any;
                    '+' => trace("+", empty).value((Assoc::Left(5), (|_: &mut _, a, b| {
                         println!("({a} + {b})");
                         Ok(a + b)
-                    }) as _)),
+                    }) as BinaryFold<_, _, _>)),
                    '-' => trace("-", empty).value((Assoc::Left(5), (|_: &mut _, a, b| {
                         println!("({a} - {b})");
                         Ok(a - b)
-                    }) as _)),
+                    }) as BinaryFold<_, _, _>)),
                    '*' => trace("*", empty).value((Assoc::Left(7), (|_: &mut _, a, b|{
                         println!("({a} * {b})");
                         Ok(a * b)
-                    }) as _)),
+                    }) as BinaryFold<_, _, _>)),
                    '/' => trace("/", empty).value((Assoc::Left(7), (|_: &mut _, a, b| {
                         println!("({a} / {b})");
                         Ok(a / b)
-                    }) as _)),
+                    }) as BinaryFold<_, _, _>)),
+                   '=' => trace("=", empty).value((Assoc::Neither(3), (|_: &mut _, a, b| {
+                        println!("({a} = {b})");
+                        Ok((a == b) as i32)
+                    }) as BinaryFold<_, _, _>)),
                    _ => fail
                 },
             ),
@@ -298,4 +434,109 @@ mod tests {
         assert!(parser.parse("1==2==3").is_err());
         assert!(parser.parse("2*").is_err());
     }
+
+    #[test]
+    fn test_errors() {
+        let err = |input| {
+            let e = parser.parse(input).unwrap_err();
+            (e.offset(), e.inner().to_string())
+        };
+        let message = |offset, message: &str| (offset, message.to_string());
+
+        // missing operands
+        assert_eq!(err(""), message(0, "expected operand"));
+        assert_eq!(
+            err("2*"),
+            message(2, "invalid infix operator\nexpected operand")
+        );
+        assert_eq!(
+            err("2**3"),
+            message(2, "invalid infix operator\nexpected operand")
+        );
+
+        // dangling prefix operators
+        assert_eq!(
+            err("-"),
+            message(1, "invalid prefix operator\nexpected operand")
+        );
+        assert_eq!(
+            err("2*--"),
+            message(4, "invalid prefix operator\nexpected operand")
+        );
+
+        // chained non-associative operators
+        let chained = "invalid chained non-associative operator";
+        assert_eq!(err("1=1=1"), message(3, chained));
+        assert_eq!(err("1=2-1=1"), message(5, chained));
+        assert_eq!(parser.parse("(1=1)=1"), Ok(1));
+        assert_eq!(parser.parse("1=1+0"), Ok(1));
+    }
+
+    /// Parses a prefix of `input` from `start_power` with both algorithms,
+    /// which have to agree, into a tree and the input left over.
+    fn cutoff<'i>(
+        start_power: Power,
+        prefix: &[(char, Power)],
+        postfix: &[(char, Power)],
+        infix: &[(char, Assoc)],
+        input: &'i str,
+    ) -> (String, &'i str) {
+        let operand = |i: &mut &str| -> Result<String> { 'a'.map(String::from).parse_next(i) };
+        let prefix = |i: &mut &str| {
+            let c = any.parse_next(i)?;
+            let &(op, power) = prefix
+                .iter()
+                .find(|(op, _)| *op == c)
+                .ok_or_else(|| ContextError::from_input(i))?;
+            Ok::<_, ContextError>((power, move |_: &mut _, a| Ok(format!("({op}{a})"))))
+        };
+        let postfix = |i: &mut &str| {
+            let c = any.parse_next(i)?;
+            let &(op, power) = postfix
+                .iter()
+                .find(|(op, _)| *op == c)
+                .ok_or_else(|| ContextError::from_input(i))?;
+            Ok::<_, ContextError>((power, move |_: &mut _, a| Ok(format!("({a}{op})"))))
+        };
+        let infix = |i: &mut &str| {
+            let c = any.parse_next(i)?;
+            let &(op, assoc) = infix
+                .iter()
+                .find(|(op, _)| *op == c)
+                .ok_or_else(|| ContextError::from_input(i))?;
+            Ok::<_, ContextError>((assoc, move |_: &mut _, a, b| Ok(format!("({a}{op}{b})"))))
+        };
+
+        let mut pratt_input = input;
+        let pratt = crate::precedence::precedence(start_power, operand, prefix, postfix, infix)
+            .parse_next(&mut pratt_input)
+            .unwrap();
+        let mut shunting_input = input;
+        let shunting = precedence(start_power, operand, prefix, postfix, infix)
+            .parse_next(&mut shunting_input)
+            .unwrap();
+        assert_eq!((&pratt, pratt_input), (&shunting, shunting_input));
+        (shunting, shunting_input)
+    }
+
+    #[test]
+    fn test_start_power_cutoff() {
+        // the `+` on the stack binds looser than the start power on its right,
+        // so `!` still applies to its rhs
+        assert_eq!(
+            cutoff(2, &[], &[('!', 1)], &[('+', Assoc::Right(2))], "a+a!"),
+            ("(a+(a!))".into(), "")
+        );
+        // the same for a prefix operator with a low power
+        assert_eq!(
+            cutoff(3, &[('<', 0)], &[('+', 0)], &[], "<a+"),
+            ("(<(a+))".into(), "")
+        );
+        // a postfix operator below the start power ends the expression, but
+        // keeps the operators before it
+        assert_eq!(
+            cutoff(2, &[], &[('-', 2), ('!', 0)], &[], "a-!"),
+            ("(a-)".into(), "!")
+        );
+    }
 }
@@ -0,0 +1,522 @@
+//! A declarative table of operators, as a less verbose way to use
+//! [`precedence::precedence`] or [`shunting_yard::precedence`].
+//!
+//! Instead of writing a `dispatch!` for each kind of operator, every operator
+//! is registered once as a parser, a binding power and a fold:
+//!
+//! ```
+//! use pratt::precedence::Assoc;
+//! use pratt::table::{OperatorTable, Pratt};
+//! use winnow::{Parser, ascii::digit1, error::ContextError};
+//!
+//! fn calc<'i>() -> OperatorTable<'i, &'i str, i64, ContextError, Pratt> {
+//!     OperatorTable::new(digit1.parse_to())
+//!         .prefix('-', 9, |_, a: i64| Ok(-a))
+//!         .infix("**", Assoc::Right(7), |_, a: i64, b| Ok(a.pow(b as u32)))
+//!         .infix('*', Assoc::Left(5), |_, a, b| Ok(a * b))
+//!         .infix('+', Assoc::Left(3), |_, a, b| Ok(a + b))
+//! }
+//!
+//! assert_eq!(calc().parse("2+3*-2**2"), Ok(14));
+//! ```
+//!
+//! Operators of the same kind are tried in the order they were registered, and
+//! the first one to match is used, like [`winnow::combinator::alt`]. So an
+//! operator like `**` has to be registered before `*`.
+//!
+//! The algorithm is chosen with the last type parameter, either [`Pratt`] or
+//! [`ShuntingYard`], and both give the same results.
+//!
+//! [`precedence::precedence`]: crate::precedence::precedence
+//! [`shunting_yard::precedence`]: crate::shunting_yard::precedence
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use winnow::{
+    Parser, Result,
+    combinator::{alt, trace},
+    error::{AddContext, ParserError, StrContext},
+    stream::{Stream, StreamIsPartial},
+};
+
+use crate::precedence::{Assoc, Grammar, Parsers, Power, precedence_impl};
+use crate::shunting_yard::shunting_yard;
+
+/// How a prefix or postfix operator is folded into its operand.
+type UnaryFold<'a, I, O, E> = Box<dyn Fn(&mut I, O) -> Result<O, E> + 'a>;
+
+/// How an infix operator is folded into its operands.
+type BinaryFold<'a, I, O, E> = Box<dyn Fn(&mut I, O, O) -> Result<O, E> + 'a>;
+
+/// How a ternary operator is folded into its operands.
+type TernaryFold<'a, I, O, E> = Box<dyn Fn(&mut I, O, O, O) -> Result<O, E> + 'a>;
+
+/// How a delimited postfix operator is folded into its operands.
+type DelimitedFold<'a, I, O, E> = Box<dyn Fn(&mut I, O, Option<O>) -> Result<O, E> + 'a>;
+
+/// The closing delimiter of a ternary or delimited postfix operator. It's only
+/// borrowed while it's parsed, which never recurses into the table.
+type Close<'a, I, E> = RefCell<Box<dyn Parser<I, (), E> + 'a>>;
+
+/// A ternary operator's associativity, closing delimiter and fold.
+type Ternary<'a, I, O, E> = (Assoc, Close<'a, I, E>, TernaryFold<'a, I, O, E>);
+
+/// A delimited postfix operator's power, closing delimiter and fold.
+type Delimited<'a, I, O, E> = (Power, Close<'a, I, E>, DelimitedFold<'a, I, O, E>);
+
+/// The operators of one kind, where each parser gives the index of its fold.
+struct Operators<'a, I, F, E> {
+    parsers: Vec<Box<dyn Parser<I, usize, E> + 'a>>,
+    folds: Vec<F>,
+}
+
+impl<'a, I, F, E> Operators<'a, I, F, E>
+where
+    I: Stream + 'a,
+    E: ParserError<I> + 'a,
+{
+    fn new() -> Self {
+        Self {
+            parsers: Vec::new(),
+            folds: Vec::new(),
+        }
+    }
+
+    fn push<T: 'a>(&mut self, parser: impl Parser<I, T, E> + 'a, fold: F) {
+        self.parsers.push(Box::new(parser.value(self.folds.len())));
+        self.folds.push(fold);
+    }
+}
+
+/// The algorithm an [`OperatorTable`] parses with.
+pub trait Algorithm {
+    /// Parses an expression starting at `start_power`.
+    #[doc(hidden)]
+    #[allow(private_bounds)]
+    fn precedence<I, O, E, G>(i: &mut I, grammar: &mut G, start_power: Power) -> Result<O, E>
+    where
+        I: Stream + StreamIsPartial,
+        G: Grammar<I, O, E>,
+        E: ParserError<I> + AddContext<I, StrContext>;
+}
+
+/// The recursive Pratt parser, see [`crate::precedence::precedence`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pratt;
+
+impl Algorithm for Pratt {
+    #[inline(always)]
+    #[allow(private_bounds)]
+    fn precedence<I, O, E, G>(i: &mut I, grammar: &mut G, start_power: Power) -> Result<O, E>
+    where
+        I: Stream + StreamIsPartial,
+        G: Grammar<I, O, E>,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        precedence_impl(i, grammar, start_power, None)
+    }
+}
+
+/// The Shunting Yard algorithm, see [`crate::shunting_yard::precedence`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShuntingYard;
+
+impl Algorithm for ShuntingYard {
+    #[inline(always)]
+    #[allow(private_bounds)]
+    fn precedence<I, O, E, G>(i: &mut I, grammar: &mut G, start_power: Power) -> Result<O, E>
+    where
+        I: Stream + StreamIsPartial,
+        G: Grammar<I, O, E>,
+        E: ParserError<I> + AddContext<I, StrContext>,
+    {
+        shunting_yard(start_power, i, grammar, None)
+    }
+}
+
+/// A table of operators around an operand parser, which is itself a [`Parser`].
+///
+/// See the [module docs](self) for an example.
+pub struct OperatorTable<'a, I, O, E, A = Pratt> {
+    start_power: Power,
+    operand: Box<dyn Parser<I, O, E> + 'a>,
+    prefix: Operators<'a, I, (Power, UnaryFold<'a, I, O, E>), E>,
+    postfix: Operators<'a, I, (Power, UnaryFold<'a, I, O, E>), E>,
+    infix: Operators<'a, I, (Assoc, BinaryFold<'a, I, O, E>), E>,
+    ternary: Operators<'a, I, Ternary<'a, I, O, E>, E>,
+    delimited: Operators<'a, I, Delimited<'a, I, O, E>, E>,
+    algorithm: PhantomData<fn() -> A>,
+}
+
+impl<'a, I, O, E, A> OperatorTable<'a, I, O, E, A>
+where
+    I: Stream + StreamIsPartial + 'a,
+    O: 'a,
+    E: ParserError<I> + 'a,
+{
+    /// Creates a table without operators, which parses `operand`.
+    pub fn new(operand: impl Parser<I, O, E> + 'a) -> Self {
+        Self {
+            start_power: 0,
+            operand: Box::new(operand),
+            prefix: Operators::new(),
+            postfix: Operators::new(),
+            infix: Operators::new(),
+            ternary: Operators::new(),
+            delimited: Operators::new(),
+            algorithm: PhantomData,
+        }
+    }
+
+    /// Only parses operators with at least this binding power, `0` by default.
+    pub fn start_power(mut self, power: Power) -> Self {
+        self.start_power = power;
+        self
+    }
+
+    /// Adds a prefix operator, like `-a`. Its operand is parsed with `power`.
+    pub fn prefix<T: 'a>(
+        mut self,
+        parser: impl Parser<I, T, E> + 'a,
+        power: Power,
+        fold: impl Fn(&mut I, O) -> Result<O, E> + 'a,
+    ) -> Self {
+        self.prefix.push(parser, (power, Box::new(fold)));
+        self
+    }
+
+    /// Adds a postfix operator, like `a!`. It only applies to operands parsed
+    /// with at most `power`, so `-a!` is `-(a!)` if `!` binds tighter than `-`.
+    pub fn postfix<T: 'a>(
+        mut self,
+        parser: impl Parser<I, T, E> + 'a,
+        power: Power,
+        fold: impl Fn(&mut I, O) -> Result<O, E> + 'a,
+    ) -> Self {
+        self.postfix.push(parser, (power, Box::new(fold)));
+        self
+    }
+
+    /// Adds an infix operator, like `a + b`.
+    pub fn infix<T: 'a>(
+        mut self,
+        parser: impl Parser<I, T, E> + 'a,
+        assoc: Assoc,
+        fold: impl Fn(&mut I, O, O) -> Result<O, E> + 'a,
+    ) -> Self {
+        self.infix.push(parser, (assoc, Box::new(fold)));
+        self
+    }
+
+    /// Adds a ternary operator, like `a ? b : c`, where `open` is followed by
+    /// the middle operand and `close`. The middle operand is parsed like an
+    /// expression between parentheses, and `assoc` applies to the last one.
+    pub fn ternary<T: 'a, U: 'a>(
+        mut self,
+        open: impl Parser<I, T, E> + 'a,
+        close: impl Parser<I, U, E> + 'a,
+        assoc: Assoc,
+        fold: impl Fn(&mut I, O, O, O) -> Result<O, E> + 'a,
+    ) -> Self {
+        let close = RefCell::new(Box::new(close.void()) as Box<dyn Parser<I, (), E>>);
+        self.ternary.push(open, (assoc, close, Box::new(fold)));
+        self
+    }
+
+    /// Adds a delimited postfix operator, like a call `f(a)` or an index
+    /// `a[i]`. The operand between `open` and `close` is optional, and is
+    /// parsed like an expression between parentheses.
+    pub fn delimited_postfix<T: 'a, U: 'a>(
+        mut self,
+        open: impl Parser<I, T, E> + 'a,
+        close: impl Parser<I, U, E> + 'a,
+        power: Power,
+        fold: impl Fn(&mut I, O, Option<O>) -> Result<O, E> + 'a,
+    ) -> Self {
+        let close = RefCell::new(Box::new(close.void()) as Box<dyn Parser<I, (), E>>);
+        self.delimited.push(open, (power, close, Box::new(fold)));
+        self
+    }
+}
+
+impl<I, O, E, A> Parser<I, O, E> for OperatorTable<'_, I, O, E, A>
+where
+    I: Stream + StreamIsPartial,
+    E: ParserError<I> + AddContext<I, StrContext>,
+    A: Algorithm,
+{
+    fn parse_next(&mut self, i: &mut I) -> Result<O, E> {
+        let Self {
+            start_power,
+            operand,
+            prefix,
+            postfix,
+            infix,
+            ternary,
+            delimited,
+            ..
+        } = self;
+        let mut grammar = Parsers::new(
+            *start_power,
+            |i: &mut I| operand.parse_next(i),
+            |i: &mut I| {
+                let (power, fold) = first_match(&mut prefix.parsers, &prefix.folds, i)?;
+                Ok((*power, fold))
+            },
+            |i: &mut I| {
+                let (power, fold) = first_match(&mut postfix.parsers, &postfix.folds, i)?;
+                Ok((*power, fold))
+            },
+            |i: &mut I| {
+                let (assoc, fold) = first_match(&mut infix.parsers, &infix.folds, i)?;
+                Ok((*assoc, fold))
+            },
+            |i: &mut I| {
+                let (assoc, close, fold) = first_match(&mut ternary.parsers, &ternary.folds, i)?;
+                Ok((*assoc, |i: &mut I| close.borrow_mut().parse_next(i), fold))
+            },
+            |i: &mut I| {
+                let (power, close, fold) =
+                    first_match(&mut delimited.parsers, &delimited.folds, i)?;
+                Ok((*power, |i: &mut I| close.borrow_mut().parse_next(i), fold))
+            },
+        );
+        trace("operator_table", |i: &mut I| {
+            A::precedence(i, &mut grammar, *start_power)
+        })
+        .parse_next(i)
+    }
+}
+
+/// Tries each operator in order, failing like `fail` when there are none.
+fn first_match<'f, I, F, E>(
+    parsers: &mut [Box<dyn Parser<I, usize, E> + '_>],
+    folds: &'f [F],
+    i: &mut I,
+) -> Result<&'f F, E>
+where
+    I: Stream,
+    E: ParserError<I> + AddContext<I, StrContext>,
+{
+    if parsers.is_empty() {
+        return Err(E::from_input(i));
+    }
+    Ok(&folds[alt(parsers).parse_next(i)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use winnow::ascii::{digit1, space0};
+    use winnow::combinator::{delimited, not};
+    use winnow::error::{ContextError, ErrMode, ParseError};
+    use winnow::{ModalResult, Partial};
+
+    use super::*;
+
+    fn factorial(x: i32) -> i32 {
+        if x <= 1 { 1 } else { x * factorial(x - 1) }
+    }
+
+    fn calc<'i, A: Algorithm + 'static>() -> OperatorTable<'i, &'i str, i32, ContextError, A> {
+        OperatorTable::new(delimited(
+            space0,
+            alt((digit1.parse_to(), delimited('(', expr::<A>, ')'))),
+            space0,
+        ))
+        .prefix(token('-'), 9, |_, a: i32| Ok(-a))
+        .prefix(token('+'), 9, |_, a| Ok(a))
+        .postfix(token(('!', not('='))), 11, |_, a| Ok(factorial(a)))
+        .infix(
+            token("=="),
+            Assoc::Neither(2),
+            |_, a, b| Ok((a == b) as i32),
+        )
+        .infix(token('+'), Assoc::Left(5), |_, a, b| Ok(a + b))
+        .infix(token('-'), Assoc::Left(5), |_, a, b| Ok(a - b))
+        .infix(token("**"), Assoc::Right(8), |_, a, b| Ok(a.pow(b as u32)))
+        .infix(token('*'), Assoc::Left(7), |_, a, b| Ok(a * b))
+        .infix(token('/'), Assoc::Left(7), |_, a, b| Ok(a / b))
+        .ternary(token('?'), token(':'), Assoc::Right(1), |_, a, b, c| {
+            Ok(if a != 0 { b } else { c })
+        })
+        // `a[b]` appends the digit `b` to `a`
+        .delimited_postfix(token('['), token(']'), 12, |_, a, b| {
+            Ok(a * 10 + b.unwrap_or(0))
+        })
+    }
+
+    fn token<'i, T>(
+        op: impl Parser<&'i str, T, ContextError>,
+    ) -> impl Parser<&'i str, T, ContextError> {
+        delimited(space0, op, space0)
+    }
+
+    fn expr<A: Algorithm + 'static>(i: &mut &str) -> Result<i32> {
+        calc::<A>().parse_next(i)
+    }
+
+    /// Parses with both algorithms, which have to agree on the result or
+    /// the offset and message of the error.
+    fn both(input: &str) -> Result<i32, (usize, String)> {
+        let error = |e: ParseError<_, ContextError>| (e.offset(), e.inner().to_string());
+        let pratt = calc::<Pratt>().parse(input).map_err(error);
+        let shunting_yard = calc::<ShuntingYard>().parse(input).map_err(error);
+        assert_eq!(pratt, shunting_yard, "{input}");
+        pratt
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(both("1 + 2 * 3"), Ok(7));
+        assert_eq!(both("(1 + 2) * 3"), Ok(9));
+        assert_eq!(both("8 - 2 - 1"), Ok(5));
+        assert_eq!(both("2 ** 3 ** 2"), Ok(512));
+        assert_eq!(both("-3! + -3 * 4"), Ok(-18));
+        assert_eq!(both("3! == 6"), Ok(1));
+        assert!(both("1 == 1 == 1").is_err());
+        assert!(both("2 *").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |offset, message: &str| Err((offset, message.to_string()));
+
+        // missing operands
+        assert_eq!(both(""), err(0, "expected operand"));
+        assert_eq!(
+            both("2 *"),
+            err(3, "invalid infix operator\nexpected operand")
+        );
+        assert_eq!(
+            both("2 * * 3"),
+            err(4, "invalid infix operator\nexpected operand")
+        );
+
+        // dangling prefix operators
+        assert_eq!(
+            both("-"),
+            err(1, "invalid prefix operator\nexpected operand")
+        );
+        assert_eq!(
+            both("2 * - -"),
+            err(7, "invalid prefix operator\nexpected operand")
+        );
+
+        // chained non-associative operators
+        let chained = "invalid chained non-associative operator";
+        assert_eq!(both("1 == 1 == 1"), err(7, chained));
+        assert_eq!(both("1 == 2 - 1 == 1"), err(11, chained));
+        assert_eq!(both("(1 == 1) == 1"), Ok(1));
+        assert_eq!(both("1 == 1 + 0"), Ok(1));
+        assert_eq!(both("1 ? 2"), err(5, "invalid ternary operator"));
+        assert_eq!(
+            both("1 ? 2 :"),
+            err(7, "invalid ternary operator\nexpected operand")
+        );
+        assert_eq!(
+            both("1 ? : 2"),
+            err(4, "invalid ternary operator\nexpected operand")
+        );
+        assert_eq!(both("4[2"), err(3, "invalid delimited postfix operator"));
+        assert_eq!(
+            both("4[2 +]"),
+            err(5, "invalid infix operator\nexpected operand")
+        );
+    }
+
+    #[test]
+    fn test_mixfix() {
+        // ternary operators
+        assert_eq!(both("1 ? 2 : 3"), Ok(2));
+        assert_eq!(both("0 ? 2 : 3 + 1"), Ok(4));
+        assert_eq!(both("1 == 1 ? 2 : 3"), Ok(2));
+        assert_eq!(both("0 ? 1 : 0 ? 2 : 3"), Ok(3));
+        assert_eq!(both("1 ? 0 ? 7 : 8 : 9"), Ok(8));
+        assert_eq!(both("(1 ? 2 : 3) * 4"), Ok(8));
+        assert_eq!(both("1 ? 2 : 3 * 4"), Ok(2));
+
+        // delimited postfix operators
+        assert_eq!(both("4[2]"), Ok(42));
+        assert_eq!(both("4[]"), Ok(40));
+        assert_eq!(both("-4[2]"), Ok(-42));
+        assert_eq!(both("4[2][3]"), Ok(423));
+        assert_eq!(both("2 * 4[1 ? 5 : 6]"), Ok(90));
+        assert_eq!(both("1[0]!"), Ok(factorial(10)));
+    }
+
+    #[test]
+    fn test_partial() {
+        type Input<'i> = Partial<&'i [u8]>;
+
+        fn calc<'i, A: Algorithm + 'static>()
+        -> OperatorTable<'i, Input<'i>, i32, ErrMode<ContextError>, A> {
+            OperatorTable::new(alt((digit1.parse_to(), delimited('(', expr::<A>, ')'))))
+                .prefix('-', 9, |_, a: i32| Ok(-a))
+                .postfix('!', 11, |_, a| Ok(factorial(a)))
+                .infix('+', Assoc::Left(5), |_, a, b| Ok(a + b))
+                .infix('*', Assoc::Left(7), |_, a, b| Ok(a * b))
+                .ternary('?', ':', Assoc::Right(1), |_, a, b, c| {
+                    Ok(if a != 0 { b } else { c })
+                })
+                .delimited_postfix('[', ']', 12, |_, a, b| Ok(a * 10 + b.unwrap_or(0)))
+        }
+
+        fn expr<A: Algorithm + 'static>(i: &mut Input<'_>) -> ModalResult<i32> {
+            calc::<A>().parse_next(i)
+        }
+
+        /// Feeds `input` to the parser one byte at a time, which has to ask
+        /// for more until the last one, and then parse all of it.
+        fn chunked<A: Algorithm + 'static>(input: &str) -> ModalResult<i32> {
+            let input = input.as_bytes();
+            for end in 0..input.len() {
+                let mut i = Partial::new(&input[..end]);
+                match expr::<A>(&mut i) {
+                    Err(ErrMode::Incomplete(_)) => {}
+                    result => panic!("{result:?} after {end} bytes"),
+                }
+            }
+            let mut i = Partial::new(input);
+            let _ = i.complete();
+            let result = expr::<A>(&mut i);
+            assert_eq!(i.eof_offset(), 0);
+            result
+        }
+
+        fn both(input: &str) -> i32 {
+            let pratt = chunked::<Pratt>(input);
+            assert_eq!(pratt, chunked::<ShuntingYard>(input), "{input}");
+            pratt.unwrap()
+        }
+
+        assert_eq!(both("12"), 12);
+        assert_eq!(both("12+34*2"), 80);
+        assert_eq!(both("-3!+(1+1)*4"), 2);
+        assert_eq!(both("1?20:30"), 20);
+        assert_eq!(both("4[2]+4[]"), 82);
+        assert_eq!(both("(1+2)!"), 6);
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let mut table = OperatorTable::<_, _, ContextError, Pratt>::new(digit1.parse_to::<i32>());
+        assert_eq!(table.parse("42"), Ok(42));
+        assert!(table.parse("-42").is_err());
+        assert!(table.parse("4 2").is_err());
+    }
+
+    #[test]
+    fn test_capturing_folds() {
+        // `%` is a percentage of the captured base
+        let base = 40;
+        let mut table =
+            OperatorTable::<_, _, ContextError, ShuntingYard>::new(digit1.parse_to::<i32>())
+                .postfix('%', 9, move |_, a| Ok(a * base / 100))
+                .infix('+', Assoc::Left(5), |_, a, b| Ok(a + b));
+        assert_eq!(table.parse("50%+1"), Ok(21));
+    }
+}
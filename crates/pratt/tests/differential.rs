@@ -0,0 +1,152 @@
+mod differential_helpers;
+
+use pratt::precedence::{Assoc, Power};
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use differential_helpers::{Case, DELIMITERS, OPERANDS, SYMBOLS};
+
+fn arb_power() -> impl Strategy<Value = Power> {
+    0..8 as Power
+}
+
+fn arb_assoc() -> impl Strategy<Value = Assoc> {
+    prop_oneof![
+        arb_power().prop_map(Assoc::Left),
+        arb_power().prop_map(Assoc::Right),
+        arb_power().prop_map(Assoc::Neither),
+    ]
+}
+
+/// An input which is mostly made of operands and operators, so it often parses.
+fn arb_input() -> impl Strategy<Value = String> {
+    let token = prop_oneof![
+        4 => select(OPERANDS),
+        4 => select(SYMBOLS),
+        1 => select(DELIMITERS),
+    ];
+    proptest::collection::vec(token, 0..16).prop_map(String::from_iter)
+}
+
+fn arb_case() -> impl Strategy<Value = Case> {
+    (
+        0..4 as Power,
+        proptest::collection::vec((select(SYMBOLS), arb_power()), 0..4),
+        proptest::collection::vec((select(SYMBOLS), arb_power()), 0..4),
+        proptest::collection::vec((select(SYMBOLS), arb_assoc()), 0..6),
+        proptest::option::of(arb_assoc()),
+        proptest::option::of(arb_power()),
+        arb_input(),
+    )
+        .prop_map(
+            |(start_power, prefix, postfix, infix, ternary, delimited, input)| Case {
+                start_power,
+                prefix,
+                postfix,
+                infix,
+                ternary,
+                delimited,
+                input,
+            },
+        )
+}
+
+// the default 256 cases run quickly, and `PROPTEST_CASES=100000` runs more
+proptest! {
+    #[test]
+    fn same_outcome(case in arb_case()) {
+        let _ = case.check();
+    }
+
+    #[test]
+    fn same_outcome_from_bytes(data in proptest::collection::vec(any::<u8>(), 0..48)) {
+        let _ = Case::from_bytes(&data).check();
+    }
+}
+
+/// A table with every kind of operator, for the cases the random ones rarely hit.
+fn table(start_power: Power, input: &str) -> Case {
+    Case {
+        start_power,
+        prefix: vec![('-', 6), ('!', 2)],
+        postfix: vec![('!', 7), ('%', 3)],
+        infix: vec![
+            ('=', Assoc::Neither(1)),
+            ('<', Assoc::Neither(1)),
+            ('+', Assoc::Left(4)),
+            ('-', Assoc::Left(4)),
+            ('*', Assoc::Right(5)),
+        ],
+        ternary: Some(Assoc::Right(2)),
+        delimited: Some(8),
+        input: input.into(),
+    }
+}
+
+fn ok(tree: &str, left: usize) -> differential_helpers::Outcome {
+    Ok((tree.into(), left))
+}
+
+#[test]
+fn test_neither() {
+    assert!(table(0, "a=b=c").check().is_err());
+    assert!(table(0, "a=b<c").check().is_err());
+    assert_eq!(table(0, "(a=b)=c").check(), ok("(((a = b)) = c)", 0));
+    assert_eq!(table(0, "a=b+c").check(), ok("(a = (b + c))", 0));
+    // a postfix operator binding tighter only applies to the right operand
+    assert_eq!(
+        table(0, "a=b%=c").check(),
+        Err((4, "invalid chained non-associative operator".into()))
+    );
+    assert_eq!(table(0, "(a=b)%=c").check(), ok("((((a = b))%) = c)", 0));
+    // the right operand of a ternary operator binds looser than the comparison
+    assert_eq!(
+        table(0, "a=b?c:a=c").check(),
+        ok("(a = (b ? c : (a = c)))", 0)
+    );
+}
+
+#[test]
+fn test_prefix_postfix_power() {
+    // `!` binds tighter than `-`, and `%` looser
+    assert_eq!(table(0, "-a!").check(), ok("(-(a!))", 0));
+    assert_eq!(table(0, "-a%").check(), ok("((-a)%)", 0));
+    assert_eq!(table(0, "!a%").check(), ok("(!(a%))", 0));
+    assert_eq!(table(0, "-a[b]!").check(), ok("(-(a[b]!))", 0));
+    assert_eq!(table(0, "!a+b").check(), ok("(!(a + b))", 0));
+    assert_eq!(table(0, "-a+b").check(), ok("((-a) + b)", 0));
+}
+
+#[test]
+fn test_start_power() {
+    // operators binding looser than the start power end the expression
+    assert_eq!(table(3, "a+b=c").check(), ok("(a + b)", 2));
+    assert_eq!(table(3, "a?b:c").check(), ok("a", 4));
+    assert_eq!(table(4, "a%").check(), ok("a", 1));
+    assert_eq!(table(4, "!a").check(), ok("(!a)", 0));
+    assert_eq!(table(4, "(a=b)%").check(), ok("((a = b))", 1));
+}
+
+#[test]
+fn test_looser_pending_operators() {
+    // a pending operator binding looser than the start power on its right
+    // still takes operators binding tighter than itself
+    let case = |prefix, infix, input: &str| Case {
+        start_power: 3,
+        prefix,
+        postfix: vec![('!', 2), ('%', 0)],
+        infix,
+        ternary: None,
+        delimited: None,
+        input: input.into(),
+    };
+    assert_eq!(
+        case(vec![('-', 1)], vec![], "-a!").check(),
+        ok("(-(a!))", 0)
+    );
+    assert_eq!(
+        case(vec![], vec![('+', Assoc::Right(3))], "a+b!%").check(),
+        ok("(a + (b!))", 1)
+    );
+    assert_eq!(case(vec![], vec![], "a!%").check(), ok("a", 2));
+}
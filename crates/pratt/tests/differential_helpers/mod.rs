@@ -0,0 +1,218 @@
+//! A random operator table and input, parsed with both algorithms.
+//!
+//! It's shared by `tests/differential.rs` and the `pratt_differential` fuzz
+//! target, so it only depends on `pratt` and `winnow`.
+
+use std::fmt;
+
+use pratt::precedence::{Assoc, Power};
+use pratt::table::{Algorithm, OperatorTable, Pratt, ShuntingYard};
+use winnow::Parser;
+use winnow::combinator::{alt, delimited};
+use winnow::error::{ContextError, ParserError};
+use winnow::token::{any, one_of};
+
+/// The operator symbols, where a symbol can be an operator of more than one kind.
+pub const SYMBOLS: &[char] = &['+', '-', '*', '!', '=', '<', '&', '%'];
+
+/// The operands, which are single letters.
+pub const OPERANDS: &[char] = &['a', 'b', 'c'];
+
+/// The other tokens an input can have: parentheses, a ternary `?` `:` and a
+/// delimited postfix `[` `]`.
+pub const DELIMITERS: &[char] = &['(', ')', '?', ':', '[', ']'];
+
+/// A random operator table with its input.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub start_power: Power,
+    pub prefix: Vec<(char, Power)>,
+    pub postfix: Vec<(char, Power)>,
+    pub infix: Vec<(char, Assoc)>,
+    pub ternary: Option<Assoc>,
+    pub delimited: Option<Power>,
+    pub input: String,
+}
+
+/// The result of a parse: the tree and how much input is left after the
+/// `start_power` cutoff, or the offset and message of the error.
+pub type Outcome = Result<(String, usize), (usize, String)>;
+
+impl Case {
+    /// Decodes a case from arbitrary bytes, like a fuzzer's.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut bytes = data.iter().copied();
+        let mut next = || bytes.next().unwrap_or(0);
+
+        let start_power = Power::from(next() % 4);
+        let symbol = |b: u8| SYMBOLS[usize::from(b) % SYMBOLS.len()];
+        let power = |b: u8| Power::from(b % 8);
+        let assoc = |kind: u8, b: u8| match kind % 3 {
+            0 => Assoc::Left(power(b)),
+            1 => Assoc::Right(power(b)),
+            _ => Assoc::Neither(power(b)),
+        };
+
+        let prefix = (0..next() % 4)
+            .map(|_| (symbol(next()), power(next())))
+            .collect();
+        let postfix = (0..next() % 4)
+            .map(|_| (symbol(next()), power(next())))
+            .collect();
+        let infix = (0..next() % 6)
+            .map(|_| (symbol(next()), assoc(next(), next())))
+            .collect();
+        let ternary = (next() % 2 == 0).then(|| assoc(next(), next()));
+        let delimited = (next() % 2 == 0).then(|| power(next()));
+
+        // the rest of the bytes are the input
+        let tokens: Vec<char> = [SYMBOLS, OPERANDS, DELIMITERS].concat();
+        let input = bytes
+            .map(|b| tokens[usize::from(b) % tokens.len()])
+            .collect();
+
+        Self {
+            start_power,
+            prefix,
+            postfix,
+            infix,
+            ternary,
+            delimited,
+            input,
+        }
+    }
+
+    /// Parses the input with the `A` algorithm.
+    pub fn parse<A: Algorithm + 'static>(&self) -> Outcome {
+        let mut i = self.input.as_str();
+        match expr::<A>(self, self.start_power).parse_next(&mut i) {
+            Ok(tree) => Ok((tree, i.len())),
+            Err(e) => Err((self.input.len() - i.len(), e.to_string())),
+        }
+    }
+
+    /// Parses the input with the `precedence` function of `pratt::precedence`,
+    /// or of `pratt::shunting_yard`, which only take prefix, postfix and infix
+    /// operators.
+    pub fn parse_precedence(&self, shunting_yard: bool) -> Outcome {
+        let mut i = self.input.as_str();
+        match precedence_expr(self, self.start_power, shunting_yard, &mut i) {
+            Ok(tree) => Ok((tree, i.len())),
+            Err(e) => Err((self.input.len() - i.len(), e.to_string())),
+        }
+    }
+
+    /// Parses the input with both algorithms, which have to agree, and with
+    /// both `precedence` functions too if the case has no ternary or delimited
+    /// postfix operator.
+    pub fn check(&self) -> Outcome {
+        let pratt = self.parse::<Pratt>();
+        let shunting_yard = self.parse::<ShuntingYard>();
+        assert_eq!(pratt, shunting_yard, "{self}");
+        if self.ternary.is_none() && self.delimited.is_none() {
+            assert_eq!(pratt, self.parse_precedence(false), "{self}");
+            assert_eq!(pratt, self.parse_precedence(true), "{self}");
+        }
+        pratt
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input: {:?}", self.input)?;
+        writeln!(f, "start power: {}", self.start_power)?;
+        writeln!(f, "prefix: {:?}", self.prefix)?;
+        writeln!(f, "postfix: {:?}", self.postfix)?;
+        writeln!(f, "infix: {:?}", self.infix)?;
+        writeln!(f, "ternary: {:?}", self.ternary)?;
+        write!(f, "delimited: {:?}", self.delimited)
+    }
+}
+
+/// The operator table of `case`, where each fold writes out its tree.
+fn expr<'c, A: Algorithm + 'static>(
+    case: &'c Case,
+    start_power: Power,
+) -> OperatorTable<'c, &'c str, String, ContextError, A> {
+    let parens = move |i: &mut &'c str| {
+        delimited('(', expr::<A>(case, 0), ')')
+            .map(|e| format!("({e})"))
+            .parse_next(i)
+    };
+    let operand = alt((one_of(OPERANDS).map(String::from), parens));
+
+    let mut table = OperatorTable::new(operand).start_power(start_power);
+    for &(op, power) in &case.prefix {
+        table = table.prefix(op, power, move |_, a| Ok(format!("({op}{a})")));
+    }
+    for &(op, power) in &case.postfix {
+        table = table.postfix(op, power, move |_, a| Ok(format!("({a}{op})")));
+    }
+    for &(op, assoc) in &case.infix {
+        table = table.infix(op, assoc, move |_, a, b| Ok(format!("({a} {op} {b})")));
+    }
+    if let Some(assoc) = case.ternary {
+        table = table.ternary('?', ':', assoc, |_, a, b, c| {
+            Ok(format!("({a} ? {b} : {c})"))
+        });
+    }
+    if let Some(power) = case.delimited {
+        table = table.delimited_postfix('[', ']', power, |_, a, b| {
+            Ok(format!("{a}[{}]", b.unwrap_or_default()))
+        });
+    }
+    table
+}
+
+/// The operators of `case` as separate parsers, which are passed to the
+/// `precedence` function of `pratt::precedence` or of `pratt::shunting_yard`.
+fn precedence_expr(
+    case: &Case,
+    start_power: Power,
+    shunting_yard: bool,
+    i: &mut &str,
+) -> Result<String, ContextError> {
+    let parens = |i: &mut &str| {
+        delimited(
+            '(',
+            |i: &mut _| precedence_expr(case, 0, shunting_yard, i),
+            ')',
+        )
+        .map(|e| format!("({e})"))
+        .parse_next(i)
+    };
+    let operand = alt((one_of(OPERANDS).map(String::from), parens));
+    let prefix = |i: &mut &str| {
+        let c = any.parse_next(i)?;
+        let &(op, power) = case
+            .prefix
+            .iter()
+            .find(|(op, _)| *op == c)
+            .ok_or_else(|| ContextError::from_input(i))?;
+        Ok((power, move |_: &mut _, a| Ok(format!("({op}{a})"))))
+    };
+    let postfix = |i: &mut &str| {
+        let c = any.parse_next(i)?;
+        let &(op, power) = case
+            .postfix
+            .iter()
+            .find(|(op, _)| *op == c)
+            .ok_or_else(|| ContextError::from_input(i))?;
+        Ok((power, move |_: &mut _, a| Ok(format!("({a}{op})"))))
+    };
+    let infix = |i: &mut &str| {
+        let c = any.parse_next(i)?;
+        let &(op, assoc) = case
+            .infix
+            .iter()
+            .find(|(op, _)| *op == c)
+            .ok_or_else(|| ContextError::from_input(i))?;
+        Ok((assoc, move |_: &mut _, a, b| Ok(format!("({a} {op} {b})"))))
+    };
+
+    if shunting_yard {
+        pratt::shunting_yard::precedence(start_power, operand, prefix, postfix, infix).parse_next(i)
+    } else {
+        pratt::precedence::precedence(start_power, operand, prefix, postfix, infix).parse_next(i)
+    }
+}
@@ -0,0 +1,57 @@
+//! The recursive parser without `std` or `alloc`, like on a microcontroller.
+//!
+//! This only proves anything without the default features, so it only runs
+//! without `alloc`, with `cargo test-pratt-no-std`. See `.cargo/config.toml`.
+
+#![cfg(not(feature = "alloc"))]
+#![no_std]
+
+use pratt::precedence::{Assoc, BinaryFold, Depth, Power, UnaryFold};
+use winnow::ascii::digit1;
+use winnow::combinator::{delimited, dispatch, empty, fail, peek};
+use winnow::error::ContextError;
+use winnow::token::any;
+use winnow::{Parser, Result};
+
+/// Rolls are summed, so `3d6` is the highest total of three six-sided dice.
+fn expr<'i>(depth: &Depth) -> impl Parser<&'i str, u32, ContextError> {
+    move |i: &mut &'i str| {
+        depth
+            .precedence(
+                0,
+                dispatch! {peek(any);
+                    '(' => depth.nest(delimited('(', expr(depth), ')')),
+                    _ => digit1.parse_to::<u32>()
+                },
+                fail::<_, (Power, UnaryFold<_, _, _>), _>,
+                fail::<_, (Power, UnaryFold<_, _, _>), _>,
+                dispatch! {any;
+                    '+' => empty.value((Assoc::Left(5), (|_: &mut _, a, b| Ok(a + b)) as BinaryFold<_, _, _>)),
+                    '*' => empty.value((Assoc::Left(7), (|_: &mut _, a, b| Ok(a * b)) as BinaryFold<_, _, _>)),
+                    'd' => empty.value((Assoc::Left(9), (|_: &mut _, a, b| Ok(a * b)) as BinaryFold<_, _, _>)),
+                    _ => fail
+                },
+            )
+            .parse_next(i)
+    }
+}
+
+fn parse(max: usize, input: &str) -> Result<u32, ()> {
+    let depth = Depth::new(max);
+    expr(&depth).parse(input).map_err(|_| ())
+}
+
+#[test]
+fn test_no_std() {
+    assert_eq!(parse(16, "3d6"), Ok(18));
+    assert_eq!(parse(16, "2*(1d20+4)"), Ok(48));
+    assert_eq!(parse(16, "((((1d4))))"), Ok(4));
+}
+
+#[test]
+fn test_no_std_depth() {
+    // without the heap, the input is a fixed buffer of open parentheses
+    let input = [b'('; 4096];
+    let input = core::str::from_utf8(&input).unwrap();
+    assert_eq!(parse(64, input), Err(()));
+}
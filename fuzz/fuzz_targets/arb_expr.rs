@@ -16,8 +16,9 @@ fuzz_target!(|data: Expr| {
     let depth = data.collapse_frames(|frame| match frame {
         ExprFrame::Int(_) => 1,
         ExprFrame::Dice(_, _) => 1,
+        ExprFrame::CritDice(_, _, _) => 1,
         ExprFrame::Not(x) => x + 1,
-        ExprFrame::Label(x, _) => x + 1,
+        ExprFrame::Label(x, _) | ExprFrame::List(x, _) => x + 1,
         ExprFrame::Add(x, y) => x + y,
         ExprFrame::Sub(x, y) => x + y,
         ExprFrame::Mul(x, y) => x + y,
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../crates/pratt/tests/differential_helpers/mod.rs"]
+mod differential_helpers;
+
+use differential_helpers::Case;
+
+// the Pratt parser and the Shunting Yard one parse the same trees from a
+// random operator table, or fail at the same offset with the same error
+fuzz_target!(|data: &[u8]| {
+    let _ = Case::from_bytes(data).check();
+});
@@ -30,7 +30,7 @@ fuzz_target!(|data: &[u8]| {
                     tree_reparse.to_string(),
                     "reparsed tree has different fmt str: `{}` != `{}`",
                     tree_str,
-                    tree_reparse.to_string(),
+                    tree_reparse,
                 );
 
                 let mut dr = DiceRoller::new(MockCryptoRng::new(&[1]));
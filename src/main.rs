@@ -1,39 +1,204 @@
-use std::io::{self, BufRead as _};
+use std::{
+    io::{self, BufRead as _},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use dice_parser::parser::{DiceParser, Parser as _, Rule, parse_expr, try_parse_to_ast};
+use dice_parser::{
+    eval::{
+        query::{Answer, Method},
+        simulate::Simulation,
+    },
+    limits::Limits,
+    parser::{ParseError, parse_query_with_limits, parse_with_limits},
+    systems::{Registry, SystemPool},
+};
+use serde_json::json;
 
 fn main() {
+    // `--json` prints one JSON object per line, with a subtotal for each label
+    let json = std::env::args().skip(1).any(|arg| arg == "--json");
+
+    // `rollers prob "1d20+7 >= 16"` answers a single query instead of reading stdin
+    let mut args = std::env::args().skip(1).filter(|arg| arg != "--json");
+    if args.next().as_deref() == Some("prob") {
+        let Some(input) = args.next() else {
+            eprintln!("Usage: rollers prob [--json] \"<expr> <op> <expr>\"");
+            std::process::exit(2);
+        };
+        if !prob(&input, json) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let stdin = io::stdin();
     let mut handle = stdin.lock();
     let mut buffer = String::new();
 
-    let mut dr = dice_parser::eval::DiceRoller::default();
+    // input comes from anyone, so cap how much work a single line can cause
+    let limits = Limits::default();
+    let mut dr = dice_parser::eval::DiceRoller::default().with_limits(limits);
+    // lines like `blades 3` are read by a game system instead
+    let systems = Registry::default();
     loop {
         buffer.clear();
-        if let Err(why) = handle.read_line(&mut buffer) {
-            eprintln!("Couldn't read line: {}", why);
-            continue;
+        match handle.read_line(&mut buffer) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(why) => {
+                eprintln!("Couldn't read line: {}", why);
+                continue;
+            }
         }
 
-        match DiceParser::parse(Rule::equation, &buffer) {
-            Ok(mut pairs) => {
-                let r = try_parse_to_ast(pairs.next().unwrap().into_inner());
-
-                if let Ok(r) = r {
-                    println!("Parsed: {:?}", r);
-                    println!("Normalized: {}", r);
+        if let Some(pool) = systems.parse(&buffer, &limits) {
+            roll_system(pool, json);
+            continue;
+        }
 
-                    dr.try_eval(&r).map_or_else(
-                        |err| eprintln!("Eval failed: {:?}", err),
-                        |res| println!("Eval: {}", res),
-                    );
+        let r = match parse_with_limits(&buffer, &limits) {
+            Ok(r) => r,
+            Err(why) => {
+                if json {
+                    println!("{}", json!({ "error": why.to_string() }));
                 } else {
-                    eprintln!("{}", r.unwrap_err())
+                    eprintln!("Parse failed: {}", why);
                 }
+                continue;
             }
-            Err(why) => {
-                eprintln!("Parse failed: {:#?}", why);
+        };
+
+        if json {
+            let output = match dr.try_eval_labeled(&r) {
+                Ok(breakdown) => json!({
+                    "expr": r.to_string(),
+                    "total": breakdown.total,
+                    "unlabeled": breakdown.unlabeled,
+                    "labels": breakdown.labels,
+                }),
+                Err(err) => json!({ "expr": r.to_string(), "error": err.to_string() }),
+            };
+            println!("{}", output);
+            continue;
+        }
+
+        println!("Parsed: {:?}", r);
+        println!("Normalized: {}", r);
+
+        dr.try_eval(&r).map_or_else(
+            |err| eprintln!("Eval failed: {}", err),
+            |res| println!("Eval: {}", res),
+        );
+    }
+}
+
+/// Rolls the pool of a game system, and prints the band it fell in.
+fn roll_system(pool: Result<SystemPool<'_, '_>, ParseError>, json: bool) {
+    let (system, pool) = match pool {
+        Ok(pool) => pool,
+        Err(why) => {
+            if json {
+                println!("{}", json!({ "error": why.to_string() }));
+            } else {
+                eprintln!("Parse failed: {}", why);
             }
+            return;
+        }
+    };
+
+    match pool.roll(&mut rand::rng()) {
+        Ok(reading) if json => println!(
+            "{}",
+            json!({ "expr": pool.to_string(), "system": system.name(), "reading": reading })
+        ),
+        Ok(reading) => println!(
+            "{}: {} ({}){} {:?}",
+            system.name(),
+            reading.band,
+            reading.value,
+            reading
+                .detail
+                .map_or_else(String::new, |detail| format!(", {}", detail)),
+            reading.faces
+        ),
+        Err(err) if json => {
+            println!(
+                "{}",
+                json!({ "expr": pool.to_string(), "error": err.to_string() })
+            )
         }
+        Err(err) => eprintln!("Eval failed: {}", err),
+    }
+}
+
+/// Prints how likely the query in `input` is, and returns `false` if it failed.
+fn prob(input: &str, json: bool) -> bool {
+    let limits = Limits::default();
+    let query = match parse_query_with_limits(input, &limits) {
+        Ok(query) => query,
+        Err(why) => {
+            if json {
+                println!("{}", json!({ "error": why.to_string() }));
+            } else {
+                eprintln!("Parse failed: {}", why);
+            }
+            return false;
+        }
+    };
+
+    // only used when there are too many outcomes to answer exactly
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    let sim = Simulation::new(100_000, seed).with_limits(limits);
+
+    let answer = match query.answer(&sim, 0.999) {
+        Ok(answer) => answer,
+        Err(err) => {
+            if json {
+                println!(
+                    "{}",
+                    json!({ "query": query.to_string(), "error": err.to_string() })
+                );
+            } else {
+                eprintln!("Eval failed: {}", err);
+            }
+            return false;
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            json!({ "query": query.to_string(), "answer": answer })
+        );
+        return true;
+    }
+
+    let Answer {
+        probability,
+        error,
+        method,
+    } = answer;
+    match method {
+        Method::Exact {
+            numerator,
+            denominator,
+        } => println!(
+            "P({}) = {}% (exactly {}/{})",
+            query,
+            probability * 100.0,
+            numerator,
+            denominator
+        ),
+        Method::Simulated { trials, confidence } => println!(
+            "P({}) ≈ {:.2}% ± {:.2}% ({} trials, {}% confidence)",
+            query,
+            probability * 100.0,
+            error * 100.0,
+            trials,
+            confidence * 100.0
+        ),
     }
+    true
 }